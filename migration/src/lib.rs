@@ -15,6 +15,21 @@ mod m20240608_160157_submissions;
 mod m20240609_093230_problem_tasks;
 mod m20240612_192624_alter_submissions_add_code;
 mod m20240613_001709_alter_submissions_add_tasks;
+mod m20240615_083012_alter_problem_tasks_add_cases;
+mod m20240618_142203_problem_validators;
+mod m20240620_101530_problem_solutions;
+mod m20240620_103245_alter_problems_add_verification;
+mod m20240622_091207_alter_problem_solutions_add_is_main;
+mod m20240624_153012_test_case_uploads;
+mod m20240626_110530_test_case_versions;
+mod m20240628_094512_alter_problems_add_deleted_at;
+mod m20240701_101245_tags;
+mod m20240703_083015_course_problems_and_members;
+mod m20240705_102318_problem_attachments;
+mod m20240708_091542_problem_translations;
+mod m20240710_153026_problem_revisions;
+mod m20240712_141208_problem_collaborators;
+mod m20240715_103742_problem_templates;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -33,6 +48,21 @@ impl MigratorTrait for Migrator {
             Box::new(m20240609_093230_problem_tasks::Migration),
             Box::new(m20240612_192624_alter_submissions_add_code::Migration),
             Box::new(m20240613_001709_alter_submissions_add_tasks::Migration),
+            Box::new(m20240615_083012_alter_problem_tasks_add_cases::Migration),
+            Box::new(m20240618_142203_problem_validators::Migration),
+            Box::new(m20240620_101530_problem_solutions::Migration),
+            Box::new(m20240620_103245_alter_problems_add_verification::Migration),
+            Box::new(m20240622_091207_alter_problem_solutions_add_is_main::Migration),
+            Box::new(m20240624_153012_test_case_uploads::Migration),
+            Box::new(m20240626_110530_test_case_versions::Migration),
+            Box::new(m20240628_094512_alter_problems_add_deleted_at::Migration),
+            Box::new(m20240701_101245_tags::Migration),
+            Box::new(m20240703_083015_course_problems_and_members::Migration),
+            Box::new(m20240705_102318_problem_attachments::Migration),
+            Box::new(m20240708_091542_problem_translations::Migration),
+            Box::new(m20240710_153026_problem_revisions::Migration),
+            Box::new(m20240712_141208_problem_collaborators::Migration),
+            Box::new(m20240715_103742_problem_templates::Migration),
         ]
     }
 }
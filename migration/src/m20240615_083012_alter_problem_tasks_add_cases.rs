@@ -0,0 +1,39 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum ProblemTasks {
+    Table,
+    Cases,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProblemTasks::Table)
+                    .add_column_if_not_exists(json_null(ProblemTasks::Cases))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProblemTasks::Table)
+                    .drop_column(ProblemTasks::Cases)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
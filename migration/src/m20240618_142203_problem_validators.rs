@@ -0,0 +1,62 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::*,
+    sea_orm::{EnumIter, Iterable},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(ProblemValidators::Table)
+                    .col(pk_auto(ProblemValidators::Id))
+                    .col(integer_uniq(ProblemValidators::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-validator-problem")
+                            .from(ProblemValidators::Table, ProblemValidators::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(
+                        ColumnDef::new(ProblemValidators::Language)
+                            .enumeration(Alias::new("language"), SubmissionLanguage::iter())
+                            .not_null(),
+                    )
+                    .col(text(ProblemValidators::Code))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProblemValidators::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemValidators {
+    Table,
+    Id,
+    ProblemId,
+    Language,
+    Code,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden, EnumIter)]
+enum SubmissionLanguage {
+    C,
+    Cpp,
+    Python,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::*,
+    sea_orm::{EnumIter, Iterable},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(ProblemSolutions::Table)
+                    .col(pk_auto(ProblemSolutions::Id))
+                    .col(integer(ProblemSolutions::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-solution-problem")
+                            .from(ProblemSolutions::Table, ProblemSolutions::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(string(ProblemSolutions::Name))
+                    .col(
+                        ColumnDef::new(ProblemSolutions::Language)
+                            .enumeration(Alias::new("language"), SubmissionLanguage::iter())
+                            .not_null(),
+                    )
+                    .col(text(ProblemSolutions::Code))
+                    .col(string(ProblemSolutions::ExpectedStatus))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProblemSolutions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemSolutions {
+    Table,
+    Id,
+    ProblemId,
+    Name,
+    Language,
+    Code,
+    ExpectedStatus,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden, EnumIter)]
+enum SubmissionLanguage {
+    C,
+    Cpp,
+    Python,
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum ProblemSolutions {
+    Table,
+    IsMain,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProblemSolutions::Table)
+                    .add_column_if_not_exists(boolean(ProblemSolutions::IsMain).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProblemSolutions::Table)
+                    .drop_column(ProblemSolutions::IsMain)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
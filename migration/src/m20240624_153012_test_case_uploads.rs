@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(TestCaseUploads::Table)
+                    .col(pk_auto(TestCaseUploads::Id))
+                    .col(uuid_uniq(TestCaseUploads::Pid))
+                    .col(integer(TestCaseUploads::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-test-case-upload-problem")
+                            .from(TestCaseUploads::Table, TestCaseUploads::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(integer(TestCaseUploads::UserId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-test-case-upload-user")
+                            .from(TestCaseUploads::Table, TestCaseUploads::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(big_integer(TestCaseUploads::Size))
+                    .col(big_integer(TestCaseUploads::Received).default(0))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TestCaseUploads::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TestCaseUploads {
+    Table,
+    Id,
+    Pid,
+    ProblemId,
+    UserId,
+    Size,
+    Received,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
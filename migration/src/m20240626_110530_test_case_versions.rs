@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(TestCaseVersions::Table)
+                    .col(pk_auto(TestCaseVersions::Id))
+                    .col(integer(TestCaseVersions::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-test-case-version-problem")
+                            .from(TestCaseVersions::Table, TestCaseVersions::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(string_uniq(TestCaseVersions::TestCaseId))
+                    .col(integer(TestCaseVersions::UploaderId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-test-case-version-uploader")
+                            .from(TestCaseVersions::Table, TestCaseVersions::UploaderId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(json_null(TestCaseVersions::Tasks))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TestCaseVersions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TestCaseVersions {
+    Table,
+    Id,
+    ProblemId,
+    TestCaseId,
+    UploaderId,
+    Tasks,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(Tags::Table)
+                    .col(pk_auto(Tags::Id))
+                    .col(string_len_uniq(Tags::Name, 1 << 6))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProblemTags::Table)
+                    .col(integer(ProblemTags::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-tag-problem")
+                            .from(ProblemTags::Table, ProblemTags::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(integer(ProblemTags::TagId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-tag-tag")
+                            .from(ProblemTags::Table, ProblemTags::TagId)
+                            .to(Tags::Table, Tags::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ProblemTags::ProblemId)
+                            .col(ProblemTags::TagId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProblemTags::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Tags::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tags {
+    Table,
+    Id,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum ProblemTags {
+    Table,
+    ProblemId,
+    TagId,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
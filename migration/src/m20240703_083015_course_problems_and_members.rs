@@ -0,0 +1,105 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CourseProblems::Table)
+                    .col(integer(CourseProblems::CourseId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-course-problem-course")
+                            .from(CourseProblems::Table, CourseProblems::CourseId)
+                            .to(Courses::Table, Courses::Id),
+                    )
+                    .col(integer(CourseProblems::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-course-problem-problem")
+                            .from(CourseProblems::Table, CourseProblems::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(CourseProblems::CourseId)
+                            .col(CourseProblems::ProblemId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(CourseMembers::Table)
+                    .col(integer(CourseMembers::CourseId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-course-member-course")
+                            .from(CourseMembers::Table, CourseMembers::CourseId)
+                            .to(Courses::Table, Courses::Id),
+                    )
+                    .col(integer(CourseMembers::UserId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-course-member-user")
+                            .from(CourseMembers::Table, CourseMembers::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(CourseMembers::CourseId)
+                            .col(CourseMembers::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CourseMembers::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(CourseProblems::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CourseProblems {
+    Table,
+    CourseId,
+    ProblemId,
+}
+
+#[derive(DeriveIden)]
+enum CourseMembers {
+    Table,
+    CourseId,
+    UserId,
+}
+
+#[derive(DeriveIden)]
+enum Courses {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(ProblemAttachments::Table)
+                    .col(pk_auto(ProblemAttachments::Id))
+                    .col(uuid_uniq(ProblemAttachments::Pid))
+                    .col(integer(ProblemAttachments::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-attachment-problem")
+                            .from(ProblemAttachments::Table, ProblemAttachments::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(string_len(ProblemAttachments::Name, 1 << 8))
+                    .col(string(ProblemAttachments::ContentType))
+                    .col(big_integer(ProblemAttachments::Size))
+                    .col(integer(ProblemAttachments::UploaderId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-attachment-uploader")
+                            .from(ProblemAttachments::Table, ProblemAttachments::UploaderId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // attachments are referenced by name from descriptions
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-problem-attachment-name")
+                    .table(ProblemAttachments::Table)
+                    .col(ProblemAttachments::ProblemId)
+                    .col(ProblemAttachments::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProblemAttachments::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemAttachments {
+    Table,
+    Id,
+    Pid,
+    ProblemId,
+    Name,
+    ContentType,
+    Size,
+    UploaderId,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
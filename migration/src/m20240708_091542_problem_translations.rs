@@ -0,0 +1,86 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // locale of the description referred by `problems.description_id`
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Problems::Table)
+                    .add_column_if_not_exists(string_len(Problems::DefaultLocale, 16).default("en"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProblemTranslations::Table)
+                    .col(integer(ProblemTranslations::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-translation-problem")
+                            .from(ProblemTranslations::Table, ProblemTranslations::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(string_len(ProblemTranslations::Locale, 16))
+                    .col(integer_uniq(ProblemTranslations::DescriptionId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-translation-description")
+                            .from(
+                                ProblemTranslations::Table,
+                                ProblemTranslations::DescriptionId,
+                            )
+                            .to(ProblemDescriptions::Table, ProblemDescriptions::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ProblemTranslations::ProblemId)
+                            .col(ProblemTranslations::Locale),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProblemTranslations::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Problems::Table)
+                    .drop_column(Problems::DefaultLocale)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemTranslations {
+    Table,
+    ProblemId,
+    Locale,
+    DescriptionId,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+    DefaultLocale,
+}
+
+#[derive(DeriveIden)]
+enum ProblemDescriptions {
+    Table,
+    Id,
+}
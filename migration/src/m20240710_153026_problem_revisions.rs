@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(ProblemRevisions::Table)
+                    .col(pk_auto(ProblemRevisions::Id))
+                    .col(integer(ProblemRevisions::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-revision-problem")
+                            .from(ProblemRevisions::Table, ProblemRevisions::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(integer(ProblemRevisions::Number))
+                    .col(integer(ProblemRevisions::AuthorId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-revision-author")
+                            .from(ProblemRevisions::Table, ProblemRevisions::AuthorId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .col(json(ProblemRevisions::Snapshot))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-problem-revision-number")
+                    .table(ProblemRevisions::Table)
+                    .col(ProblemRevisions::ProblemId)
+                    .col(ProblemRevisions::Number)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // revision of problem a submission was judged against
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Submissions::Table)
+                    .add_column_if_not_exists(integer_null(Submissions::RevisionId))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Submissions::Table)
+                    .drop_column(Submissions::RevisionId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ProblemRevisions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemRevisions {
+    Table,
+    Id,
+    ProblemId,
+    Number,
+    AuthorId,
+    Snapshot,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Submissions {
+    Table,
+    RevisionId,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(ProblemCollaborators::Table)
+                    .col(integer(ProblemCollaborators::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-collaborator-problem")
+                            .from(ProblemCollaborators::Table, ProblemCollaborators::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(integer(ProblemCollaborators::UserId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-collaborator-user")
+                            .from(ProblemCollaborators::Table, ProblemCollaborators::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    // 0: viewer, 1: editor
+                    .col(integer(ProblemCollaborators::Role))
+                    .primary_key(
+                        Index::create()
+                            .col(ProblemCollaborators::ProblemId)
+                            .col(ProblemCollaborators::UserId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProblemCollaborators::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemCollaborators {
+    Table,
+    ProblemId,
+    UserId,
+    Role,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
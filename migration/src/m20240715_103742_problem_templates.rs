@@ -0,0 +1,75 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::*,
+    sea_orm::{EnumIter, Iterable},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                table_auto(ProblemTemplates::Table)
+                    .col(pk_auto(ProblemTemplates::Id))
+                    .col(integer(ProblemTemplates::ProblemId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-problem-template-problem")
+                            .from(ProblemTemplates::Table, ProblemTemplates::ProblemId)
+                            .to(Problems::Table, Problems::Id),
+                    )
+                    .col(
+                        ColumnDef::new(ProblemTemplates::Language)
+                            .enumeration(Alias::new("language"), SubmissionLanguage::iter())
+                            .not_null(),
+                    )
+                    .col(text(ProblemTemplates::Code))
+                    .to_owned(),
+            )
+            .await?;
+
+        // at most one template per language
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-problem-template-language")
+                    .table(ProblemTemplates::Table)
+                    .col(ProblemTemplates::ProblemId)
+                    .col(ProblemTemplates::Language)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProblemTemplates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProblemTemplates {
+    Table,
+    Id,
+    ProblemId,
+    Language,
+    Code,
+}
+
+#[derive(DeriveIden)]
+enum Problems {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden, EnumIter)]
+enum SubmissionLanguage {
+    C,
+    Cpp,
+    Python,
+}
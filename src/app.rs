@@ -18,7 +18,10 @@ use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, Statement};
 use crate::{
     controllers,
     models::_entities::{
-        courses, problem_descriptions, problem_tasks, problems, submissions, users,
+        course_members, course_problems, courses, problem_attachments, problem_collaborators,
+        problem_descriptions, problem_revisions, problem_solutions, problem_tags, problem_tasks,
+        problem_templates, problem_translations, problem_validators, problems, submissions, tags,
+        test_case_uploads, test_case_versions, users,
     },
     tasks,
     workers::downloader::DownloadWorker,
@@ -45,30 +48,46 @@ impl Hooks for App {
         create_app::<Self, Migrator>(mode, environment).await
     }
 
-    fn routes(_ctx: &AppContext) -> AppRoutes {
+    fn routes(ctx: &AppContext) -> AppRoutes {
         AppRoutes::with_default_routes()
             .prefix("/api")
-            .add_route(controllers::problems::routes())
+            .add_route(controllers::problems::routes(ctx))
             .add_route(controllers::courses::routes())
             .add_route(controllers::notes::routes())
             .add_route(controllers::auth::routes())
             .add_route(controllers::user::routes())
             .add_route(controllers::submissions::routes())
+            .add_route(controllers::tags::routes())
             .add_route(controllers::mock::routes())
     }
 
     fn connect_workers<'a>(p: &'a mut Processor, ctx: &'a AppContext) {
         p.register(crate::workers::submission::SubmissionWorker::build(ctx));
         p.register(DownloadWorker::build(ctx));
+        p.register(crate::workers::verify::VerifyWorker::build(ctx));
     }
 
     fn register_tasks(tasks: &mut Tasks) {
         tasks.register(tasks::seed::SeedData);
+        tasks.register(tasks::problem_bundle::ProblemBundle);
     }
 
     async fn truncate(db: &DatabaseConnection) -> Result<()> {
         truncate_table(db, submissions::Entity).await?;
+        truncate_table(db, test_case_uploads::Entity).await?;
+        truncate_table(db, test_case_versions::Entity).await?;
+        truncate_table(db, course_problems::Entity).await?;
+        truncate_table(db, course_members::Entity).await?;
+        truncate_table(db, problem_tags::Entity).await?;
+        truncate_table(db, tags::Entity).await?;
         truncate_table(db, problem_tasks::Entity).await?;
+        truncate_table(db, problem_validators::Entity).await?;
+        truncate_table(db, problem_solutions::Entity).await?;
+        truncate_table(db, problem_revisions::Entity).await?;
+        truncate_table(db, problem_attachments::Entity).await?;
+        truncate_table(db, problem_translations::Entity).await?;
+        truncate_table(db, problem_collaborators::Entity).await?;
+        truncate_table(db, problem_templates::Entity).await?;
         truncate_table(db, problems::Entity).await?;
         truncate_table(db, courses::Entity).await?;
         truncate_table(db, users::Entity).await?;
@@ -90,7 +109,15 @@ impl Hooks for App {
             "problems",
             "problem_descriptions",
             "problem_tasks",
+            "problem_validators",
+            "problem_solutions",
             "submissions",
+            "test_case_uploads",
+            "test_case_versions",
+            "tags",
+            "problem_revisions",
+            "problem_attachments",
+            "problem_templates",
         ];
         for table in tables {
             db.execute(Statement::from_string(
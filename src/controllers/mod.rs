@@ -4,6 +4,7 @@ pub mod mock;
 pub mod notes;
 pub mod problems;
 pub mod submissions;
+pub mod tags;
 pub mod user;
 
 /// utils
@@ -26,6 +27,29 @@ async fn verify_admin(
     Ok(user)
 }
 
+/// Ensure that current user is a teacher or an admin
+async fn verify_teacher(
+    ctx: &AppContext,
+    auth: &middleware::auth::JWT,
+) -> Result<users::Model, Result<Response>> {
+    let user = find_user_by_auth(ctx, auth).await?;
+
+    if !matches!(user.role, Role::Teacher | Role::Admin) {
+        return Err(permission_denied());
+    }
+
+    Ok(user)
+}
+
+/// Convert model error, rendering missing entities as 404 rather than loco's
+/// default 400
+fn model_error(err: ModelError) -> Error {
+    match err {
+        ModelError::EntityNotFound => Error::NotFound,
+        e => e.into(),
+    }
+}
+
 fn permission_denied() -> Result<Response> {
     format::render()
         .status(StatusCode::FORBIDDEN)
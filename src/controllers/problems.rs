@@ -1,16 +1,53 @@
 use crate::{
     models::{
-        self,
-        problems::{self, Type, Visibility},
-        transform_db_error, users,
+        self, courses,
+        problems::{
+            self, archive, attachments,
+            bundle::{self, Bundle},
+            collaborators, kattis, locales, markdown,
+            permission::{self, Permission},
+            revisions, solutions, stats, templates, validators, versions, BadTestCase, SortBy,
+            TagMatch, Type, Visibility,
+        },
+        submissions::{self, Language},
+        tags, test_case_uploads, transform_db_error, users, SortOrder,
     },
-    views::problems::{ProblemDetailResponse, ProblemListResponse},
+    sandbox,
+    settings::Settings,
+    views::{
+        problems::{
+            ProblemAttachmentListResponse, ProblemAttachmentView, ProblemCollaboratorListResponse,
+            ProblemCollaboratorView, ProblemDetailResponse, ProblemListResponse,
+            ProblemRevisionDiffResponse, ProblemRevisionListResponse, ProblemRevisionResponse,
+            ProblemSolutionListResponse, ProblemSolutionView, ProblemStatsResponse,
+            ProblemTemplateView, ProblemValidatorResponse, RenderedDescriptionResponse,
+            TestCaseVersionListResponse,
+        },
+        NojResponseBuilder,
+    },
+    workers::{
+        submission::{SubmissionWorker, SubmissionWorkerArgs},
+        verify::{VerifyWorker, VerifyWorkerArgs},
+    },
+};
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Multipart, Query},
+    http::{header, HeaderMap, StatusCode},
+    routing::patch,
+};
+use loco_rs::{
+    controller::{format::render, views::pagination::PagerMeta},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    io::{Cursor, Read, Seek},
+    time::Duration,
 };
-use axum::extract::{DefaultBodyLimit, Multipart, Query};
-use loco_rs::{controller::format::render, prelude::*};
-use serde::Deserialize;
 
-use super::{find_user_by_auth, permission_denied, verify_admin};
+use super::{find_user_by_auth, model_error, permission_denied, verify_teacher};
 
 #[derive(Debug, Deserialize)]
 pub struct CreateProblemRequest {
@@ -24,7 +61,12 @@ pub struct CreateProblemRequest {
     pub r#type: Option<Type>,
     pub allowed_language: Option<i32>,
     pub quota: Option<i32>,
+    /// Locale of `description`, e.g. `en` or `zh-tw`
+    pub locale: Option<String>,
     pub tasks: Vec<problems::tasks::AddParams>,
+    /// Names of existing tags
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 async fn create(
@@ -32,11 +74,17 @@ async fn create(
     auth: auth::JWT,
     Json(params): Json<CreateProblemRequest>,
 ) -> Result<Response> {
-    let user = match verify_admin(&ctx, &auth).await {
+    let user = match find_user_by_auth(&ctx, &auth).await {
         Ok(u) => u,
         Err(e) => return e,
     };
 
+    let tags = find_tags(&ctx, &params.tags).await?;
+    let courses = find_courses(&ctx, &params.courses).await?;
+    if !authorize_courses(&ctx, &user, &courses)? {
+        return permission_denied();
+    }
+    let default_locale = params.locale.as_deref().map(parse_locale).transpose()?;
     let params = problems::AddParams {
         owner: user,
         courses: params.courses,
@@ -46,62 +94,120 @@ async fn create(
         r#type: params.r#type,
         allowed_language: params.allowed_language,
         quota: params.quota,
+        default_locale,
         tasks: params.tasks,
     };
 
     let problem = problems::Model::add(&ctx.db, &params).await?;
+    problem.set_tags(&ctx.db, &tags).await?;
 
     render().json(problem)
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ListProblemRequest {
-    pub offset: Option<usize>,
-    /// how many problems to return, -1 to return all
-    pub count: Option<i32>,
     pub name: Option<String>,
+    /// full-text search over problem name and description
+    pub search: Option<String>,
+    /// comma separated tag names
     pub tags: Option<String>,
+    /// whether problems should have all (default) or any of the tags
+    pub tag_match: Option<TagMatch>,
     pub course: Option<String>,
+    pub sort_by: Option<SortBy>,
+    pub order: Option<SortOrder>,
 }
 
 async fn list(
     State(ctx): State<AppContext>,
     auth: auth::JWT,
     params: Query<ListProblemRequest>,
+    Query(page_params): Query<model::query::PaginationQuery>,
 ) -> Result<Response> {
     let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
 
     let params = problems::ListParams {
         viewer: user,
-        offset: params.offset,
-        count: params.count,
+        page: page_params.page,
+        page_size: page_params.page_size,
+        sort_by: params.sort_by.unwrap_or_default(),
+        order: params.order.unwrap_or_default(),
         name: params.name.clone(),
+        search: params.search.clone(),
         tags: params
             .tags
             .as_ref()
             .map(|t| t.split(',').map(str::to_string).collect()),
+        tag_match: params.tag_match.unwrap_or_default(),
         course: params.course.clone(),
     };
 
-    let problems = problems::Model::list(&ctx.db, &params).await?;
+    let (problems, total) = problems::Model::list(&ctx.db, &params).await?;
+    let ids = problems.iter().map(|p| p.id).collect::<Vec<_>>();
+    let tags = tags::Model::names_by_problems(&ctx.db, &ids).await?;
+    let counts = stats::counts_by_problems(&ctx.db, &ids).await?;
+    let pagination = PagerMeta {
+        page: page_params.page,
+        page_size: models::clamp_page_size(page_params.page_size),
+        total_pages: total.number_of_pages,
+    };
+
+    format::json(
+        ProblemListResponse::new(&problems, &tags, &counts, pagination, total.number_of_items)
+            .done(),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LocaleQuery {
+    /// preferred locale of description, takes precedence over
+    /// `Accept-Language`
+    pub lang: Option<String>,
+}
+
+fn parse_locale(locale: &str) -> Result<String> {
+    locales::normalize(locale).ok_or_else(|| Error::BadRequest(format!("invalid locale: {locale}")))
+}
+
+/// Locales requested by `lang` query and `Accept-Language` header, most
+/// preferred first
+fn preferred_locales(query: &LocaleQuery, headers: &HeaderMap) -> Vec<String> {
+    let accepted = headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|h| h.to_str().ok())
+        .map(locales::parse_accept_language)
+        .unwrap_or_default();
 
-    format::json(ProblemListResponse::new(&problems).done())
+    query
+        .lang
+        .as_deref()
+        .and_then(locales::normalize)
+        .into_iter()
+        .chain(accepted)
+        .collect()
 }
 
 async fn get_problem(
     State(ctx): State<AppContext>,
-    // auth: auth::JWT,
+    auth: auth::JWT,
     Path(problem_id): Path<i32>,
+    Query(locale_query): Query<LocaleQuery>,
+    headers: HeaderMap,
 ) -> Result<Response> {
-    // TOOD: authz
-
-    let prob = problems::Model::find_by_id(&ctx.db, problem_id).await?;
-    let desc = prob
-        .find_related(models::_entities::problem_descriptions::Entity)
-        .one(&ctx.db)
+    let user = match find_user_by_auth(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
+    let prob = problems::Model::find_active_by_id(&ctx.db, problem_id)
         .await
-        .map_err(transform_db_error)?
-        .ok_or(ModelError::EntityNotFound)?;
+        .map_err(model_error)?;
+    let permission = prob.permission(&ctx.db, &user).await?;
+    if permission == Permission::None {
+        return permission_denied();
+    }
+    let (locale, desc, locales) = prob
+        .negotiate_description(&ctx.db, &preferred_locales(&locale_query, &headers))
+        .await?;
     let owner = prob
         .find_related(models::_entities::users::Entity)
         .one(&ctx.db)
@@ -109,76 +215,1565 @@ async fn get_problem(
         .map_err(transform_db_error)?
         .ok_or(ModelError::EntityNotFound)?;
     let tasks = prob.tasks(&ctx.db).await?;
+    let templates = templates::Model::list_by_problem(&ctx.db, prob.id).await?;
+    let tags = prob.tag_names(&ctx.db).await?;
+    let courses = prob.course_names(&ctx.db).await?;
+    let counts = prob.counts(&ctx.db).await?;
 
-    format::json(ProblemDetailResponse::new(&prob, &desc, &owner, &tasks).done())
+    format::json(
+        ProblemDetailResponse::new(
+            &prob,
+            &desc,
+            (&locale, &locales),
+            &owner,
+            &tasks,
+            &templates,
+            &tags,
+            &courses,
+            &counts,
+            permission,
+        )
+        .done(),
+    )
 }
 
-async fn upload_test_case(
+async fn get_rendered_description(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Query(locale_query): Query<LocaleQuery>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let user = match find_user_by_auth(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
+    let prob = problems::Model::find_active_by_id(&ctx.db, problem_id)
+        .await
+        .map_err(model_error)?;
+    if prob.permission(&ctx.db, &user).await? == Permission::None {
+        return permission_denied();
+    }
+    let (locale, desc, locales) = prob
+        .negotiate_description(&ctx.db, &preferred_locales(&locale_query, &headers))
+        .await?;
+
+    format::json(RenderedDescriptionResponse::new(&prob, &desc, (&locale, &locales)).done())
+}
+
+/// Create or replace the description in a locale
+async fn set_description(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, locale)): Path<(i32, String)>,
+    Json(params): Json<problems::descriptions::AddParams>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
+    let locale = parse_locale(&locale)?;
+    // the change and its revision are kept or lost together
+    let txn = ctx.db.begin().await?;
+    prob.set_description(&txn, &locale, &params).await?;
+    revisions::Model::record(&txn, &prob, user.id).await?;
+    txn.commit().await.map_err(transform_db_error)?;
+
+    format::json(NojResponseBuilder::new(json!({ "locales": prob.locales(&ctx.db).await? })).done())
+}
+
+async fn remove_description(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, locale)): Path<(i32, String)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let locale = parse_locale(&locale)?;
+    if locale == prob.default_locale {
+        return render()
+            .status(StatusCode::BAD_REQUEST)
+            .json(json!({"msg": "default description can't be removed"}));
+    }
+    let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
+    let txn = ctx.db.begin().await?;
+    prob.remove_description(&txn, &locale).await?;
+    revisions::Model::record(&txn, &prob, user.id).await?;
+    txn.commit().await.map_err(transform_db_error)?;
+
+    format::empty_json()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenderMarkdownRequest {
+    pub markdown: String,
+    /// problem whose attachments are referred by `attachment:` links
+    pub problem_id: Option<i32>,
+}
+
+/// Preview Markdown as it would be rendered in problem descriptions
+async fn render_markdown(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Json(params): Json<RenderMarkdownRequest>,
+) -> Result<Response> {
+    if let Err(e) = find_user_by_auth(&ctx, &auth).await {
+        return e;
+    }
+
+    let html = match params.problem_id {
+        Some(problem_id) => markdown::render_problem(&params.markdown, problem_id),
+        None => markdown::render(&params.markdown),
+    };
+
+    format::json(NojResponseBuilder::new(json!({ "html": html })).done())
+}
+
+async fn get_stats(
     State(ctx): State<AppContext>,
     auth: auth::JWT,
     Path(problem_id): Path<i32>,
-    mut multipart: Multipart,
 ) -> Result<Response> {
     let user = match find_user_by_auth(&ctx, &auth).await {
         Ok(u) => u,
         Err(e) => return e,
     };
-    let prob = problems::Model::find_by_id(&ctx.db, problem_id).await?;
-    if user.id != prob.owner_id {
+    let prob = problems::Model::find_active_by_id(&ctx.db, problem_id)
+        .await
+        .map_err(model_error)?;
+    if prob.permission(&ctx.db, &user).await? == Permission::None {
         return permission_denied();
     }
+    let stats = prob.stats(&ctx.db).await?;
+
+    format::json(ProblemStatsResponse::new(&stats).done())
+}
+
+/// Edit problem fields, both PUT and PATCH only change given fields
+async fn update_problem(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Json(params): Json<problems::UpdateParams>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
+    if let Some(names) = &params.courses {
+        let courses = find_courses(&ctx, names).await?;
+        if !authorize_courses(&ctx, &user, &courses)? {
+            return permission_denied();
+        }
+    }
+
+    let had_test_case = prob.test_case_id.is_some();
+    let txn = ctx.db.begin().await?;
+    let prob = prob.update(&txn, &params).await?;
+    // checked on the updated problem, changing tasks may drop its test case;
+    // the update is rolled back with txn on rejection
+    if prob.status == Visibility::Show as i32 && !prob.is_verified(&txn).await? {
+        return render()
+            .status(StatusCode::BAD_REQUEST)
+            .json(json!({"msg": "problem is not verified"}));
+    }
+    revisions::Model::record(&txn, &prob, user.id).await?;
+    txn.commit().await.map_err(transform_db_error)?;
+    if had_test_case && prob.test_case_id.is_none() {
+        tracing::info!(
+            problem_id = prob.id,
+            "test case dropped because problem tasks changed"
+        );
+    }
+
+    format::empty_json()
+}
+
+/// Soft delete a problem, keeping its submissions. Only its owner or admins can
+/// do so.
+async fn delete_problem(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_owned_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    prob.into_active_model().soft_delete(&ctx.db).await?;
+
+    format::empty_json()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTagsRequest {
+    /// Names of existing tags
+    pub tags: Vec<String>,
+}
+
+async fn set_tags(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Json(params): Json<SetTagsRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let tags = find_tags(&ctx, &params.tags).await?;
+    prob.set_tags(&ctx.db, &tags).await?;
+
+    format::empty_json()
+}
+
+/// Find tags by names, all of them must exist
+async fn find_tags(ctx: &AppContext, names: &[String]) -> Result<Vec<tags::Model>> {
+    if names.is_empty() {
+        return Ok(vec![]);
+    }
+    let tags = tags::Model::find_by_names(&ctx.db, names).await?;
+    let unknown = names
+        .iter()
+        .filter(|n| !tags.iter().any(|t| t.name == **n))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(Error::BadRequest(format!(
+            "unknown tag(s): {}",
+            unknown.join(", ")
+        )));
+    }
+
+    Ok(tags)
+}
+
+/// Find courses by names, all of them must exist
+async fn find_courses(ctx: &AppContext, names: &[String]) -> Result<Vec<courses::Model>> {
+    let courses = courses::Model::find_by_names(&ctx.db, names).await?;
+    let unknown = names
+        .iter()
+        .filter(|n| !courses.iter().any(|c| c.name == **n))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(Error::BadRequest(format!(
+            "unknown course(s): {}",
+            unknown.join(", ")
+        )));
+    }
+
+    Ok(courses)
+}
+
+/// Whether `user` can place a problem in `courses`, see `problem.create` in
+/// settings
+fn authorize_courses(
+    ctx: &AppContext,
+    user: &users::Model,
+    courses: &[courses::Model],
+) -> Result<bool> {
+    let policy = Settings::from_ctx(ctx)?.problem.create.for_role(&user.role);
+
+    Ok(permission::can_create(policy, user, courses))
+}
+
+async fn upload_test_case(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    mut multipart: Multipart,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
+
+    let file_content = read_archive_field(&mut multipart, "test case").await?;
+    save_test_case(&ctx, prob, user.id, Cursor::new(file_content)).await
+}
 
-    let file_content = loop {
+/// Read the first file field of multipart form, which should be an archive
+/// named `what` in error messages
+async fn read_archive_field(multipart: &mut Multipart, what: &str) -> Result<Bytes> {
+    loop {
         let Some(field) = multipart.next_field().await.map_err(|err| {
             tracing::error!(error = ?err,"could not read multipart");
             Error::BadRequest("could not read multipart".into())
         })?
         else {
-            return Err(Error::BadRequest("cloud not find test case file".into()));
+            return Err(Error::BadRequest(format!(
+                "cloud not find {what} file, accepted formats: {}",
+                archive::Format::ACCEPTED
+            )));
         };
 
-        if !matches!(field.content_type(), Some("application/x-zip")) {
+        // format is sniffed from content, content type is unreliable
+        if field.file_name().is_none() {
             continue;
         }
 
-        break field.bytes().await.map_err(|err| {
+        return field.bytes().await.map_err(|err| {
             tracing::error!(error = ?err,"could not read bytes");
             Error::BadRequest("could not read bytes".into())
-        })?;
+        });
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportProblemQuery {
+    /// comma separated names of courses to place the problem in
+    pub courses: Option<String>,
+}
+
+/// Create a hidden problem from a Kattis problem package
+async fn import_problem(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Query(params): Query<ImportProblemQuery>,
+    mut multipart: Multipart,
+) -> Result<Response> {
+    let user = match verify_teacher(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
+    let course_names = params
+        .courses
+        .as_ref()
+        .map(|c| c.split(',').map(str::to_string).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let courses = find_courses(&ctx, &course_names).await?;
+    if !authorize_courses(&ctx, &user, &courses)? {
+        return permission_denied();
+    }
+
+    let file_content = read_archive_field(&mut multipart, "problem package").await?;
+    let limits = Settings::from_ctx(&ctx)?.archive;
+    let package = match kattis::import(&mut Cursor::new(file_content), &limits) {
+        Ok(p) => p,
+        Err(e) => return bad_test_case(Error::Any(Box::new(problems::Error::BadTestCase(e)))),
     };
 
-    prob.validate_test_case(&ctx.db, &file_content).await?;
-    tracing::info!(problem_id = prob.id, "test case validated");
+    let uploader_id = user.id;
+    let params = problems::AddParams {
+        owner: user,
+        courses: course_names,
+        name: package.name,
+        status: Some(Visibility::Hidden),
+        description: package.description,
+        r#type: None,
+        allowed_language: None,
+        quota: None,
+        default_locale: None,
+        tasks: package.tasks,
+    };
+    // don't leave a problem without test case behind
+    let txn = ctx.db.begin().await?;
+    let problem = problems::Model::add(&txn, &params).await?;
+    let problem = match problem
+        .store_test_case(&ctx, &txn, uploader_id, Cursor::new(package.test_case))
+        .await
+    {
+        Ok(p) => p,
+        Err(e) => return bad_test_case(e),
+    };
+    if let Err(e) = txn.commit().await {
+        if let Some(path) = problem.test_case_path() {
+            if let Err(e) = ctx.storage.as_ref().delete(&path).await {
+                tracing::warn!(error = %e, "failed to remove stored test case");
+            }
+        }
+        return Err(transform_db_error(e).into());
+    }
+    tracing::info!(problem_id = problem.id, "problem imported from package");
 
-    let test_case_id = uuid::Uuid::new_v4();
-    let prob = prob
-        .into_active_model()
-        .update_test_case_id(&ctx.db, Some(test_case_id.to_string()))
-        .await?;
+    render().json(problem)
+}
 
-    // because we just set its test case id, it's safe to unwrap()
-    let path = prob.test_case_path().unwrap();
-    ctx.storage
-        .as_ref()
-        .upload(path.as_path(), &file_content)
-        .await?;
-    tracing::info!(test_case_id = ?test_case_id, "test case uploaded");
+/// Create a hidden problem from a bundle made by [`export_bundle`], possibly on
+/// another instance
+async fn import_bundle(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    mut multipart: Multipart,
+) -> Result<Response> {
+    let user = match verify_teacher(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
 
-    format::empty_json()
+    let file_content = read_archive_field(&mut multipart, "problem bundle").await?;
+    let (bundle, files) = match Bundle::read(Cursor::new(file_content)) {
+        Ok(b) => b,
+        Err(e) => return bad_test_case(Error::Any(Box::new(problems::Error::BadTestCase(e)))),
+    };
+    let courses = bundle.find_courses(&ctx.db).await?;
+    if !authorize_courses(&ctx, &user, &courses)? {
+        return permission_denied();
+    }
+    match bundle::import(&ctx, user, &bundle, files).await {
+        Ok(problem) => render().json(problem),
+        Err(e) => bad_test_case(e),
+    }
 }
 
-pub fn routes() -> Routes {
-    Routes::new()
-        .prefix("problems")
-        .add("/", post(create))
-        .add("/manage", post(create))
-        .add("/", get(list))
-        .add("/:problem_id", get(get_problem))
-        .add("/view/:problem_id", get(get_problem))
-        .add("/manage/:problem_id", put(upload_test_case))
-        .add(
-            "/:problem_id",
-            // change body limit to 128 MB
-            put(upload_test_case).layer(DefaultBodyLimit::max(128 * 1024 * 1024)),
+/// Download a lossless bundle of problem, see [`bundle::Bundle`]
+async fn export_bundle(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let content = bundle::export(&ctx, &prob).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"problem-{}.bundle.zip\"", prob.id),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+/// Download problem as a Kattis problem package
+async fn export_problem(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let desc = prob
+        .find_related(models::_entities::problem_descriptions::Entity)
+        .one(&ctx.db)
+        .await
+        .map_err(transform_db_error)?
+        .ok_or(ModelError::EntityNotFound)?;
+    let tasks = prob.tasks(&ctx.db).await?;
+    let mut test_case = match prob.test_case_path() {
+        Some(path) => {
+            let content: Vec<u8> = ctx.storage.download(&path).await?;
+            Some(zip::ZipArchive::new(Cursor::new(content)).map_err(|e| Error::Any(e.into()))?)
+        }
+        None => None,
+    };
+
+    let content = match kattis::export(&prob, &desc, &tasks, test_case.as_mut()) {
+        Ok(c) => c,
+        Err(e) => return bad_test_case(Error::Any(Box::new(problems::Error::BadTestCase(e)))),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"problem-{}.zip\"", prob.id),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+/// Normalize, validate and store an uploaded test case package as the current
+/// test case of problem.
+async fn save_test_case<R: Read + Seek + Send>(
+    ctx: &AppContext,
+    prob: problems::Model,
+    uploader_id: i32,
+    mut content: R,
+) -> Result<Response> {
+    let limits = Settings::from_ctx(ctx)?.archive;
+    match archive::normalize(&mut content, &limits) {
+        Ok(Some(repacked)) => store_test_case(ctx, prob, uploader_id, repacked).await,
+        Ok(None) => store_test_case(ctx, prob, uploader_id, content).await,
+        Err(e) => bad_test_case(Error::Any(Box::new(problems::Error::BadTestCase(e)))),
+    }
+}
+
+async fn store_test_case<R: Read + Seek + Send>(
+    ctx: &AppContext,
+    prob: problems::Model,
+    uploader_id: i32,
+    content: R,
+) -> Result<Response> {
+    match prob
+        .store_test_case(ctx, &ctx.db, uploader_id, content)
+        .await
+    {
+        Ok(_) => format::empty_json(),
+        Err(e) => bad_test_case(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUploadRequest {
+    /// Total bytes of the test case package
+    pub size: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadResponse {
+    pub id: String,
+    pub size: i64,
+    /// Where the next chunk should start
+    pub offset: i64,
+}
+
+impl UploadResponse {
+    fn new(upload: &test_case_uploads::Model) -> NojResponseBuilder<Self> {
+        NojResponseBuilder::new(Self {
+            id: upload.pid.to_string(),
+            size: upload.size,
+            offset: upload.received,
+        })
+    }
+}
+
+/// Start a resumable upload of a large test case. Chunks are sent by
+/// [`upload_chunk`] and the package is stored by [`finish_upload`].
+async fn create_upload(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Json(params): Json<CreateUploadRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
+    let settings = Settings::from_ctx(&ctx)?.upload;
+    if params.size <= 0 || params.size > settings.max_size {
+        return Err(Error::BadRequest(format!(
+            "test case size should be in 1..={} bytes",
+            settings.max_size
+        )));
+    }
+
+    // sessions are only cleaned up here, there's no need for a periodic job
+    test_case_uploads::Model::discard_stale(
+        &ctx.db,
+        &settings.dir,
+        Duration::from_secs(settings.expire_after),
+    )
+    .await?;
+    let upload =
+        test_case_uploads::Model::create(&ctx.db, &settings.dir, prob.id, user.id, params.size)
+            .await?;
+
+    format::json(UploadResponse::new(&upload).done())
+}
+
+async fn get_upload(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, upload_id)): Path<(i32, String)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let upload = test_case_uploads::Model::find_by_pid(&ctx.db, prob.id, &upload_id)
+        .await
+        .map_err(model_error)?;
+
+    format::json(UploadResponse::new(&upload).done())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadChunkRequest {
+    pub offset: i64,
+}
+
+/// Append a chunk (raw request body) at `offset`, which must be equal to the
+/// number of bytes received so far. On mismatch, responds 409 with the
+/// expected offset so client could resume from there.
+async fn upload_chunk(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, upload_id)): Path<(i32, String)>,
+    Query(params): Query<UploadChunkRequest>,
+    chunk: Bytes,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let upload = test_case_uploads::Model::find_by_pid(&ctx.db, prob.id, &upload_id)
+        .await
+        .map_err(model_error)?;
+    if params.offset != upload.received {
+        return render().status(StatusCode::CONFLICT).json(json!({
+            "msg": "offset mismatch",
+            "data": { "offset": upload.received },
+        }));
+    }
+    let chunk_size = i64::try_from(chunk.len()).unwrap_or(i64::MAX);
+    if chunk_size > upload.size - upload.received {
+        return Err(Error::BadRequest("chunk exceeds test case size".into()));
+    }
+
+    let settings = Settings::from_ctx(&ctx)?.upload;
+    let upload = upload.append(&ctx.db, &settings.dir, &chunk).await?;
+
+    format::json(UploadResponse::new(&upload).done())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FinishUploadRequest {
+    /// Hex encoded SHA-256 of the whole package
+    pub sha256: String,
+}
+
+async fn finish_upload(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, upload_id)): Path<(i32, String)>,
+    Json(params): Json<FinishUploadRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let upload = test_case_uploads::Model::find_by_pid(&ctx.db, prob.id, &upload_id)
+        .await
+        .map_err(model_error)?;
+    if upload.received != upload.size {
+        return Err(Error::BadRequest(format!(
+            "upload is incomplete, {} of {} bytes received",
+            upload.received, upload.size
+        )));
+    }
+
+    let dir = Settings::from_ctx(&ctx)?.upload.dir;
+    let checksum = upload.sha256(&dir).map_err(|e| Error::Any(e.into()))?;
+    if !checksum.eq_ignore_ascii_case(params.sha256.trim()) {
+        // received data is corrupted, client has to start over
+        upload.discard(&ctx.db, &dir).await?;
+        return Err(Error::BadRequest("checksum mismatch".into()));
+    }
+
+    let file = std::fs::File::open(upload.file_path(&dir)).map_err(|e| Error::Any(e.into()))?;
+    let response = save_test_case(&ctx, prob, upload.user_id, file).await;
+    upload.discard(&ctx.db, &dir).await?;
+
+    response
+}
+
+/// Render test case validation error as 400 with its details, e.g. which
+/// inputs are rejected by validator. Other errors are passed through.
+fn bad_test_case(err: Error) -> Result<Response> {
+    let body = match &err {
+        Error::Any(e) => match e.downcast_ref::<problems::Error>() {
+            Some(problems::Error::BadTestCase(bad)) => {
+                let data = match bad {
+                    BadTestCase::InvalidInput(inputs) => json!(inputs),
+                    _ => serde_json::Value::Null,
+                };
+                Some(json!({"msg": bad.to_string(), "data": data}))
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(body) = body else {
+        return Err(err);
+    };
+
+    render().status(StatusCode::BAD_REQUEST).json(body)
+}
+
+/// Find problem by id and ensure that it's owned by current user, or the user
+/// is an admin. Collaborators can't act as owner.
+async fn find_owned_problem(
+    ctx: &AppContext,
+    auth: &auth::JWT,
+    problem_id: i32,
+) -> Result<problems::Model, Result<Response>> {
+    let user = find_user_by_auth(ctx, auth).await?;
+    let prob = problems::Model::find_active_by_id(&ctx.db, problem_id)
+        .await
+        .map_err(|e| Err(model_error(e)))?;
+    if user.id != prob.owner_id && user.role != users::Role::Admin {
+        return Err(permission_denied());
+    }
+
+    Ok(prob)
+}
+
+/// Find problem by id and ensure that current user can manage it, see
+/// [`problems::Model::permission`]
+async fn find_editable_problem(
+    ctx: &AppContext,
+    auth: &auth::JWT,
+    problem_id: i32,
+) -> Result<problems::Model, Result<Response>> {
+    let user = find_user_by_auth(ctx, auth).await?;
+    let prob = problems::Model::find_active_by_id(&ctx.db, problem_id)
+        .await
+        .map_err(|e| Err(model_error(e)))?;
+    let permission = prob
+        .permission(&ctx.db, &user)
+        .await
+        .map_err(|e| Err(e.into()))?;
+    if permission != Permission::Manage {
+        return Err(permission_denied());
+    }
+
+    Ok(prob)
+}
+
+async fn get_validator(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let Some(validator) = validators::Model::find_by_problem(&ctx.db, prob.id).await? else {
+        return not_found();
+    };
+
+    format::json(ProblemValidatorResponse::new(&validator).done())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetValidatorRequest {
+    pub language: i32,
+    pub code: String,
+}
+
+async fn set_validator(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Json(params): Json<SetValidatorRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let params = validators::SetParams {
+        language: params
+            .language
+            .try_into()
+            .map_err(|_| Error::BadRequest("invalid language".into()))?,
+        code: params.code,
+    };
+
+    // reject validators which could not even be compiled
+    if let Some(stderr) = compile_error(params.language.clone(), params.code.clone()).await? {
+        return render().status(StatusCode::BAD_REQUEST).json(json!({
+            "msg": "validator could not be compiled",
+            "data": stderr,
+        }));
+    }
+
+    let validator = validators::Model::set(&ctx.db, prob.id, &params).await?;
+    tracing::info!(problem_id = prob.id, "problem validator updated");
+
+    format::json(ProblemValidatorResponse::new(&validator).done())
+}
+
+/// Compile `code` the same way submissions are compiled, returns compiler's
+/// stderr if it fails. Compiler runs on a blocking thread.
+async fn compile_error(language: Language, code: String) -> Result<Option<String>> {
+    tokio::task::spawn_blocking(move || {
+        let dir = tempfile::tempdir().map_err(|e| Error::Any(e.into()))?;
+        let compile_output =
+            sandbox::compile(dir.path(), &language, &code).map_err(|e| Error::Any(e.into()))?;
+
+        Ok((!compile_output.success).then_some(compile_output.stderr))
+    })
+    .await
+    .map_err(|e| Error::Any(e.into()))?
+}
+
+async fn remove_validator(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    validators::Model::remove(&ctx.db, prob.id).await?;
+
+    format::empty_json()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTemplateRequest {
+    pub code: String,
+}
+
+/// Set the starter template in a language, it must compile
+async fn set_template(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, language)): Path<(i32, i32)>,
+    Json(params): Json<SetTemplateRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let params = templates::SetParams {
+        language: language
+            .try_into()
+            .map_err(|_| Error::BadRequest("invalid language".into()))?,
+        code: params.code,
+    };
+
+    if let Some(stderr) = compile_error(params.language.clone(), params.code.clone()).await? {
+        return render().status(StatusCode::BAD_REQUEST).json(json!({
+            "msg": "template could not be compiled",
+            "data": stderr,
+        }));
+    }
+
+    let template = templates::Model::set(&ctx.db, prob.id, &params).await?;
+    tracing::info!(problem_id = prob.id, language, "problem template updated");
+
+    format::json(NojResponseBuilder::new(ProblemTemplateView::new(&template)).done())
+}
+
+async fn remove_template(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, language)): Path<(i32, i32)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let language = language
+        .try_into()
+        .map_err(|_| Error::BadRequest("invalid language".into()))?;
+    templates::Model::remove(&ctx.db, prob.id, &language)
+        .await
+        .map_err(model_error)?;
+
+    format::empty_json()
+}
+
+async fn list_attachments(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let user = match find_user_by_auth(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
+    let prob = problems::Model::find_active_by_id(&ctx.db, problem_id)
+        .await
+        .map_err(model_error)?;
+    if prob.permission(&ctx.db, &user).await? == Permission::None {
+        return permission_denied();
+    }
+    let attachments = attachments::Model::list_by_problem(&ctx.db, prob.id).await?;
+
+    format::json(ProblemAttachmentListResponse::new(&attachments).done())
+}
+
+/// Upload the first file field of multipart form as attachment, named after
+/// the field's file name
+async fn add_attachment(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    mut multipart: Multipart,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = match find_user_by_auth(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
+    let max_size = Settings::from_ctx(&ctx)?.attachment.max_size;
+
+    let (name, content) = loop {
+        let Some(mut field) = multipart.next_field().await.map_err(|err| {
+            tracing::error!(error = ?err,"could not read multipart");
+            Error::BadRequest("could not read multipart".into())
+        })?
+        else {
+            return Err(Error::BadRequest("could not find attachment file".into()));
+        };
+        let Some(name) = field.file_name().map(ToString::to_string) else {
+            continue;
+        };
+        // stop reading as soon as it's too large
+        let mut content = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(|err| {
+            tracing::error!(error = ?err,"could not read bytes");
+            Error::BadRequest("could not read bytes".into())
+        })? {
+            if content.len() + chunk.len() > max_size {
+                return Err(Error::BadRequest(
+                    attachments::InvalidAttachment::TooLarge(max_size).to_string(),
+                ));
+            }
+            content.extend_from_slice(&chunk);
+        }
+        break (name, Bytes::from(content));
+    };
+    let content_type = attachments::validate(&name, content.len(), max_size)
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+    if attachments::Model::find_by_name(&ctx.db, prob.id, &name)
+        .await
+        .is_ok()
+    {
+        return render()
+            .status(StatusCode::CONFLICT)
+            .json(json!({"msg": format!("attachment {name} already exists")}));
+    }
+
+    let attachment = attachments::Model::add(
+        &ctx,
+        &ctx.db,
+        prob.id,
+        user.id,
+        &name,
+        content_type,
+        content,
+    )
+    .await?;
+
+    format::json(NojResponseBuilder::new(ProblemAttachmentView::new(&attachment)).done())
+}
+
+async fn get_attachment(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, name)): Path<(i32, String)>,
+) -> Result<Response> {
+    let user = match find_user_by_auth(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
+    let prob = problems::Model::find_active_by_id(&ctx.db, problem_id)
+        .await
+        .map_err(model_error)?;
+    if prob.permission(&ctx.db, &user).await? == Permission::None {
+        return permission_denied();
+    }
+    let attachment = attachments::Model::find_by_name(&ctx.db, prob.id, &name)
+        .await
+        .map_err(model_error)?;
+    let content: Vec<u8> = ctx.storage.download(&attachment.path()).await?;
+
+    // images are shown in descriptions, others are downloaded
+    let disposition = if attachment.content_type.starts_with("image/") {
+        "inline"
+    } else {
+        "attachment"
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.content_type.clone()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("{disposition}; filename=\"{}\"", attachment.name),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+async fn remove_attachment(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, name)): Path<(i32, String)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let attachment = attachments::Model::find_by_name(&ctx.db, prob.id, &name)
+        .await
+        .map_err(model_error)?;
+    attachment.remove(&ctx).await?;
+
+    format::empty_json()
+}
+
+async fn list_collaborators(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let collaborators = prob.collaborators(&ctx.db).await?;
+
+    format::json(ProblemCollaboratorListResponse::new(&collaborators).done())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetCollaboratorRequest {
+    pub role: collaborators::Role,
+}
+
+/// Add a collaborator or change their role, only the owner and admins can
+/// do this
+async fn set_collaborator(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, username)): Path<(i32, String)>,
+    Json(params): Json<SetCollaboratorRequest>,
+) -> Result<Response> {
+    let prob = match find_owned_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_username(&ctx.db, &username)
+        .await
+        .map_err(model_error)?;
+    if user.id == prob.owner_id {
+        return render()
+            .status(StatusCode::BAD_REQUEST)
+            .json(json!({"msg": "owner can't be a collaborator of the problem"}));
+    }
+    let collaborator = prob.set_collaborator(&ctx.db, user.id, params.role).await?;
+
+    format::json(
+        NojResponseBuilder::new(ProblemCollaboratorView::new(&collaborator, Some(&user))).done(),
+    )
+}
+
+async fn remove_collaborator(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, username)): Path<(i32, String)>,
+) -> Result<Response> {
+    let prob = match find_owned_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_username(&ctx.db, &username)
+        .await
+        .map_err(model_error)?;
+    prob.remove_collaborator(&ctx.db, user.id)
+        .await
+        .map_err(model_error)?;
+    tracing::info!(
+        problem_id = prob.id,
+        user_id = user.id,
+        "collaborator removed"
+    );
+
+    format::empty_json()
+}
+
+async fn list_revisions(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let revisions = revisions::Model::list_by_problem(&ctx.db, prob.id).await?;
+
+    format::json(ProblemRevisionListResponse::new(&revisions).done())
+}
+
+async fn get_revision(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, number)): Path<(i32, i32)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let revision = revisions::Model::find_by_number(&ctx.db, prob.id, number)
+        .await
+        .map_err(model_error)?;
+
+    format::json(ProblemRevisionResponse::new(&revision).done())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevisionDiffQuery {
+    pub from: i32,
+    pub to: i32,
+}
+
+async fn diff_revisions(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Query(params): Query<RevisionDiffQuery>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let from = revisions::Model::find_by_number(&ctx.db, prob.id, params.from)
+        .await
+        .map_err(model_error)?;
+    let to = revisions::Model::find_by_number(&ctx.db, prob.id, params.to)
+        .await
+        .map_err(model_error)?;
+    let changes = revisions::diff(&from.snapshot, &to.snapshot);
+
+    format::json(ProblemRevisionDiffResponse::new(&from, &to, &changes).done())
+}
+
+/// Bring the problem back to a revision, which makes a new revision
+async fn restore_revision(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, number)): Path<(i32, i32)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
+    let revision = revisions::Model::find_by_number(&ctx.db, prob.id, number)
+        .await
+        .map_err(model_error)?;
+    let (prob, new_revision) = prob.restore_revision(&ctx.db, &revision, user.id).await?;
+    tracing::info!(
+        problem_id = prob.id,
+        from = number,
+        to = new_revision.number,
+        "problem revision restored"
+    );
+
+    format::json(ProblemRevisionResponse::new(&new_revision).done())
+}
+
+async fn list_solutions(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let solutions = solutions::Model::list_by_problem(&ctx.db, prob.id).await?;
+
+    format::json(ProblemSolutionListResponse::new(&solutions).done())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddSolutionRequest {
+    pub name: String,
+    pub language: i32,
+    pub code: String,
+    /// Expected verdict, e.g. `AC`, `TLE`, `WA`
+    pub expected_status: String,
+    /// Whether to use it to produce answers of generated test cases
+    #[serde(default)]
+    pub is_main: bool,
+}
+
+async fn add_solution(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Json(params): Json<AddSolutionRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    if !solutions::EXPECTED_STATUSES.contains(&params.expected_status.as_str()) {
+        return Err(Error::BadRequest(format!(
+            "unknown expected status: {}",
+            params.expected_status
+        )));
+    }
+    if params.is_main && params.expected_status != "AC" {
+        return Err(Error::BadRequest(
+            "main solution must be expected to get AC".into(),
+        ));
+    }
+    let params = solutions::AddParams {
+        name: params.name,
+        language: params
+            .language
+            .try_into()
+            .map_err(|_| Error::BadRequest("invalid language".into()))?,
+        code: params.code,
+        expected_status: params.expected_status,
+        is_main: params.is_main,
+    };
+
+    let solution = solutions::Model::add(&ctx.db, prob.id, &params).await?;
+    // previous verification does not cover the new solution
+    prob.into_active_model()
+        .update_verification(&ctx.db, None)
+        .await?;
+
+    format::json(NojResponseBuilder::new(ProblemSolutionView::new(&solution)).done())
+}
+
+async fn remove_solution(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, solution_id)): Path<(i32, i32)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    solutions::Model::remove(&ctx.db, prob.id, solution_id).await?;
+    prob.into_active_model()
+        .update_verification(&ctx.db, None)
+        .await?;
+
+    format::empty_json()
+}
+
+async fn verify(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    if prob.test_case_id.is_none() {
+        return Err(Error::BadRequest("test case hasn't been uploaded".into()));
+    }
+
+    if let Err(e) = VerifyWorker::perform_later(
+        &ctx,
+        VerifyWorkerArgs {
+            problem_id: prob.id,
+        },
+    )
+    .await
+    {
+        tracing::error!(err = ?e, "failed to created verify work");
+        return render().status(StatusCode::INTERNAL_SERVER_ERROR).empty();
+    }
+
+    format::empty_json()
+}
+
+async fn get_verification(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let Some(verification) = prob.verification() else {
+        return not_found();
+    };
+
+    format::json(NojResponseBuilder::new(verification).done())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateStatusRequest {
+    pub status: Visibility,
+}
+
+async fn update_status(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+    Json(params): Json<UpdateStatusRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    if params.status == Visibility::Show && !prob.is_verified(&ctx.db).await? {
+        return render()
+            .status(StatusCode::BAD_REQUEST)
+            .json(json!({"msg": "problem is not verified"}));
+    }
+    prob.into_active_model()
+        .update_status(&ctx.db, params.status)
+        .await?;
+
+    format::empty_json()
+}
+
+/// Respond a test case package as zip attachment
+async fn download_test_case(
+    ctx: &AppContext,
+    path: &std::path::Path,
+    test_case_id: &str,
+) -> Result<Response> {
+    let content: Vec<u8> = ctx.storage.download(path).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{test_case_id}.zip\""),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+async fn get_test_case(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let (Some(test_case_id), Some(path)) = (&prob.test_case_id, prob.test_case_path()) else {
+        return not_found();
+    };
+
+    download_test_case(&ctx, &path, test_case_id).await
+}
+
+async fn list_test_case_versions(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(problem_id): Path<i32>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let versions = versions::Model::list_by_problem(&ctx.db, prob.id).await?;
+
+    format::json(TestCaseVersionListResponse::new(&prob, &versions).done())
+}
+
+async fn get_test_case_version(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, test_case_id)): Path<(i32, String)>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let version = versions::Model::find(&ctx.db, prob.id, &test_case_id)
+        .await
+        .map_err(model_error)?;
+
+    download_test_case(&ctx, &version.path(), &version.test_case_id).await
+}
+
+/// Number of submissions loaded at a time when rejudging
+const REJUDGE_BATCH_SIZE: u64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackRequest {
+    /// Rejudge all submissions of the problem against the restored test case
+    #[serde(default)]
+    pub rejudge: bool,
+}
+
+async fn rollback_test_case(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((problem_id, test_case_id)): Path<(i32, String)>,
+    Json(params): Json<RollbackRequest>,
+) -> Result<Response> {
+    let prob = match find_editable_problem(&ctx, &auth, problem_id).await {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let user = users::Model::find_by_claims_key(&ctx.db, &auth.claims.pid).await?;
+    let version = versions::Model::find(&ctx.db, prob.id, &test_case_id)
+        .await
+        .map_err(model_error)?;
+    let txn = ctx.db.begin().await?;
+    let prob = prob.rollback_test_case(&txn, &version).await?;
+    let revision = revisions::Model::record(&txn, &prob, user.id).await?;
+    txn.commit().await.map_err(transform_db_error)?;
+    tracing::info!(
+        problem_id = prob.id,
+        test_case_id = %version.test_case_id,
+        "test case rolled back"
+    );
+
+    if params.rejudge {
+        // a problem may have lots of submissions, enqueue them in batches
+        let mut after = 0;
+        loop {
+            let submission_ids =
+                submissions::Model::list_judgeable_ids(&ctx.db, prob.id, after, REJUDGE_BATCH_SIZE)
+                    .await?;
+            let Some(&last_id) = submission_ids.last() else {
+                break;
+            };
+            for submission_id in submission_ids {
+                if let Err(e) = SubmissionWorker::perform_later(
+                    &ctx,
+                    SubmissionWorkerArgs {
+                        submission_id,
+                        revision_id: Some(revision.id),
+                    },
+                )
+                .await
+                {
+                    tracing::error!(err = ?e, "failed to created submission work");
+                    return render().status(StatusCode::INTERNAL_SERVER_ERROR).empty();
+                }
+            }
+            after = last_id;
+        }
+    }
+
+    format::empty_json()
+}
+
+/// Room for multipart boundary and headers around an uploaded file
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+
+pub fn routes(ctx: &AppContext) -> Routes {
+    // invalid settings are reported by handlers reading them
+    let settings = Settings::from_ctx(ctx).unwrap_or_default();
+
+    Routes::new()
+        .prefix("problems")
+        .add("/", post(create))
+        .add("/manage", post(create))
+        .add("/", get(list))
+        .add("/:problem_id", get(get_problem))
+        .add("/view/:problem_id", get(get_problem))
+        .add("/manage/:problem_id", put(upload_test_case))
+        .add("/:problem_id", put(update_problem))
+        .add("/:problem_id", patch(update_problem))
+        .add("/manage/:problem_id", patch(update_problem))
+        .add("/:problem_id", delete(delete_problem))
+        .add("/manage/:problem_id", delete(delete_problem))
+        .add("/:problem_id/tags", put(set_tags))
+        .add("/render", post(render_markdown))
+        .add(
+            "/:problem_id/description/html",
+            get(get_rendered_description),
+        )
+        .add("/:problem_id/descriptions/:locale", put(set_description))
+        .add(
+            "/:problem_id/descriptions/:locale",
+            delete(remove_description),
+        )
+        .add("/:problem_id/stats", get(get_stats))
+        .add("/:problem_id/export", get(export_problem))
+        .add("/:problem_id/bundle", get(export_bundle))
+        .add("/:problem_id/attachments", get(list_attachments))
+        .add(
+            "/:problem_id/attachments",
+            // attachment size is limited by settings, this only caps the body
+            post(add_attachment).layer(DefaultBodyLimit::max(
+                settings
+                    .attachment
+                    .max_size
+                    .saturating_add(MULTIPART_OVERHEAD),
+            )),
+        )
+        .add("/:problem_id/attachments/:name", get(get_attachment))
+        .add("/:problem_id/attachments/:name", delete(remove_attachment))
+        .add("/:problem_id/collaborators", get(list_collaborators))
+        .add(
+            "/:problem_id/collaborators/:username",
+            put(set_collaborator),
+        )
+        .add(
+            "/:problem_id/collaborators/:username",
+            delete(remove_collaborator),
+        )
+        .add("/:problem_id/revisions", get(list_revisions))
+        .add("/:problem_id/revisions/diff", get(diff_revisions))
+        .add("/:problem_id/revisions/:number", get(get_revision))
+        .add(
+            "/:problem_id/revisions/:number/restore",
+            post(restore_revision),
+        )
+        .add("/:problem_id/validator", get(get_validator))
+        .add("/:problem_id/validator", put(set_validator))
+        .add("/:problem_id/validator", delete(remove_validator))
+        .add("/:problem_id/templates/:language", put(set_template))
+        .add("/:problem_id/templates/:language", delete(remove_template))
+        .add("/:problem_id/solutions", get(list_solutions))
+        .add("/:problem_id/solutions", post(add_solution))
+        .add(
+            "/:problem_id/solutions/:solution_id",
+            delete(remove_solution),
+        )
+        .add("/:problem_id/verify", post(verify))
+        .add("/:problem_id/verification", get(get_verification))
+        .add("/:problem_id/status", put(update_status))
+        .add("/:problem_id/test-case", get(get_test_case))
+        .add(
+            "/:problem_id/test-case/versions",
+            get(list_test_case_versions),
+        )
+        .add(
+            "/:problem_id/test-case/versions/:test_case_id",
+            get(get_test_case_version),
+        )
+        .add(
+            "/:problem_id/test-case/versions/:test_case_id/rollback",
+            post(rollback_test_case),
+        )
+        .add("/:problem_id/test-case/uploads", post(create_upload))
+        .add("/:problem_id/test-case/uploads/:upload_id", get(get_upload))
+        .add(
+            "/:problem_id/test-case/uploads/:upload_id",
+            // change body limit to 32 MB per chunk
+            put(upload_chunk).layer(DefaultBodyLimit::max(32 * 1024 * 1024)),
+        )
+        .add(
+            "/:problem_id/test-case/uploads/:upload_id/finish",
+            post(finish_upload),
+        )
+        .add(
+            "/:problem_id/test-case",
+            // change body limit to 128 MB
+            put(upload_test_case).layer(DefaultBodyLimit::max(128 * 1024 * 1024)),
+        )
+        .add(
+            "/import",
+            // same as test case upload, packages mostly consist of test cases
+            post(import_problem).layer(DefaultBodyLimit::max(128 * 1024 * 1024)),
+        )
+        .add(
+            "/bundle",
+            post(import_bundle).layer(DefaultBodyLimit::max(128 * 1024 * 1024)),
         )
 }
@@ -1,16 +1,32 @@
+use std::collections::HashMap;
+
 use axum::{extract::Query, http::StatusCode};
 use chrono::offset::Utc;
 use format::render;
-use loco_rs::prelude::*;
-use serde::Deserialize;
+use loco_rs::{controller::views::pagination::PagerMeta, prelude::*};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    models::{self, _entities::problems, submissions, transform_db_error},
-    views::submission::{SubmissionDetailResponse, SubmissionListResponse},
-    workers::submission::{SubmissionWorker, SubmissionWorkerArgs},
+    models::{
+        self,
+        problems::{
+            self,
+            permission::Permission,
+            tasks as problem_tasks,
+            test_case::{self, CaseIo},
+            BadTestCase,
+        },
+        submissions::{self, SortBy},
+        transform_db_error, SortOrder,
+    },
+    views::{
+        submission::{SubmissionDetailResponse, SubmissionListResponse},
+        NojResponseBuilder,
+    },
+    workers::submission::{test_case_dir, SubmissionWorker, SubmissionWorkerArgs},
 };
 
-use super::find_user_by_auth;
+use super::{find_user_by_auth, permission_denied};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -43,22 +59,37 @@ async fn create(
 
 #[derive(Debug, Deserialize)]
 pub struct ListSubmissionRequest {
-    pub offset: Option<usize>,
-    pub count: Option<usize>,
+    /// Deprecated, use `page` instead. Rounded down to the start of the page
+    /// containing it.
+    pub offset: Option<u64>,
+    /// Deprecated, use `page_size` instead
+    pub count: Option<u64>,
     pub problem: Option<i32>,
     pub user: Option<i32>,
     pub status: Option<i32>,
     pub language: Option<i32>,
     pub course: Option<String>,
+    pub sort_by: Option<SortBy>,
+    pub order: Option<SortOrder>,
 }
 
 async fn list(
     State(ctx): State<AppContext>,
     params: Query<ListSubmissionRequest>,
+    Query(page_params): Query<model::query::PaginationQuery>,
 ) -> Result<Response> {
+    // map legacy offset & count onto pages, so old clients keep working
+    let (page, page_size) = if params.offset.is_some() || params.count.is_some() {
+        let page_size = models::clamp_page_size(params.count.unwrap_or(models::MAX_PAGE_SIZE));
+        (params.offset.unwrap_or(0) / page_size + 1, page_size)
+    } else {
+        (page_params.page, page_params.page_size)
+    };
     let params = submissions::ListParams {
-        offset: params.offset,
-        count: params.count,
+        page,
+        page_size,
+        sort_by: params.sort_by.unwrap_or_default(),
+        order: params.order.unwrap_or_default(),
         problem: params.problem,
         user: params.user,
         status: params.status.map(|s| s.try_into().unwrap()),
@@ -66,7 +97,7 @@ async fn list(
         course: params.course.clone(),
     };
 
-    let submissions = submissions::Model::list(&ctx.db, &params).await?;
+    let (submissions, total) = submissions::Model::list(&ctx.db, &params).await?;
     let mut users = vec![];
 
     for s in &submissions {
@@ -79,7 +110,15 @@ async fn list(
         users.push(u);
     }
 
-    format::json(SubmissionListResponse::new(&submissions, &users).done())
+    let pagination = PagerMeta {
+        page,
+        page_size: models::clamp_page_size(page_size),
+        total_pages: total.number_of_pages,
+    };
+
+    format::json(
+        SubmissionListResponse::new(&submissions, &users, pagination, total.number_of_items).done(),
+    )
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,11 +136,15 @@ async fn upload_code(
         .into_active_model()
         .update_code(&ctx.db, params.code)
         .await?;
+    // judged on the problem as it is now, even if it's edited before the work
+    // is picked up
+    let revision = problems::revisions::Model::latest(&ctx.db, submission.problem_id).await?;
 
     if let Err(e) = SubmissionWorker::perform_later(
         &ctx,
         SubmissionWorkerArgs {
             submission_id: submission.id,
+            revision_id: revision.map(|r| r.id),
         },
     )
     .await
@@ -133,8 +176,123 @@ async fn get_one(
         .ok_or(ModelError::EntityNotFound)?;
     let problem = problems::Model::find_by_id(&ctx.db, submission.problem_id).await?;
     let tasks = problem.tasks(&ctx.db).await?;
+    let public_io = load_public_case_io(&ctx, &problem, &tasks).await?;
+
+    format::json(SubmissionDetailResponse::new(&submission, &user, &tasks, &public_io).done())
+}
+
+/// Load I/O of public test cases from problem's test case. It's read from the
+/// directory extracted for judging if there is one, otherwise from the stored
+/// package. A case that could not be read is left out instead of failing the
+/// whole response.
+async fn load_public_case_io(
+    ctx: &AppContext,
+    problem: &problems::Model,
+    tasks: &[problem_tasks::Model],
+) -> Result<HashMap<(usize, usize), CaseIo>> {
+    let public_cases = tasks
+        .iter()
+        .enumerate()
+        .flat_map(|(i, t)| {
+            t.case_meta()
+                .into_iter()
+                .enumerate()
+                .filter(|(_, c)| c.public)
+                .map(move |(j, c)| (i, j, c.dir(i, j)))
+        })
+        .collect::<Vec<_>>();
+    let Some(test_case_path) = problem.test_case_path() else {
+        return Ok(HashMap::new());
+    };
+    if public_cases.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    enum Source {
+        Extracted(std::path::PathBuf),
+        Package(zip::ZipArchive<std::io::Cursor<Vec<u8>>>),
+    }
+    // the package is only downloaded if it's not extracted here
+    let mut source = match test_case_dir(problem).filter(|d| d.exists()) {
+        Some(dir) => Source::Extracted(dir),
+        None => {
+            let test_case: Vec<u8> = ctx.storage.download(test_case_path.as_path()).await?;
+            let archive = zip::ZipArchive::new(std::io::Cursor::new(test_case)).map_err(|e| {
+                Error::Any(Box::new(problems::Error::BadTestCase(
+                    BadTestCase::ZipError(e),
+                )))
+            })?;
+            Source::Package(archive)
+        }
+    };
+
+    let mut public_io = HashMap::new();
+    for (i, j, dir) in public_cases {
+        let io = match &mut source {
+            Source::Extracted(root) => test_case::read_dir_case_io(root, &dir),
+            Source::Package(archive) => test_case::read_case_io(archive, &dir),
+        };
+        match io {
+            Ok(io) => {
+                public_io.insert((i, j), io);
+            }
+            Err(e) => {
+                tracing::warn!(
+                    problem_id = problem.id,
+                    case = dir,
+                    error = %e,
+                    "failed to read public case"
+                );
+            }
+        }
+    }
+
+    Ok(public_io)
+}
+
+#[derive(Debug, Serialize)]
+pub struct FullOutputResponse {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Download full outputs of a test case, only the submitter and who can manage
+/// the problem can access it.
+async fn get_output(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path((submission_id, task_id, case_id)): Path<(i32, i32, i32)>,
+) -> Result<Response> {
+    let user = match find_user_by_auth(&ctx, &auth).await {
+        Ok(u) => u,
+        Err(e) => return e,
+    };
+    let submission = match submissions::Model::find_by_id(&ctx.db, submission_id).await {
+        Ok(s) => s,
+        Err(ModelError::EntityNotFound) => {
+            return not_found();
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if submission.user_id != user.id {
+        let problem = problems::Model::find_by_id(&ctx.db, submission.problem_id).await?;
+        if problem.permission(&ctx.db, &user).await? != Permission::Manage {
+            return permission_denied();
+        }
+    }
+
+    let stdout_path = submission.output_path(task_id, case_id, "stdout");
+    let stderr_path = submission.output_path(task_id, case_id, "stderr");
+    let (Ok(stdout), Ok(stderr)) = (
+        ctx.storage.download::<Vec<u8>>(stdout_path.as_path()).await,
+        ctx.storage.download::<Vec<u8>>(stderr_path.as_path()).await,
+    ) else {
+        return not_found();
+    };
+    let stdout = String::from_utf8_lossy(&stdout).to_string();
+    let stderr = String::from_utf8_lossy(&stderr).to_string();
 
-    format::json(SubmissionDetailResponse::new(&submission, &user, &tasks).done())
+    format::json(NojResponseBuilder::new(FullOutputResponse { stdout, stderr }).done())
 }
 
 pub fn routes() -> Routes {
@@ -144,4 +302,5 @@ pub fn routes() -> Routes {
         .add("/", post(create))
         .add("/:submission_id", put(upload_code))
         .add("/:submission_id", get(get_one))
+        .add("/:submission_id/output/:task_id/:case_id", get(get_output))
 }
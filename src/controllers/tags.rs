@@ -0,0 +1,79 @@
+use crate::{models::tags, views::tags::TagListResponse};
+use loco_rs::prelude::*;
+use serde::Deserialize;
+
+use super::verify_teacher;
+
+#[derive(Debug, Deserialize)]
+pub struct TagRequest {
+    pub name: String,
+}
+
+fn validate_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(',') {
+        return Err(Error::BadRequest(
+            "tag name must be non-empty and must not contain ','".into(),
+        ));
+    }
+    Ok(name)
+}
+
+async fn list(State(ctx): State<AppContext>) -> Result<Response> {
+    let tags = tags::Model::list(&ctx.db).await?;
+
+    format::json(TagListResponse::new(&tags).done())
+}
+
+async fn create(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Json(params): Json<TagRequest>,
+) -> Result<Response> {
+    if let Err(e) = verify_teacher(&ctx, &auth).await {
+        return e;
+    }
+    let tag = tags::Model::create(&ctx.db, validate_name(&params.name)?).await?;
+
+    format::json(tag)
+}
+
+async fn rename(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(tag_id): Path<i32>,
+    Json(params): Json<TagRequest>,
+) -> Result<Response> {
+    if let Err(e) = verify_teacher(&ctx, &auth).await {
+        return e;
+    }
+    let tag = tags::Model::find_by_id(&ctx.db, tag_id).await?;
+    let tag = tag.rename(&ctx.db, validate_name(&params.name)?).await?;
+
+    format::json(tag)
+}
+
+async fn remove(
+    State(ctx): State<AppContext>,
+    auth: auth::JWT,
+    Path(tag_id): Path<i32>,
+) -> Result<Response> {
+    if let Err(e) = verify_teacher(&ctx, &auth).await {
+        return e;
+    }
+    tags::Model::find_by_id(&ctx.db, tag_id)
+        .await?
+        .remove(&ctx.db)
+        .await?;
+
+    format::empty_json()
+}
+
+pub fn routes() -> Routes {
+    Routes::new()
+        .prefix("tags")
+        .add("/", get(list))
+        .add("/", post(create))
+        .add("/:tag_id", put(rename))
+        .add("/:tag_id", delete(remove))
+}
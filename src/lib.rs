@@ -2,6 +2,8 @@ pub mod app;
 pub mod controllers;
 pub mod mailers;
 pub mod models;
+pub mod sandbox;
+pub mod settings;
 pub mod tasks;
 pub mod views;
 pub mod workers;
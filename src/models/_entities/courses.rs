@@ -17,6 +17,10 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::course_members::Entity")]
+    CourseMembers,
+    #[sea_orm(has_many = "super::course_problems::Entity")]
+    CourseProblems,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::TeacherId",
@@ -27,6 +31,27 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::course_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseMembers.def()
+    }
+}
+
+impl Related<super::course_problems::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseProblems.def()
+    }
+}
+
+impl Related<super::problems::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::course_problems::Relation::Problems.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::course_problems::Relation::Courses.def().rev())
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
@@ -2,11 +2,24 @@
 
 pub mod prelude;
 
+pub mod course_members;
+pub mod course_problems;
 pub mod courses;
 pub mod notes;
+pub mod problem_attachments;
+pub mod problem_collaborators;
 pub mod problem_descriptions;
+pub mod problem_revisions;
+pub mod problem_solutions;
+pub mod problem_tags;
 pub mod problem_tasks;
+pub mod problem_templates;
+pub mod problem_translations;
+pub mod problem_validators;
 pub mod problems;
 pub mod sea_orm_active_enums;
 pub mod submissions;
+pub mod tags;
+pub mod test_case_uploads;
+pub mod test_case_versions;
 pub mod users;
@@ -1,9 +1,22 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+pub use super::course_members::Entity as CourseMembers;
+pub use super::course_problems::Entity as CourseProblems;
 pub use super::courses::Entity as Courses;
 pub use super::notes::Entity as Notes;
+pub use super::problem_attachments::Entity as ProblemAttachments;
+pub use super::problem_collaborators::Entity as ProblemCollaborators;
 pub use super::problem_descriptions::Entity as ProblemDescriptions;
+pub use super::problem_revisions::Entity as ProblemRevisions;
+pub use super::problem_solutions::Entity as ProblemSolutions;
+pub use super::problem_tags::Entity as ProblemTags;
 pub use super::problem_tasks::Entity as ProblemTasks;
+pub use super::problem_templates::Entity as ProblemTemplates;
+pub use super::problem_translations::Entity as ProblemTranslations;
+pub use super::problem_validators::Entity as ProblemValidators;
 pub use super::problems::Entity as Problems;
 pub use super::submissions::Entity as Submissions;
+pub use super::tags::Entity as Tags;
+pub use super::test_case_uploads::Entity as TestCaseUploads;
+pub use super::test_case_versions::Entity as TestCaseVersions;
 pub use super::users::Entity as Users;
@@ -20,10 +20,18 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_one = "super::problem_translations::Entity")]
+    ProblemTranslations,
     #[sea_orm(has_one = "super::problems::Entity")]
     Problems,
 }
 
+impl Related<super::problem_translations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemTranslations.def()
+    }
+}
+
 impl Related<super::problems::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Problems.def()
@@ -13,6 +13,7 @@ pub struct Model {
     pub time_limit: i32,
     pub memory_limit: i32,
     pub problem_id: i32,
+    pub cases: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
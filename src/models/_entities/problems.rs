@@ -19,10 +19,17 @@ pub struct Model {
     pub allowed_language: i32,
     pub quota: i32,
     pub test_case_id: Option<String>,
+    pub verification: Option<Json>,
+    pub deleted_at: Option<DateTime>,
+    pub default_locale: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::course_problems::Entity")]
+    CourseProblems,
+    #[sea_orm(has_many = "super::problem_attachments::Entity")]
+    ProblemAttachments,
     #[sea_orm(
         belongs_to = "super::problem_descriptions::Entity",
         from = "Column::DescriptionId",
@@ -31,8 +38,26 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     ProblemDescriptions,
+    #[sea_orm(has_many = "super::problem_collaborators::Entity")]
+    ProblemCollaborators,
+    #[sea_orm(has_many = "super::problem_revisions::Entity")]
+    ProblemRevisions,
+    #[sea_orm(has_many = "super::problem_solutions::Entity")]
+    ProblemSolutions,
+    #[sea_orm(has_many = "super::problem_tags::Entity")]
+    ProblemTags,
     #[sea_orm(has_many = "super::problem_tasks::Entity")]
     ProblemTasks,
+    #[sea_orm(has_many = "super::problem_templates::Entity")]
+    ProblemTemplates,
+    #[sea_orm(has_many = "super::problem_translations::Entity")]
+    ProblemTranslations,
+    #[sea_orm(has_one = "super::problem_validators::Entity")]
+    ProblemValidators,
+    #[sea_orm(has_many = "super::test_case_uploads::Entity")]
+    TestCaseUploads,
+    #[sea_orm(has_many = "super::test_case_versions::Entity")]
+    TestCaseVersions,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::OwnerId",
@@ -43,18 +68,102 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::course_problems::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseProblems.def()
+    }
+}
+
+impl Related<super::courses::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::course_problems::Relation::Courses.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::course_problems::Relation::Problems.def().rev())
+    }
+}
+
+impl Related<super::problem_attachments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemAttachments.def()
+    }
+}
+
 impl Related<super::problem_descriptions::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ProblemDescriptions.def()
     }
 }
 
+impl Related<super::problem_collaborators::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemCollaborators.def()
+    }
+}
+
+impl Related<super::problem_revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemRevisions.def()
+    }
+}
+
+impl Related<super::problem_solutions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemSolutions.def()
+    }
+}
+
+impl Related<super::problem_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemTags.def()
+    }
+}
+
 impl Related<super::problem_tasks::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ProblemTasks.def()
     }
 }
 
+impl Related<super::problem_templates::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemTemplates.def()
+    }
+}
+
+impl Related<super::problem_translations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemTranslations.def()
+    }
+}
+
+impl Related<super::problem_validators::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemValidators.def()
+    }
+}
+
+impl Related<super::tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::problem_tags::Relation::Tags.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::problem_tags::Relation::Problems.def().rev())
+    }
+}
+
+impl Related<super::test_case_uploads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TestCaseUploads.def()
+    }
+}
+
+impl Related<super::test_case_versions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TestCaseVersions.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
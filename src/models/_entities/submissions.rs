@@ -24,6 +24,7 @@ pub struct Model {
     pub status: SubmissionStatus,
     pub language: Language,
     pub tasks: Option<Json>,
+    pub revision_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
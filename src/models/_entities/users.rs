@@ -31,12 +31,30 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::course_members::Entity")]
+    CourseMembers,
     #[sea_orm(has_many = "super::courses::Entity")]
     Courses,
+    #[sea_orm(has_many = "super::problem_attachments::Entity")]
+    ProblemAttachments,
+    #[sea_orm(has_many = "super::problem_collaborators::Entity")]
+    ProblemCollaborators,
+    #[sea_orm(has_many = "super::problem_revisions::Entity")]
+    ProblemRevisions,
     #[sea_orm(has_many = "super::problems::Entity")]
     Problems,
     #[sea_orm(has_many = "super::submissions::Entity")]
     Submissions,
+    #[sea_orm(has_many = "super::test_case_uploads::Entity")]
+    TestCaseUploads,
+    #[sea_orm(has_many = "super::test_case_versions::Entity")]
+    TestCaseVersions,
+}
+
+impl Related<super::course_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CourseMembers.def()
+    }
 }
 
 impl Related<super::courses::Entity> for Entity {
@@ -45,6 +63,24 @@ impl Related<super::courses::Entity> for Entity {
     }
 }
 
+impl Related<super::problem_attachments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemAttachments.def()
+    }
+}
+
+impl Related<super::problem_collaborators::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemCollaborators.def()
+    }
+}
+
+impl Related<super::problem_revisions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProblemRevisions.def()
+    }
+}
+
 impl Related<super::problems::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Problems.def()
@@ -56,3 +92,15 @@ impl Related<super::submissions::Entity> for Entity {
         Relation::Submissions.def()
     }
 }
+
+impl Related<super::test_case_uploads::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TestCaseUploads.def()
+    }
+}
+
+impl Related<super::test_case_versions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TestCaseVersions.def()
+    }
+}
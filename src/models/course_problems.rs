@@ -0,0 +1,7 @@
+use sea_orm::entity::prelude::*;
+
+use super::_entities::course_problems::ActiveModel;
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
@@ -1,7 +1,8 @@
-use super::_entities::courses;
 pub use super::_entities::courses::{ActiveModel, Entity, Model};
+use super::_entities::{course_members, courses};
+use crate::models::transform_db_error;
 use loco_rs::model::{ModelError, ModelResult};
-use sea_orm::entity::prelude::*;
+use sea_orm::{entity::prelude::*, ActiveValue, Order, QueryOrder};
 
 impl super::_entities::courses::Model {
     /// finds a course by the provided name
@@ -22,6 +23,46 @@ impl super::_entities::courses::Model {
         Self::find_by_column(db, courses::Column::Id, id).await
     }
 
+    /// finds courses by the provided names, unknown names are ignored
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn find_by_names<C: ConnectionTrait>(
+        db: &C,
+        names: &[String],
+    ) -> ModelResult<Vec<Self>> {
+        let courses = courses::Entity::find()
+            .filter(courses::Column::Name.is_in(names.iter().cloned()))
+            .order_by(courses::Column::Name, Order::Asc)
+            .all(db)
+            .await?;
+
+        Ok(courses)
+    }
+
+    /// Add a user to this course, do nothing if the user is already a member
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn add_member<C: ConnectionTrait>(&self, db: &C, user_id: i32) -> ModelResult<()> {
+        let member = course_members::Entity::find_by_id((self.id, user_id))
+            .one(db)
+            .await?;
+        if member.is_none() {
+            course_members::ActiveModel {
+                course_id: ActiveValue::set(self.id),
+                user_id: ActiveValue::set(user_id),
+            }
+            .insert(db)
+            .await
+            .map_err(transform_db_error)?;
+        }
+
+        Ok(())
+    }
+
     async fn find_by_column(
         db: &DatabaseConnection,
         column: impl sea_orm::ColumnTrait,
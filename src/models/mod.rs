@@ -1,15 +1,49 @@
 pub mod _entities;
+pub mod course_members;
+pub mod course_problems;
 pub mod courses;
 pub mod language;
 pub mod notes;
+pub mod problem_tags;
+pub mod problem_translations;
 pub mod problems;
 pub mod submissions;
+pub mod tags;
+pub mod test_case_uploads;
 pub mod users;
 
 pub use language::Language;
 
 use loco_rs::model::ModelError;
-use sea_orm::{DbErr, SqlErr};
+use sea_orm::{DbErr, Order, SqlErr};
+use serde::Deserialize;
+
+/// Max number of items in a page of lists
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Clamp a requested page size into `1..=MAX_PAGE_SIZE`
+#[must_use]
+pub fn clamp_page_size(page_size: u64) -> u64 {
+    page_size.clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Direction of sorted lists
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Self::Asc,
+            SortOrder::Desc => Self::Desc,
+        }
+    }
+}
 
 pub(crate) fn is_unique_constraint_violation_err(e: &DbErr) -> bool {
     matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_)))
@@ -0,0 +1,7 @@
+use sea_orm::entity::prelude::*;
+
+use super::_entities::problem_tags::ActiveModel;
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
@@ -0,0 +1,419 @@
+// Uploaded test case archives
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{Read, Seek, Write},
+    path::{Component, Path},
+};
+
+use flate2::read::GzDecoder;
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use super::BadTestCase;
+use crate::settings::ArchiveSettings;
+
+/// Archive formats accepted as test case package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Zip,
+    TarGz,
+}
+
+impl Format {
+    /// Human readable list of accepted formats, used in error messages
+    pub const ACCEPTED: &'static str = ".zip, .tar.gz, .tgz";
+
+    /// Detect archive format by its magic bytes
+    #[must_use]
+    pub fn sniff(content: &[u8]) -> Option<Self> {
+        if content.starts_with(b"PK\x03\x04") || content.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if content.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Convert an uploaded archive into the zip layout used everywhere else. A
+/// package wrapped in a single top-level directory (e.g. `problem/test-case/`)
+/// is unwrapped. Returns `None` if the input is a zip already in that layout,
+/// so large packages are never copied; otherwise returns the repacked zip,
+/// which is streamed into a temporary file entry by entry.
+///
+/// # Errors
+///
+/// - When the format is not supported
+/// - When the archive is broken or contains unsafe entries (symlinks, paths
+///   escaping the archive)
+/// - When the archive exceeds `limits`
+pub fn normalize<R: Read + Seek>(
+    input: &mut R,
+    limits: &ArchiveSettings,
+) -> Result<Option<File>, BadTestCase> {
+    let io_error = |e: std::io::Error| BadTestCase::Custom(format!("failed to read archive: {e}"));
+
+    let mut magic = vec![];
+    input
+        .by_ref()
+        .take(4)
+        .read_to_end(&mut magic)
+        .map_err(io_error)?;
+    input.rewind().map_err(io_error)?;
+
+    let Some(format) = Format::sniff(&magic) else {
+        return Err(BadTestCase::Custom(format!(
+            "unsupported test case format, accepted formats: {}",
+            Format::ACCEPTED
+        )));
+    };
+    // entries are decompressed to count their real sizes, so the limit also
+    // applies to a zip passed through as is
+    let mut names = vec![];
+    let mut remaining = limits.max_unpacked_size;
+    let collect_name = |name: String, entry: &mut dyn Read| {
+        copy_entry(entry, &name, &mut std::io::sink(), &mut remaining, limits)?;
+        names.push(name);
+        Ok(())
+    };
+    match format {
+        Format::Zip => visit_zip(input, limits, collect_name)?,
+        Format::TarGz => visit_tar_gz(&mut *input, limits, collect_name)?,
+    }
+    input.rewind().map_err(io_error)?;
+
+    let prefix = wrapper_dir(&names).map(|d| format!("{d}/"));
+    if format == Format::Zip && prefix.is_none() {
+        return Ok(None);
+    }
+
+    let repack_error =
+        |e: std::io::Error| BadTestCase::Custom(format!("failed to repack test case: {e}"));
+    let mut output = tempfile::tempfile().map_err(repack_error)?;
+    let mut zip = ZipWriter::new(&mut output);
+    let mut remaining = limits.max_unpacked_size;
+    let repack = |name: String, entry: &mut dyn Read| {
+        let name = prefix
+            .as_ref()
+            .and_then(|p| name.strip_prefix(p.as_str()))
+            .unwrap_or(&name);
+        zip.start_file(name, SimpleFileOptions::default())?;
+        copy_entry(entry, name, &mut zip, &mut remaining, limits)
+    };
+    match format {
+        Format::Zip => visit_zip(input, limits, repack)?,
+        Format::TarGz => visit_tar_gz(&mut *input, limits, repack)?,
+    }
+    zip.finish()?;
+    output.rewind().map_err(repack_error)?;
+
+    Ok(Some(output))
+}
+
+/// Read all regular files of an archive in any accepted format, with the
+/// single top-level directory wrapping them (if any) stripped from names.
+///
+/// # Errors
+///
+/// Same as [`normalize`]
+pub fn read_files<R: Read + Seek>(
+    input: &mut R,
+    limits: &ArchiveSettings,
+) -> Result<Vec<(String, Vec<u8>)>, BadTestCase> {
+    match normalize(input, limits)? {
+        Some(mut repacked) => read_zip(&mut repacked, limits),
+        None => read_zip(input, limits),
+    }
+}
+
+fn check_entry_count(count: usize, limits: &ArchiveSettings) -> Result<(), BadTestCase> {
+    if count > limits.max_entries {
+        return Err(BadTestCase::Custom(format!(
+            "too many entries in archive, at most {} allowed",
+            limits.max_entries
+        )));
+    }
+    Ok(())
+}
+
+/// Copy an archive entry into `output`, counting its size against
+/// `remaining` bytes of the whole archive. Copy is stopped as soon as it's
+/// exceeded, regardless of the size the entry declares.
+fn copy_entry<W: Write>(
+    entry: &mut dyn Read,
+    name: &str,
+    output: &mut W,
+    remaining: &mut u64,
+    limits: &ArchiveSettings,
+) -> Result<(), BadTestCase> {
+    let size = std::io::copy(&mut entry.take(*remaining + 1), output)
+        .map_err(|e| BadTestCase::Custom(format!("failed to read {name}: {e}")))?;
+    if size > *remaining {
+        return Err(BadTestCase::Custom(format!(
+            "archive is too large after decompression, at most {} bytes allowed",
+            limits.max_unpacked_size
+        )));
+    }
+    *remaining -= size;
+    Ok(())
+}
+
+/// Check that an entry path stays inside archive and convert it to `/`
+/// separated string.
+fn safe_path(path: &Path, raw: &str) -> Result<String, BadTestCase> {
+    let mut parts = vec![];
+    for component in path.components() {
+        match component {
+            Component::Normal(p) => parts.push(p.to_str().ok_or_else(|| {
+                BadTestCase::Custom(format!(
+                    "invalid path found in archive (maybe non-UTF8 path?): {raw}"
+                ))
+            })?),
+            Component::CurDir => {}
+            _ => {
+                return Err(BadTestCase::Custom(format!(
+                    "invalid path found in archive: {raw}"
+                )))
+            }
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn zip_entry_name(file: &zip::read::ZipFile) -> Result<Option<String>, BadTestCase> {
+    if file.is_symlink() {
+        return Err(BadTestCase::Custom(format!(
+            "symlink is not allowed: {}",
+            file.name()
+        )));
+    }
+    if file.is_dir() {
+        return Ok(None);
+    }
+    let name = file.enclosed_name().ok_or_else(|| {
+        BadTestCase::Custom(format!("invalid path found in archive: {}", file.name()))
+    })?;
+    safe_path(&name, file.name()).map(Some)
+}
+
+/// Call `visit` with the name and content of each regular file inside zip,
+/// unsafe entries are rejected
+fn visit_zip<R, F>(input: &mut R, limits: &ArchiveSettings, mut visit: F) -> Result<(), BadTestCase>
+where
+    R: Read + Seek,
+    F: FnMut(String, &mut dyn Read) -> Result<(), BadTestCase>,
+{
+    let mut archive = ZipArchive::new(input)?;
+    check_entry_count(archive.len(), limits)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let Some(name) = zip_entry_name(&file)? else {
+            continue;
+        };
+        visit(name, &mut file)?;
+    }
+    Ok(())
+}
+
+/// Same as [`visit_zip`], for tar.gz
+fn visit_tar_gz<R, F>(input: R, limits: &ArchiveSettings, mut visit: F) -> Result<(), BadTestCase>
+where
+    R: Read,
+    F: FnMut(String, &mut dyn Read) -> Result<(), BadTestCase>,
+{
+    let broken = |e: std::io::Error| BadTestCase::Custom(format!("broken tar.gz file: {e}"));
+
+    let mut archive = tar::Archive::new(GzDecoder::new(input));
+    let mut names = HashSet::new();
+    for (i, entry) in archive.entries().map_err(broken)?.enumerate() {
+        // tar has no index, count entries as they come
+        check_entry_count(i + 1, limits)?;
+        let mut entry = entry.map_err(broken)?;
+        let raw = entry.path().map_err(broken)?.to_string_lossy().to_string();
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            continue;
+        }
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            return Err(BadTestCase::Custom(format!(
+                "symlink is not allowed: {raw}"
+            )));
+        }
+        if !entry_type.is_file() {
+            return Err(BadTestCase::Custom(format!(
+                "only regular files are allowed: {raw}"
+            )));
+        }
+        let name = safe_path(&entry.path().map_err(broken)?, &raw)?;
+        if !names.insert(name.clone()) {
+            return Err(BadTestCase::Custom(format!(
+                "duplicated or extra file found: {raw}"
+            )));
+        }
+        visit(name, &mut entry)?;
+    }
+    Ok(())
+}
+
+fn read_zip<R: Read + Seek>(
+    input: &mut R,
+    limits: &ArchiveSettings,
+) -> Result<Vec<(String, Vec<u8>)>, BadTestCase> {
+    let mut remaining = limits.max_unpacked_size;
+    let mut files = vec![];
+    visit_zip(input, limits, |name, file| {
+        let mut data = vec![];
+        copy_entry(file, &name, &mut data, &mut remaining, limits)?;
+        files.push((name, data));
+        Ok(())
+    })?;
+    Ok(files)
+}
+
+/// The single top-level directory wrapping all files, if any
+fn wrapper_dir(names: &[String]) -> Option<&str> {
+    let mut dirs = names
+        .iter()
+        .map(|name| name.split_once('/').map(|(d, _)| d));
+    let first = dirs.next()??;
+    if matches!(first, "test-case" | "generators") || !dirs.all(|d| d == Some(first)) {
+        return None;
+    }
+    Some(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn make_tar_gz(files: &[(&str, &str)]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn names(zip: File) -> Vec<String> {
+        let archive = ZipArchive::new(zip).unwrap();
+        let mut names = archive.file_names().map(str::to_string).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(Format::sniff(b"PK\x03\x04..."), Some(Format::Zip));
+        assert_eq!(Format::sniff(&make_tar_gz(&[])), Some(Format::TarGz));
+        assert_eq!(Format::sniff(b"Rar!"), None);
+        assert!(normalize(&mut Cursor::new(b"Rar!"), &ArchiveSettings::default()).is_err());
+    }
+
+    #[test]
+    fn test_normalize_tar_gz_with_wrapper_dir() {
+        let content = make_tar_gz(&[
+            ("problem/test-case/0000/STDIN", "1 2"),
+            ("problem/test-case/0000/STDOUT", "3"),
+        ]);
+        assert_eq!(
+            names(
+                normalize(&mut Cursor::new(content), &ArchiveSettings::default())
+                    .unwrap()
+                    .unwrap()
+            ),
+            vec!["test-case/0000/STDIN", "test-case/0000/STDOUT"],
+        );
+    }
+
+    #[test]
+    fn test_normalize_tar_gz_without_wrapper_dir() {
+        let content = make_tar_gz(&[
+            ("./test-case/0000/STDIN", "1 2"),
+            ("./test-case/0000/STDOUT", "3"),
+        ]);
+        assert_eq!(
+            names(
+                normalize(&mut Cursor::new(content), &ArchiveSettings::default())
+                    .unwrap()
+                    .unwrap()
+            ),
+            vec!["test-case/0000/STDIN", "test-case/0000/STDOUT"],
+        );
+    }
+
+    #[test]
+    fn test_tar_gz_rejects_symlink() {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_cksum();
+        builder
+            .append_link(&mut header, "test-case/0000/STDIN", "/etc/passwd")
+            .unwrap();
+        let content = builder.into_inner().unwrap().finish().unwrap();
+
+        assert!(normalize(&mut Cursor::new(content), &ArchiveSettings::default()).is_err());
+    }
+
+    #[test]
+    fn test_tar_gz_limits() {
+        let content = make_tar_gz(&[
+            ("test-case/0000/STDIN", "1 2"),
+            ("test-case/0000/STDOUT", "3"),
+        ]);
+        let normalize_with = |max_unpacked_size, max_entries| {
+            normalize(
+                &mut Cursor::new(content.clone()),
+                &ArchiveSettings {
+                    max_unpacked_size,
+                    max_entries,
+                },
+            )
+        };
+        assert!(normalize_with(4, 2).is_ok());
+        assert!(normalize_with(3, 2).is_err());
+        assert!(normalize_with(4, 1).is_err());
+    }
+
+    #[test]
+    fn test_zip_limits() {
+        let mut content = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut content);
+            for (name, data) in [
+                ("test-case/0000/STDIN", "1 2"),
+                ("test-case/0000/STDOUT", "3"),
+            ] {
+                zip.start_file(name, SimpleFileOptions::default()).unwrap();
+                zip.write_all(data.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        let content = content.into_inner();
+        let normalize_with = |max_unpacked_size, max_entries| {
+            normalize(
+                &mut Cursor::new(content.clone()),
+                &ArchiveSettings {
+                    max_unpacked_size,
+                    max_entries,
+                },
+            )
+        };
+        // passed through without repacking, but still checked
+        assert!(normalize_with(4, 2).unwrap().is_none());
+        assert!(normalize_with(3, 2).is_err());
+        assert!(normalize_with(4, 1).is_err());
+    }
+}
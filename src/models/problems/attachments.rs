@@ -0,0 +1,227 @@
+use std::path::PathBuf;
+
+use axum::body::Bytes;
+use loco_rs::{
+    app::AppContext,
+    model::{ModelError, ModelResult},
+};
+use sea_orm::{entity::prelude::*, ActiveValue, Order, QueryOrder};
+
+pub use super::_entities::problem_attachments::{self, ActiveModel, Model};
+use crate::models::transform_db_error;
+
+/// Accepted file extensions and the content type they are served with. SVG
+/// and HTML are left out on purpose, as they can carry script.
+pub const CONTENT_TYPES: [(&str, &str); 9] = [
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("pdf", "application/pdf"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("zip", "application/zip"),
+];
+
+/// Max length of attachment name
+pub const MAX_NAME_LEN: usize = 1 << 8;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum InvalidAttachment {
+    #[error("invalid attachment name, expect letters, digits, '.', '-' or '_'")]
+    InvalidName,
+    #[error(
+        "unsupported attachment type, accepted extensions: {}",
+        accepted_extensions()
+    )]
+    UnsupportedType,
+    #[error("attachment too large, max size is {0} bytes")]
+    TooLarge(usize),
+}
+
+fn accepted_extensions() -> String {
+    CONTENT_TYPES
+        .iter()
+        .map(|(ext, _)| *ext)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Check name and size of an attachment, returns its content type
+///
+/// Names must be plain file names, so they can be referenced from Markdown
+/// without escaping.
+///
+/// # Errors
+///
+/// When the name, type or size is not accepted
+pub fn validate(
+    name: &str,
+    size: usize,
+    max_size: usize,
+) -> Result<&'static str, InvalidAttachment> {
+    let valid_name = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !valid_name {
+        return Err(InvalidAttachment::InvalidName);
+    }
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .ok_or(InvalidAttachment::UnsupportedType)?;
+    let content_type = CONTENT_TYPES
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, t)| *t)
+        .ok_or(InvalidAttachment::UnsupportedType)?;
+    if size > max_size {
+        return Err(InvalidAttachment::TooLarge(max_size));
+    }
+
+    Ok(content_type)
+}
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+impl Model {
+    /// Store an attachment of a problem. The caller should check it with
+    /// [`validate`] first. `db` could be a transaction of the caller, who
+    /// should remove the stored content if it's rolled back.
+    ///
+    /// # Errors
+    ///
+    /// - When the problem already has an attachment with the same name
+    /// - When there is DB or storage error
+    pub async fn add<C: ConnectionTrait>(
+        ctx: &AppContext,
+        db: &C,
+        problem_id: i32,
+        uploader_id: i32,
+        name: &str,
+        content_type: &str,
+        content: Bytes,
+    ) -> loco_rs::Result<Self> {
+        let attachment = ActiveModel {
+            pid: ActiveValue::set(Uuid::new_v4()),
+            problem_id: ActiveValue::set(problem_id),
+            name: ActiveValue::set(name.to_string()),
+            content_type: ActiveValue::set(content_type.to_string()),
+            size: ActiveValue::set(i64::try_from(content.len()).unwrap_or(i64::MAX)),
+            uploader_id: ActiveValue::set(uploader_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(transform_db_error)?;
+
+        if let Err(e) = ctx.storage.upload(&attachment.path(), &content).await {
+            attachment
+                .clone()
+                .delete(db)
+                .await
+                .map_err(transform_db_error)?;
+            return Err(e.into());
+        }
+        tracing::info!(problem_id, name, "attachment uploaded");
+
+        Ok(attachment)
+    }
+
+    /// List attachments of a problem, ordered by name
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn list_by_problem<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+    ) -> ModelResult<Vec<Self>> {
+        let attachments = problem_attachments::Entity::find()
+            .filter(problem_attachments::Column::ProblemId.eq(problem_id))
+            .order_by(problem_attachments::Column::Name, Order::Asc)
+            .all(db)
+            .await
+            .map_err(transform_db_error)?;
+
+        Ok(attachments)
+    }
+
+    /// Find an attachment of a problem by its name
+    ///
+    /// # Errors
+    ///
+    /// - When the attachment does not exist
+    /// - When there is DB error.
+    pub async fn find_by_name<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        name: &str,
+    ) -> ModelResult<Self> {
+        problem_attachments::Entity::find()
+            .filter(problem_attachments::Column::ProblemId.eq(problem_id))
+            .filter(problem_attachments::Column::Name.eq(name))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?
+            .ok_or(ModelError::EntityNotFound)
+    }
+
+    /// Remove this attachment and its file
+    ///
+    /// # Errors
+    ///
+    /// When there is DB or storage error
+    pub async fn remove(self, ctx: &AppContext) -> loco_rs::Result<()> {
+        let path = self.path();
+        self.delete(&ctx.db).await.map_err(transform_db_error)?;
+        ctx.storage.delete(&path).await?;
+
+        Ok(())
+    }
+
+    /// Path of this attachment in app's storage
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from("attachment").join(self.pid.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_attachment() {
+        assert_eq!(validate("figure-1.PNG", 10, 10), Ok("image/png"));
+        assert_eq!(validate("data.txt", 0, 10), Ok("text/plain; charset=utf-8"));
+        assert_eq!(validate("", 0, 10), Err(InvalidAttachment::InvalidName));
+        assert_eq!(validate(".png", 0, 10), Err(InvalidAttachment::InvalidName));
+        assert_eq!(
+            validate("../a.png", 0, 10),
+            Err(InvalidAttachment::InvalidName)
+        );
+        assert_eq!(
+            validate("a b.png", 0, 10),
+            Err(InvalidAttachment::InvalidName)
+        );
+        assert_eq!(
+            validate("x.svg", 0, 10),
+            Err(InvalidAttachment::UnsupportedType)
+        );
+        assert_eq!(
+            validate("README", 0, 10),
+            Err(InvalidAttachment::UnsupportedType)
+        );
+        assert_eq!(
+            validate("a.png", 11, 10),
+            Err(InvalidAttachment::TooLarge(10))
+        );
+    }
+}
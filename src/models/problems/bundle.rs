@@ -0,0 +1,438 @@
+// Lossless problem bundle, for backup and moving problems between instances
+
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read, Seek, Write},
+    path::PathBuf,
+};
+
+use loco_rs::{
+    app::AppContext,
+    model::{ModelError, ModelResult},
+};
+use num_traits::FromPrimitive;
+use sea_orm::{entity::prelude::*, DatabaseTransaction, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use zip::{result::ZipError, write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use super::{
+    attachments, descriptions, locales, revisions, solutions, tasks, templates, validators,
+    AddParams, BadTestCase, Error, Type, Visibility,
+};
+use crate::{
+    models::{_entities::problem_descriptions, courses, problems, tags, transform_db_error, users},
+    settings::Settings,
+};
+
+/// Version of bundle layout, bumped on incompatible changes
+pub const VERSION: u32 = 1;
+/// Bundle header with all problem data except test case
+const HEADER_NAME: &str = "bundle.json";
+/// Test case package, stored as is. Its checker lives in the package's
+/// manifest.
+const TEST_CASE_NAME: &str = "test-case.zip";
+/// Directory of problem attachments, stored by their names
+const ATTACHMENT_DIR: &str = "attachments/";
+
+/// Everything needed to re-create a problem. Ids are not included, as they're
+/// reassigned on import.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub name: String,
+    pub r#type: i32,
+    pub status: i32,
+    pub allowed_language: i32,
+    pub quota: i32,
+    pub description: descriptions::AddParams,
+    /// Locale of `description`
+    #[serde(default = "default_locale")]
+    pub default_locale: String,
+    /// Descriptions in other locales
+    #[serde(default)]
+    pub translations: BTreeMap<String, descriptions::AddParams>,
+    pub tasks: Vec<tasks::AddParams>,
+    pub tags: Vec<String>,
+    /// Names of courses containing the problem
+    pub courses: Vec<String>,
+    pub validator: Option<validators::SetParams>,
+    pub solutions: Vec<solutions::AddParams>,
+    /// Starter code, at most one per language
+    #[serde(default)]
+    pub templates: Vec<templates::SetParams>,
+    /// Names of attachments, whose content is under `attachments/`
+    #[serde(default)]
+    pub attachments: Vec<String>,
+}
+
+/// Binary files of a bundle besides its header
+#[derive(Debug, Default)]
+pub struct Files {
+    pub test_case: Option<Vec<u8>>,
+    /// Attachment names and contents
+    pub attachments: Vec<(String, Vec<u8>)>,
+}
+
+fn default_locale() -> String {
+    locales::DEFAULT_LOCALE.to_string()
+}
+
+fn invalid(msg: impl Into<String>) -> BadTestCase {
+    BadTestCase::InvalidPackage(msg.into())
+}
+
+fn wrap_error(e: BadTestCase) -> loco_rs::Error {
+    loco_rs::Error::Any(Box::new(Error::BadTestCase(e)))
+}
+
+impl Bundle {
+    /// Collect bundle of a problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn of_problem<C: ConnectionTrait>(
+        db: &C,
+        problem: &problems::Model,
+    ) -> ModelResult<Self> {
+        let description = problem_descriptions::Entity::find_by_id(problem.description_id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        let validator = validators::Model::find_by_problem(db, problem.id).await?;
+        let solutions = solutions::Model::list_by_problem(db, problem.id).await?;
+        let attachments = attachments::Model::list_by_problem(db, problem.id).await?;
+        let templates = templates::Model::list_by_problem(db, problem.id).await?;
+
+        Ok(Self {
+            version: VERSION,
+            name: problem.name.clone(),
+            r#type: problem.r#type,
+            status: problem.status,
+            allowed_language: problem.allowed_language,
+            quota: problem.quota,
+            description: description.to_params(),
+            default_locale: problem.default_locale.clone(),
+            translations: problem
+                .translations(db)
+                .await?
+                .into_iter()
+                .map(|(locale, d)| (locale, d.to_params()))
+                .collect(),
+            tasks: problem
+                .tasks(db)
+                .await?
+                .iter()
+                .map(tasks::Model::to_params)
+                .collect(),
+            tags: problem.tag_names(db).await?,
+            courses: problem.course_names(db).await?,
+            validator: validator.map(|v| validators::SetParams {
+                language: v.language,
+                code: v.code,
+            }),
+            solutions: solutions
+                .into_iter()
+                .map(|s| solutions::AddParams {
+                    name: s.name,
+                    language: s.language,
+                    code: s.code,
+                    expected_status: s.expected_status,
+                    is_main: s.is_main,
+                })
+                .collect(),
+            attachments: attachments.into_iter().map(|a| a.name).collect(),
+            templates: templates
+                .into_iter()
+                .map(|t| templates::SetParams {
+                    language: t.language,
+                    code: t.code,
+                })
+                .collect(),
+        })
+    }
+
+    /// Write bundle archive
+    ///
+    /// # Errors
+    ///
+    /// When the archive could not be written
+    pub fn write(&self, files: &Files) -> Result<Vec<u8>, BadTestCase> {
+        let header = serde_json::to_vec_pretty(self)
+            .map_err(|e| BadTestCase::Custom(format!("failed to write bundle: {e}")))?;
+        let mut buf = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buf);
+        let entries =
+            std::iter::once((HEADER_NAME.to_string(), header.as_slice()))
+                .chain(
+                    files
+                        .test_case
+                        .as_deref()
+                        .map(|t| (TEST_CASE_NAME.to_string(), t)),
+                )
+                .chain(files.attachments.iter().map(|(name, content)| {
+                    (format!("{ATTACHMENT_DIR}{name}"), content.as_slice())
+                }));
+        for (name, content) in entries {
+            zip.start_file(name, SimpleFileOptions::default())?;
+            zip.write_all(content)
+                .map_err(|e| BadTestCase::Custom(format!("failed to write bundle: {e}")))?;
+        }
+        zip.finish()?;
+
+        Ok(buf.into_inner())
+    }
+
+    /// Read bundle archive, returns the bundle and its files
+    ///
+    /// # Errors
+    ///
+    /// When the archive is broken or made by an incompatible version
+    pub fn read<R: Read + Seek>(input: R) -> Result<(Self, Files), BadTestCase> {
+        let mut archive = ZipArchive::new(input)?;
+        let mut read = |name: &str| -> Result<Option<Vec<u8>>, BadTestCase> {
+            let mut file = match archive.by_name(name) {
+                Ok(f) => f,
+                Err(ZipError::FileNotFound) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            let mut content = vec![];
+            file.read_to_end(&mut content)
+                .map_err(|e| invalid(format!("failed to read {name}: {e}")))?;
+            Ok(Some(content))
+        };
+
+        let header = read(HEADER_NAME)?.ok_or_else(|| invalid("bundle.json not found"))?;
+        let version = serde_json::from_slice::<serde_json::Value>(&header)
+            .map_err(|e| invalid(format!("invalid bundle.json: {e}")))?
+            .get("version")
+            .and_then(serde_json::Value::as_u64);
+        if version != Some(VERSION.into()) {
+            return Err(invalid(format!(
+                "unsupported bundle version: {version:?}, expected {VERSION}"
+            )));
+        }
+        let bundle = serde_json::from_slice::<Self>(&header)
+            .map_err(|e| invalid(format!("invalid bundle.json: {e}")))?;
+        let bundle_locales =
+            std::iter::once(&bundle.default_locale).chain(bundle.translations.keys());
+        for locale in bundle_locales {
+            if locales::normalize(locale).as_ref() != Some(locale) {
+                return Err(invalid(format!("invalid locale: {locale}")));
+            }
+        }
+        let test_case = read(TEST_CASE_NAME)?;
+        let mut attachments = vec![];
+        for name in &bundle.attachments {
+            let content = read(&format!("{ATTACHMENT_DIR}{name}"))?
+                .ok_or_else(|| invalid(format!("attachment {name} not found")))?;
+            attachments.push((name.clone(), content));
+        }
+
+        Ok((
+            bundle,
+            Files {
+                test_case,
+                attachments,
+            },
+        ))
+    }
+
+    /// Courses of the bundle existing on this instance, unknown ones are
+    /// skipped
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn find_courses<C: ConnectionTrait>(
+        &self,
+        db: &C,
+    ) -> ModelResult<Vec<courses::Model>> {
+        courses::Model::find_by_names(db, &self.courses).await
+    }
+
+    /// Create a hidden problem owned by `owner` from this bundle, without test
+    /// case. Tags missing on this instance are created, while unknown courses
+    /// are skipped.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn create_problem<C: ConnectionTrait + TransactionTrait>(
+        &self,
+        db: &C,
+        owner: users::Model,
+    ) -> ModelResult<problems::Model> {
+        let txn = db.begin().await?;
+
+        let courses = self
+            .find_courses(&txn)
+            .await?
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        let params = AddParams {
+            owner,
+            courses,
+            name: self.name.clone(),
+            // imported problems are reviewed before shown
+            status: Some(Visibility::Hidden),
+            description: self.description.clone(),
+            r#type: Type::from_i32(self.r#type),
+            allowed_language: Some(self.allowed_language),
+            quota: Some(self.quota),
+            default_locale: Some(self.default_locale.clone()),
+            tasks: self.tasks.clone(),
+        };
+        let problem = problems::Model::add(&txn, &params).await?;
+        for (locale, description) in &self.translations {
+            problem.set_description(&txn, locale, description).await?;
+        }
+
+        let mut problem_tags = tags::Model::find_by_names(&txn, &self.tags).await?;
+        for name in &self.tags {
+            if !problem_tags.iter().any(|t| t.name == *name) {
+                problem_tags.push(tags::Model::create(&txn, name).await?);
+            }
+        }
+        problem.set_tags(&txn, &problem_tags).await?;
+
+        if let Some(validator) = &self.validator {
+            validators::Model::set(&txn, problem.id, validator).await?;
+        }
+        for solution in &self.solutions {
+            solutions::Model::add(&txn, problem.id, solution).await?;
+        }
+        for template in &self.templates {
+            templates::Model::set(&txn, problem.id, template).await?;
+        }
+        // translations are added after the first revision
+        revisions::Model::record(&txn, &problem, problem.owner_id).await?;
+
+        txn.commit().await?;
+
+        Ok(problem)
+    }
+}
+
+/// Export a problem with its current test case and attachments as bundle
+/// archive
+///
+/// # Errors
+///
+/// When there is DB or storage error
+pub async fn export(ctx: &AppContext, problem: &problems::Model) -> loco_rs::Result<Vec<u8>> {
+    let bundle = Bundle::of_problem(&ctx.db, problem).await?;
+    let mut files = Files::default();
+    if let Some(path) = problem.test_case_path() {
+        files.test_case = Some(ctx.storage.download(&path).await?);
+    }
+    for attachment in attachments::Model::list_by_problem(&ctx.db, problem.id).await? {
+        let content: Vec<u8> = ctx.storage.download(&attachment.path()).await?;
+        files.attachments.push((attachment.name, content));
+    }
+
+    bundle.write(&files).map_err(wrap_error)
+}
+
+/// Import a bundle read by [`Bundle::read`] as a new hidden problem owned by
+/// `owner`. The caller should check that `owner` can create problems in
+/// [`Bundle::find_courses`]. The bundled test case and attachments are
+/// validated and stored as usual, all in one transaction; if that fails,
+/// nothing is left behind.
+///
+/// # Errors
+///
+/// - When the test case or attachments are invalid
+/// - When there is DB or storage error
+pub async fn import(
+    ctx: &AppContext,
+    owner: users::Model,
+    bundle: &Bundle,
+    files: Files,
+) -> loco_rs::Result<problems::Model> {
+    let max_size = Settings::from_ctx(ctx)?.attachment.max_size;
+    let mut checked_attachments = vec![];
+    for (name, content) in files.attachments {
+        let content_type = attachments::validate(&name, content.len(), max_size)
+            .map_err(|e| wrap_error(invalid(e.to_string())))?;
+        checked_attachments.push((name, content_type, content));
+    }
+
+    let uploader_id = owner.id;
+    let mut stored = vec![];
+    let txn = ctx.db.begin().await?;
+    let result = match store_problem(
+        ctx,
+        &txn,
+        bundle,
+        owner,
+        files.test_case,
+        checked_attachments,
+        &mut stored,
+    )
+    .await
+    {
+        Ok(problem) => txn
+            .commit()
+            .await
+            .map(|()| problem)
+            .map_err(|e| transform_db_error(e).into()),
+        Err(e) => Err(e),
+    };
+    let problem = match result {
+        Ok(p) => p,
+        Err(e) => {
+            // the transaction is rolled back, nothing refers to stored files
+            for path in stored {
+                if let Err(e) = ctx.storage.as_ref().delete(&path).await {
+                    tracing::warn!(path = %path.display(), error = %e, "failed to remove stored file");
+                }
+            }
+            return Err(e);
+        }
+    };
+    tracing::info!(
+        problem_id = problem.id,
+        uploader_id,
+        "problem imported from bundle"
+    );
+
+    Ok(problem)
+}
+
+/// Create problem of a bundle and store its files, pushing paths of stored
+/// files into `stored`
+async fn store_problem(
+    ctx: &AppContext,
+    txn: &DatabaseTransaction,
+    bundle: &Bundle,
+    owner: users::Model,
+    test_case: Option<Vec<u8>>,
+    checked_attachments: Vec<(String, &'static str, Vec<u8>)>,
+    stored: &mut Vec<PathBuf>,
+) -> loco_rs::Result<problems::Model> {
+    let uploader_id = owner.id;
+    let mut problem = bundle.create_problem(txn, owner).await?;
+    if let Some(test_case) = test_case {
+        problem = problem
+            .store_test_case(ctx, txn, uploader_id, Cursor::new(test_case))
+            .await?;
+        stored.extend(problem.test_case_path());
+    }
+    for (name, content_type, content) in checked_attachments {
+        let attachment = attachments::Model::add(
+            ctx,
+            txn,
+            problem.id,
+            uploader_id,
+            &name,
+            content_type,
+            content.into(),
+        )
+        .await?;
+        stored.push(attachment.path());
+    }
+
+    Ok(problem)
+}
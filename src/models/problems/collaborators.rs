@@ -0,0 +1,121 @@
+use loco_rs::model::{ModelError, ModelResult};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use sea_orm::{entity::prelude::*, ActiveValue, IntoActiveModel, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    _entities::{problem_collaborators, problems, users},
+    transform_db_error,
+};
+
+pub use problem_collaborators::{ActiveModel, Model};
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+/// What a collaborator can do besides the owner
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, FromPrimitive)]
+#[serde(rename_all = "lowercase")]
+#[repr(i8)]
+pub enum Role {
+    /// See the problem even if it's hidden, without its test case details
+    Viewer = 0,
+    /// Manage the problem like its owner, except its collaborators
+    Editor = 1,
+}
+
+impl Model {
+    /// Role of this collaborator, `None` if the stored value is unknown
+    #[must_use]
+    pub fn role(&self) -> Option<Role> {
+        Role::from_i32(self.role)
+    }
+}
+
+impl problems::Model {
+    /// Collaborators of this problem with their users, ordered by user id
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn collaborators<C: ConnectionTrait>(
+        &self,
+        db: &C,
+    ) -> ModelResult<Vec<(Model, Option<users::Model>)>> {
+        problem_collaborators::Entity::find()
+            .filter(problem_collaborators::Column::ProblemId.eq(self.id))
+            .find_also_related(users::Entity)
+            .order_by_asc(problem_collaborators::Column::UserId)
+            .all(db)
+            .await
+            .map_err(transform_db_error)
+    }
+
+    /// Add a user as collaborator, or change the role if already one. The
+    /// owner can't be a collaborator of their own problem.
+    ///
+    /// # Errors
+    ///
+    /// - When the user is the owner
+    /// - When there is DB error.
+    pub async fn set_collaborator<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        user_id: i32,
+        role: Role,
+    ) -> ModelResult<Model> {
+        if user_id == self.owner_id {
+            return Err(ModelError::Any(super::Error::OwnerCollaborator.into()));
+        }
+
+        let collaborator = problem_collaborators::Entity::find_by_id((self.id, user_id))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?;
+        let collaborator = match collaborator {
+            Some(c) => {
+                let mut c = c.into_active_model();
+                c.role = ActiveValue::set(role as i32);
+                c.update(db).await
+            }
+            None => {
+                ActiveModel {
+                    problem_id: ActiveValue::set(self.id),
+                    user_id: ActiveValue::set(user_id),
+                    role: ActiveValue::set(role as i32),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await
+            }
+        }
+        .map_err(transform_db_error)?;
+        tracing::info!(problem_id = self.id, user_id, ?role, "collaborator set");
+
+        Ok(collaborator)
+    }
+
+    /// Remove a collaborator of this problem
+    ///
+    /// # Errors
+    ///
+    /// - When the user is not a collaborator
+    /// - When there is DB error.
+    pub async fn remove_collaborator<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        user_id: i32,
+    ) -> ModelResult<()> {
+        let result = problem_collaborators::Entity::delete_by_id((self.id, user_id))
+            .exec(db)
+            .await
+            .map_err(transform_db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ModelError::EntityNotFound);
+        }
+
+        Ok(())
+    }
+}
@@ -1,15 +1,16 @@
+use super::markdown;
 use crate::models::is_unique_constraint_violation_err;
 
 pub use super::_entities::problem_descriptions::{ActiveModel, Model};
 use loco_rs::model::{ModelError, ModelResult};
-use sea_orm::{entity::prelude::*, ActiveValue};
-use serde::Deserialize;
+use sea_orm::{entity::prelude::*, ActiveValue, IntoActiveModel};
+use serde::{Deserialize, Serialize};
 
 impl ActiveModelBehavior for ActiveModel {
     // extend activemodel below (keep comment for generators)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddParams {
     pub description: String,
     pub input: String,
@@ -19,6 +20,15 @@ pub struct AddParams {
     pub sample_output: Vec<String>,
 }
 
+/// Description sections rendered as sanitized HTML
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    pub description: String,
+    pub input: String,
+    pub output: String,
+    pub hint: String,
+}
+
 impl Model {
     /// Create a problem without test case binary
     ///
@@ -47,4 +57,47 @@ impl Model {
 
         Ok(problem_description)
     }
+
+    /// Replace the content of a description
+    ///
+    /// # Errors
+    ///
+    /// When could not save the description into DB
+    pub async fn update<C: ConnectionTrait>(self, db: &C, params: &AddParams) -> ModelResult<Self> {
+        let mut description = self.into_active_model();
+        description.description = ActiveValue::set(params.description.to_string());
+        description.input = ActiveValue::set(params.input.to_string());
+        description.output = ActiveValue::set(params.output.to_string());
+        description.hint = ActiveValue::set(params.hint.to_string());
+        description.sample_input = ActiveValue::set(params.sample_input.clone());
+        description.sample_output = ActiveValue::set(params.sample_output.clone());
+
+        Ok(description.update(db).await?)
+    }
+
+    /// Parameters re-creating this description, e.g. when exporting or
+    /// restoring a problem
+    #[must_use]
+    pub fn to_params(&self) -> AddParams {
+        AddParams {
+            description: self.description.clone(),
+            input: self.input.clone(),
+            output: self.output.clone(),
+            hint: self.hint.clone(),
+            sample_input: self.sample_input.clone(),
+            sample_output: self.sample_output.clone(),
+        }
+    }
+
+    /// Render Markdown sections of this description, samples are plain text
+    /// thus left as is. Attachment links refer to problem `problem_id`.
+    #[must_use]
+    pub fn render(&self, problem_id: i32) -> Rendered {
+        Rendered {
+            description: markdown::render_problem(&self.description, problem_id),
+            input: markdown::render_problem(&self.input, problem_id),
+            output: markdown::render_problem(&self.output, problem_id),
+            hint: markdown::render_problem(&self.hint, problem_id),
+        }
+    }
 }
@@ -0,0 +1,144 @@
+// Materialize generated test cases
+
+use std::{collections::HashMap, fs, path::Path};
+
+use super::{
+    solutions,
+    test_case::{Generation, Manifest},
+    validators, BadTestCase,
+};
+use crate::{models::submissions::Language, sandbox};
+
+/// Time limit (ms) of generating / solving a single case
+const TIME_LIMIT: i32 = 10_000;
+/// Memory limit (KB) of generating / solving a single case
+const MEMORY_LIMIT: i32 = 512 * 1024;
+
+/// Produce `STDIN` & `STDOUT` of generated cases under an extracted test case
+/// directory. Inputs come from running generators with their arguments as
+/// stdin, and answers come from running the main solution on those inputs.
+///
+/// # Errors
+///
+/// - When any generator or the main solution fails to compile or run
+/// - When `validator` rejects any generated input
+pub fn materialize(
+    dir: &Path,
+    manifest: &Manifest,
+    main_solution: &solutions::Model,
+    validator: Option<&validators::Model>,
+) -> Result<(), BadTestCase> {
+    let generated_cases = manifest.generated_cases();
+    if generated_cases.is_empty() {
+        return Ok(());
+    }
+
+    let work_dir = tempfile::tempdir().map_err(|e| io_error(&e))?;
+    let args_path = work_dir.path().join("args");
+
+    // compile each generator once
+    let mut programs = HashMap::new();
+    for (_, generation) in &generated_cases {
+        if programs.contains_key(&generation.program) {
+            continue;
+        }
+        let source =
+            fs::read_to_string(dir.join(generation.source_path())).map_err(|e| io_error(&e))?;
+        let program_dir = work_dir
+            .path()
+            .join(format!("generator-{}", programs.len()));
+        compile(&program_dir, generation, &source)?;
+        programs.insert(generation.program.clone(), program_dir);
+    }
+
+    let solution_dir = work_dir.path().join("solution");
+    fs::create_dir(&solution_dir).map_err(|e| io_error(&e))?;
+    let compile_output =
+        sandbox::compile(&solution_dir, &main_solution.language, &main_solution.code)
+            .map_err(|e| BadTestCase::Custom(format!("main solution: {e}")))?;
+    if !compile_output.success {
+        return Err(BadTestCase::Custom(format!(
+            "main solution could not be compiled: {}",
+            compile_output.stderr
+        )));
+    }
+
+    for (case, generation) in &generated_cases {
+        let case_dir = dir.join(case);
+        fs::create_dir_all(&case_dir).map_err(|e| io_error(&e))?;
+
+        fs::write(&args_path, &generation.args).map_err(|e| io_error(&e))?;
+        let input = run(
+            &programs[&generation.program],
+            &generation.language()?,
+            &args_path,
+            &format!("generator {} on {case}", generation.program),
+        )?;
+        fs::write(case_dir.join("STDIN"), input).map_err(|e| io_error(&e))?;
+    }
+
+    // generated inputs are held to the same rules as uploaded ones
+    if let Some(validator) = validator {
+        let case_dirs = generated_cases
+            .iter()
+            .map(|(case, _)| case.clone())
+            .collect::<Vec<_>>();
+        let invalid_inputs = validator.validate_dir_inputs(dir, &case_dirs)?;
+        if !invalid_inputs.is_empty() {
+            return Err(BadTestCase::InvalidInput(invalid_inputs));
+        }
+    }
+
+    for (case, _) in &generated_cases {
+        let case_dir = dir.join(case);
+        let answer = run(
+            &solution_dir,
+            &main_solution.language,
+            &case_dir.join("STDIN"),
+            &format!("main solution on {case}"),
+        )?;
+        fs::write(case_dir.join("STDOUT"), answer).map_err(|e| io_error(&e))?;
+    }
+
+    Ok(())
+}
+
+fn io_error(e: &std::io::Error) -> BadTestCase {
+    BadTestCase::Custom(format!("failed to generate test case: {e}"))
+}
+
+fn compile(dir: &Path, generation: &Generation, source: &str) -> Result<(), BadTestCase> {
+    fs::create_dir(dir).map_err(|e| io_error(&e))?;
+    let output = sandbox::compile(dir, &generation.language()?, source)
+        .map_err(|e| BadTestCase::Custom(format!("generator {}: {e}", generation.program)))?;
+    if !output.success {
+        return Err(BadTestCase::Custom(format!(
+            "generator {} could not be compiled: {}",
+            generation.program, output.stderr
+        )));
+    }
+    Ok(())
+}
+
+/// Run a compiled program and return its stdout, `what` describes the run in
+/// error message.
+fn run(dir: &Path, language: &Language, stdin: &Path, what: &str) -> Result<String, BadTestCase> {
+    let output = sandbox::run(
+        dir,
+        &sandbox::RunConfig {
+            language,
+            stdin,
+            time_limit: TIME_LIMIT,
+            memory_limit: MEMORY_LIMIT,
+        },
+    )
+    .map_err(|e| BadTestCase::Custom(format!("{what}: {e}")))?;
+    if !output.exited_normally() {
+        return Err(BadTestCase::Custom(format!(
+            "{what} failed with {}: {}",
+            output.status, output.stderr
+        )));
+    }
+
+    Ok(output.stdout)
+}
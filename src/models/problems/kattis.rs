@@ -0,0 +1,523 @@
+// Import & export problems in Kattis problem package format, see
+// https://www.kattis.com/problem-package-format/
+
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, Read, Seek, Write},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use super::{archive, descriptions, tasks, test_case, BadTestCase};
+use crate::{
+    models::_entities::{problem_descriptions, problem_tasks, problems},
+    settings::ArchiveSettings,
+};
+
+/// Used when neither `problem.yaml` nor `.timelimit` declares a time limit, in
+/// seconds
+const DEFAULT_TIME_LIMIT: f64 = 1.0;
+/// Used when `problem.yaml` doesn't declare a memory limit, in MiB
+const DEFAULT_MEMORY_LIMIT: i32 = 256;
+/// Total score split among secret test groups without `accept_score`
+const FULL_SCORE: i32 = 100;
+
+const STATEMENT_DIRS: [&str; 2] = ["statement", "problem_statement"];
+const STATEMENT_FILES: [&str; 4] = [
+    "problem.en.md",
+    "problem.md",
+    "problem.en.tex",
+    "problem.tex",
+];
+
+/// A problem read from Kattis package
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub name: String,
+    pub description: descriptions::AddParams,
+    pub tasks: Vec<tasks::AddParams>,
+    /// Test case package in our zip layout, matching `tasks`
+    pub test_case: Vec<u8>,
+}
+
+/// `problem.yaml`, only fields we use
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<Name>,
+    #[serde(default)]
+    limits: Limits,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Name {
+    Single(String),
+    /// Names by language code
+    Localized(BTreeMap<String, String>),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Limits {
+    /// in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_limit: Option<f64>,
+    /// in MiB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    memory: Option<i32>,
+}
+
+/// `testdata.yaml` of a test group, only fields we use
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TestData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    accept_score: Option<i32>,
+}
+
+/// Input & answer of a test case
+struct Case {
+    input: Vec<u8>,
+    answer: Vec<u8>,
+}
+
+/// Secret test groups keyed by their directory
+type TestGroups = BTreeMap<String, Vec<Case>>;
+
+#[derive(Default)]
+struct Statement {
+    title: Option<String>,
+    description: String,
+    input: String,
+    output: String,
+    hint: String,
+}
+
+fn invalid(msg: impl Into<String>) -> BadTestCase {
+    BadTestCase::InvalidPackage(msg.into())
+}
+
+/// Read a Kattis problem package, in any format accepted by
+/// [`archive::read_files`].
+///
+/// Samples under `data/sample/` become a public task worth no score. Each
+/// test group under `data/secret/` becomes a task, cases directly under it
+/// form another one. Scores come from groups' `testdata.yaml`, or split
+/// evenly if any group doesn't declare one.
+///
+/// # Errors
+///
+/// When the archive is broken or exceeds `limits`, or it's not a valid
+/// Kattis package
+pub fn import<R: Read + Seek>(
+    input: &mut R,
+    limits: &ArchiveSettings,
+) -> Result<Package, BadTestCase> {
+    let files = archive::read_files(input, limits)?
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+
+    let config = files
+        .get("problem.yaml")
+        .ok_or_else(|| invalid("problem.yaml not found"))?;
+    let config = serde_yaml::from_slice::<Config>(config)
+        .map_err(|e| invalid(format!("invalid problem.yaml: {e}")))?;
+
+    let time_limit = match config.limits.time_limit {
+        Some(t) => t,
+        None => files
+            .get(".timelimit")
+            .map(|t| {
+                String::from_utf8_lossy(t)
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|e| invalid(format!("invalid .timelimit: {e}")))
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_TIME_LIMIT),
+    };
+    let valid = time_limit > 0.0 && time_limit <= 3600.0;
+    if !valid {
+        return Err(invalid(format!("invalid time limit: {time_limit}")));
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let time_limit = (time_limit * 1000.0).round() as i32;
+    let memory_limit = config
+        .limits
+        .memory
+        .unwrap_or(DEFAULT_MEMORY_LIMIT)
+        .checked_mul(1024)
+        .filter(|m| *m > 0)
+        .ok_or_else(|| invalid("invalid memory limit"))?;
+
+    let statement = STATEMENT_DIRS
+        .iter()
+        .flat_map(|d| STATEMENT_FILES.iter().map(move |f| format!("{d}/{f}")))
+        .find_map(|path| {
+            let content = String::from_utf8_lossy(files.get(&path)?).to_string();
+            Some(parse_statement(&content, path.ends_with(".tex")))
+        })
+        .unwrap_or_default();
+
+    let name = match config.name {
+        Some(Name::Single(name)) => Some(name),
+        Some(Name::Localized(mut names)) => {
+            names.remove("en").or_else(|| names.into_values().next())
+        }
+        None => None,
+    }
+    .or(statement.title)
+    .filter(|n| !n.trim().is_empty())
+    .ok_or_else(|| invalid("problem name not found"))?;
+
+    let (samples, groups) = test_groups(&files)?;
+    let scores = group_scores(&files, &groups);
+
+    let mut task_params = vec![];
+    let mut buf = Cursor::new(Vec::new());
+    let mut zip = ZipWriter::new(&mut buf);
+    let public_task = (!samples.is_empty()).then_some((&samples, 0, true));
+    let secret_tasks = groups
+        .values()
+        .zip(scores)
+        .map(|(cases, score)| (cases, score, false));
+    for (i, (cases, score, public)) in public_task.into_iter().chain(secret_tasks).enumerate() {
+        for (j, case) in cases.iter().enumerate() {
+            let dir = test_case::case_dir(i, j);
+            write_file(&mut zip, &format!("{dir}/STDIN"), &case.input)?;
+            write_file(&mut zip, &format!("{dir}/STDOUT"), &case.answer)?;
+        }
+        task_params.push(tasks::AddParams {
+            test_case_count: cases.len().try_into().unwrap_or(i32::MAX),
+            score,
+            time_limit,
+            memory_limit,
+            cases: vec![
+                tasks::CaseMeta {
+                    public,
+                    ..Default::default()
+                };
+                if public { cases.len() } else { 0 }
+            ],
+        });
+    }
+    zip.finish()?;
+    if task_params.is_empty() {
+        return Err(invalid("no test data found under data/"));
+    }
+
+    let text = |b: &[u8]| String::from_utf8_lossy(b).to_string();
+    Ok(Package {
+        name,
+        description: descriptions::AddParams {
+            description: statement.description,
+            input: statement.input,
+            output: statement.output,
+            hint: statement.hint,
+            sample_input: samples.iter().map(|c| text(&c.input)).collect(),
+            sample_output: samples.iter().map(|c| text(&c.answer)).collect(),
+        },
+        tasks: task_params,
+        test_case: buf.into_inner(),
+    })
+}
+
+/// Sample cases and secret test groups keyed by their directory, cases are
+/// ordered by file name
+fn test_groups(files: &BTreeMap<String, Vec<u8>>) -> Result<(Vec<Case>, TestGroups), BadTestCase> {
+    let mut samples = vec![];
+    let mut groups = TestGroups::new();
+    for (path, input) in files {
+        let Some(stem) = path
+            .strip_prefix("data/")
+            .and_then(|p| p.strip_suffix(".in"))
+        else {
+            continue;
+        };
+        let answer = files
+            .get(&format!("data/{stem}.ans"))
+            .ok_or_else(|| invalid(format!("answer of {path} not found")))?;
+        let case = Case {
+            input: input.clone(),
+            answer: answer.clone(),
+        };
+        match stem.split('/').collect::<Vec<_>>().as_slice() {
+            ["sample", _] => samples.push(case),
+            ["secret", _] => groups.entry("secret".to_string()).or_default().push(case),
+            ["secret", group, ..] => groups
+                .entry(format!("secret/{group}"))
+                .or_default()
+                .push(case),
+            _ => {}
+        }
+    }
+    Ok((samples, groups))
+}
+
+/// Score of each group in `groups`, in the same order
+fn group_scores(
+    files: &BTreeMap<String, Vec<u8>>,
+    groups: &BTreeMap<String, Vec<Case>>,
+) -> Vec<i32> {
+    let declared = groups
+        .keys()
+        .map(|g| {
+            let test_data = files.get(&format!("data/{g}/testdata.yaml"))?;
+            serde_yaml::from_slice::<TestData>(test_data)
+                .ok()?
+                .accept_score
+        })
+        .collect::<Option<Vec<_>>>();
+    if let Some(scores) = declared {
+        return scores;
+    }
+
+    let count = i32::try_from(groups.len()).unwrap_or(i32::MAX).max(1);
+    let mut scores = vec![FULL_SCORE / count; groups.len()];
+    if let Some(last) = scores.last_mut() {
+        *last += FULL_SCORE % count;
+    }
+    scores
+}
+
+/// Split statement into sections by their headings, either Markdown or LaTeX
+fn parse_statement(content: &str, is_tex: bool) -> Statement {
+    let mut title = None;
+    // description, input, output and hint
+    let mut sections: [String; 4] = Default::default();
+    let mut current = 0;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if is_tex {
+            if let Some(t) = tex_command(trimmed, "problemname") {
+                title = Some(t.to_string());
+                continue;
+            }
+            if matches!(trimmed, r"\begin{problem}" | r"\end{problem}") {
+                continue;
+            }
+        } else if title.is_none() && sections[0].trim().is_empty() {
+            if let Some(t) = trimmed.strip_prefix("# ") {
+                title = Some(t.trim().to_string());
+                continue;
+            }
+        }
+
+        let heading = if is_tex {
+            tex_command(trimmed, "section*").or_else(|| tex_command(trimmed, "section"))
+        } else {
+            trimmed
+                .strip_prefix('#')
+                .map(|h| h.trim_start_matches('#').trim())
+        };
+        let section = match heading.map(str::to_lowercase).as_deref() {
+            Some("input") => Some(1),
+            Some("output") => Some(2),
+            Some("hint" | "note" | "notes") => Some(3),
+            _ => None,
+        };
+        if let Some(section) = section {
+            current = section;
+            continue;
+        }
+        sections[current].push_str(line);
+        sections[current].push('\n');
+    }
+
+    let [description, input, output, hint] = sections.map(|s| s.trim().to_string());
+    Statement {
+        title,
+        description,
+        input,
+        output,
+        hint,
+    }
+}
+
+/// Argument of LaTeX command `\name{..}` occupying the whole line
+fn tex_command<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    line.strip_prefix('\\')?
+        .strip_prefix(name)?
+        .strip_prefix('{')?
+        .strip_suffix('}')
+}
+
+fn write_file<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), BadTestCase> {
+    zip.start_file(name, SimpleFileOptions::default())?;
+    zip.write_all(content)
+        .map_err(|e| BadTestCase::Custom(format!("failed to write {name}: {e}")))
+}
+
+fn read_file<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<Vec<u8>, BadTestCase> {
+    let mut file = archive.by_name(name)?;
+    let mut content = vec![];
+    file.read_to_end(&mut content)
+        .map_err(|e| BadTestCase::Custom(format!("failed to read {name}: {e}")))?;
+    Ok(content)
+}
+
+/// Write problem as a Kattis package zip. Tasks with only public cases and no
+/// score are written as samples, others as secret test groups.
+///
+/// # Errors
+///
+/// - When the test case could not be read
+/// - When the test case contains generated cases, which can't be exported
+pub fn export<R: Read + Seek>(
+    problem: &problems::Model,
+    description: &problem_descriptions::Model,
+    tasks: &[problem_tasks::Model],
+    test_case: Option<&mut ZipArchive<R>>,
+) -> Result<Vec<u8>, BadTestCase> {
+    let mut buf = Cursor::new(Vec::new());
+    let mut zip = ZipWriter::new(&mut buf);
+
+    let config = Config {
+        name: Some(Name::Single(problem.name.clone())),
+        limits: Limits {
+            time_limit: tasks
+                .iter()
+                .map(|t| t.time_limit)
+                .max()
+                .map(|t| f64::from(t) / 1000.0),
+            memory: tasks.iter().map(|t| t.memory_limit / 1024).max(),
+        },
+    };
+    let config = serde_yaml::to_string(&config)
+        .map_err(|e| BadTestCase::Custom(format!("failed to write problem.yaml: {e}")))?;
+    write_file(&mut zip, "problem.yaml", config.as_bytes())?;
+
+    let mut statement = format!(
+        "# {}\n\n{}\n\n## Input\n\n{}\n\n## Output\n\n{}\n",
+        problem.name, description.description, description.input, description.output
+    );
+    if !description.hint.is_empty() {
+        statement.push_str(&format!("\n## Hint\n\n{}\n", description.hint));
+    }
+    write_file(
+        &mut zip,
+        &format!("problem_statement/problem.{}.md", problem.default_locale),
+        statement.as_bytes(),
+    )?;
+
+    if let Some(archive) = test_case {
+        for (i, task) in tasks.iter().enumerate() {
+            let cases = task.case_meta();
+            let is_sample = task.score == 0 && cases.iter().all(|c| c.public);
+            let group = if is_sample {
+                "data/sample".to_string()
+            } else {
+                let group = format!("data/secret/task{i:02}");
+                let test_data = serde_yaml::to_string(&TestData {
+                    accept_score: Some(task.score),
+                })
+                .map_err(|e| BadTestCase::Custom(format!("failed to write testdata.yaml: {e}")))?;
+                write_file(
+                    &mut zip,
+                    &format!("{group}/testdata.yaml"),
+                    test_data.as_bytes(),
+                )?;
+                group
+            };
+            for (j, case) in cases.iter().enumerate() {
+                if case.generate.is_some() {
+                    return Err(invalid("generated test cases can't be exported"));
+                }
+                let dir = case.dir(i, j);
+                let input = read_file(archive, &format!("{dir}/STDIN"))?;
+                let answer = read_file(archive, &format!("{dir}/STDOUT"))?;
+                write_file(&mut zip, &format!("{group}/{i:02}{j:02}.in"), &input)?;
+                write_file(&mut zip, &format!("{group}/{i:02}{j:02}.ans"), &answer)?;
+            }
+        }
+    }
+    zip.finish()?;
+
+    Ok(buf.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buf);
+        for (name, content) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        buf.into_inner()
+    }
+
+    #[test]
+    fn test_import() {
+        let content = make_zip(&[
+            ("hello/problem.yaml", "name: Hello\nlimits:\n  memory: 64\n"),
+            ("hello/.timelimit", "2\n"),
+            (
+                "hello/problem_statement/problem.en.tex",
+                "\\problemname{Ignored}\nSay hello.\n\\section*{Input}\nNothing.\n\\section*{Output}\nHello.\n",
+            ),
+            ("hello/data/sample/1.in", ""),
+            ("hello/data/sample/1.ans", "Hello\n"),
+            ("hello/data/secret/a/1.in", "1"),
+            ("hello/data/secret/a/1.ans", "1"),
+            ("hello/data/secret/b/1.in", "2"),
+            ("hello/data/secret/b/1.ans", "2"),
+            ("hello/data/secret/b/2.in", "3"),
+            ("hello/data/secret/b/2.ans", "3"),
+        ]);
+        let package = import(&mut Cursor::new(content), &ArchiveSettings::default()).unwrap();
+
+        assert_eq!(package.name, "Hello");
+        assert_eq!(package.description.description, "Say hello.");
+        assert_eq!(package.description.input, "Nothing.");
+        assert_eq!(package.description.output, "Hello.");
+        assert_eq!(package.description.sample_output, vec!["Hello\n"]);
+        let tasks = package
+            .tasks
+            .iter()
+            .map(|t| (t.test_case_count, t.score, t.time_limit, t.memory_limit))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tasks,
+            vec![
+                (1, 0, 2000, 65536),
+                (1, 50, 2000, 65536),
+                (2, 50, 2000, 65536)
+            ]
+        );
+        assert!(package.tasks[0].cases[0].public);
+
+        let mut archive = ZipArchive::new(Cursor::new(package.test_case)).unwrap();
+        let io = test_case::read_case_io(&mut archive, &test_case::case_dir(2, 1)).unwrap();
+        assert_eq!((io.input.as_str(), io.answer.as_str()), ("3", "3"));
+    }
+
+    #[test]
+    fn test_import_rejects_missing_answer() {
+        let content = make_zip(&[("problem.yaml", "name: Hello\n"), ("data/secret/1.in", "1")]);
+        assert!(import(&mut Cursor::new(content), &ArchiveSettings::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_markdown_statement() {
+        let statement = parse_statement(
+            "# A + B\n\nAdd numbers.\n\n## Input\n\nTwo numbers.\n\n## Output\n\nThe sum.\n",
+            false,
+        );
+        assert_eq!(statement.title.as_deref(), Some("A + B"));
+        assert_eq!(statement.description, "Add numbers.");
+        assert_eq!(statement.input, "Two numbers.");
+        assert_eq!(statement.output, "The sum.");
+    }
+}
@@ -0,0 +1,269 @@
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{entity::prelude::*, ActiveValue, QueryOrder, TransactionTrait};
+
+use super::descriptions;
+use crate::models::{
+    _entities::{problem_descriptions, problem_translations, problems},
+    transform_db_error,
+};
+
+/// Locale of descriptions when none is given
+pub const DEFAULT_LOCALE: &str = "en";
+/// Max length of locale tags, e.g. `zh-tw`
+pub const MAX_LOCALE_LEN: usize = 16;
+
+/// Normalize a locale tag like `zh_TW` into `zh-tw`, `None` if it's not a
+/// valid tag
+#[must_use]
+pub fn normalize(locale: &str) -> Option<String> {
+    let locale = locale.trim().replace('_', "-").to_ascii_lowercase();
+    let valid = !locale.is_empty()
+        && locale.len() <= MAX_LOCALE_LEN
+        && locale
+            .split('-')
+            .all(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric()));
+    valid.then_some(locale)
+}
+
+/// Locales of an `Accept-Language` header, most preferred first. Wildcards
+/// and malformed entries are ignored.
+#[must_use]
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let locale = normalize(parts.next()?)?;
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (q > 0.0).then_some((locale, q))
+        })
+        .collect::<Vec<_>>();
+    // stable sort keeps header order for the same weight
+    weighted.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    weighted.into_iter().map(|(l, _)| l).collect()
+}
+
+fn primary(locale: &str) -> &str {
+    locale.split('-').next().unwrap_or(locale)
+}
+
+/// Pick the first of `preferred` locales that is `available`. Exact matches
+/// win, then ones sharing the language, e.g. `zh-tw` for `zh`.
+#[must_use]
+pub fn negotiate<'a>(available: &'a [String], preferred: &[String]) -> Option<&'a str> {
+    preferred.iter().find_map(|p| {
+        available
+            .iter()
+            .find(|a| *a == p)
+            .or_else(|| available.iter().find(|a| primary(a) == primary(p)))
+            .map(String::as_str)
+    })
+}
+
+impl problems::Model {
+    /// Locales this problem's description is available in, the default one
+    /// comes first
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn locales<C: ConnectionTrait>(&self, db: &C) -> ModelResult<Vec<String>> {
+        let translations = problem_translations::Entity::find()
+            .filter(problem_translations::Column::ProblemId.eq(self.id))
+            .order_by_asc(problem_translations::Column::Locale)
+            .all(db)
+            .await
+            .map_err(transform_db_error)?;
+
+        Ok(std::iter::once(self.default_locale.clone())
+            .chain(translations.into_iter().map(|t| t.locale))
+            .collect())
+    }
+
+    /// Description in a locale, `None` if not translated to it
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn description_in<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        locale: &str,
+    ) -> ModelResult<Option<descriptions::Model>> {
+        let id = if locale == self.default_locale {
+            self.description_id
+        } else {
+            let Some(translation) =
+                problem_translations::Entity::find_by_id((self.id, locale.to_string()))
+                    .one(db)
+                    .await
+                    .map_err(transform_db_error)?
+            else {
+                return Ok(None);
+            };
+            translation.description_id
+        };
+
+        problem_descriptions::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(transform_db_error)
+    }
+
+    /// Description in the best matching one of `preferred` locales, fallback
+    /// to the default description. Returns the chosen locale, the description
+    /// and all available locales.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn negotiate_description<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        preferred: &[String],
+    ) -> ModelResult<(String, descriptions::Model, Vec<String>)> {
+        let locales = self.locales(db).await?;
+        let locale = negotiate(&locales, preferred)
+            .unwrap_or(self.default_locale.as_str())
+            .to_string();
+        let description = self
+            .description_in(db, &locale)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        Ok((locale, description, locales))
+    }
+
+    /// Create or replace the description in a locale
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn set_description<C: ConnectionTrait + TransactionTrait>(
+        &self,
+        db: &C,
+        locale: &str,
+        params: &descriptions::AddParams,
+    ) -> ModelResult<descriptions::Model> {
+        let txn = db.begin().await?;
+        let description = match self.description_in(&txn, locale).await? {
+            Some(d) => d.update(&txn, params).await?,
+            None => {
+                let description = descriptions::Model::add(&txn, params).await?;
+                problem_translations::ActiveModel {
+                    problem_id: ActiveValue::set(self.id),
+                    locale: ActiveValue::set(locale.to_string()),
+                    description_id: ActiveValue::set(description.id),
+                }
+                .insert(&txn)
+                .await
+                .map_err(transform_db_error)?;
+                description
+            }
+        };
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(description)
+    }
+
+    /// Remove the description in a locale, the default one can't be removed
+    ///
+    /// # Errors
+    ///
+    /// - When the locale is the default or has no description
+    /// - When there is DB error.
+    pub async fn remove_description<C: ConnectionTrait + TransactionTrait>(
+        &self,
+        db: &C,
+        locale: &str,
+    ) -> ModelResult<()> {
+        if locale == self.default_locale {
+            return Err(ModelError::Any(super::Error::DefaultDescription.into()));
+        }
+        let translation = problem_translations::Entity::find_by_id((self.id, locale.to_string()))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        let txn = db.begin().await?;
+        let description_id = translation.description_id;
+        translation.delete(&txn).await.map_err(transform_db_error)?;
+        problem_descriptions::Entity::delete_by_id(description_id)
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(())
+    }
+
+    /// Translated descriptions, i.e. all but the default one, by locale
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn translations<C: ConnectionTrait>(
+        &self,
+        db: &C,
+    ) -> ModelResult<Vec<(String, descriptions::Model)>> {
+        let translations = problem_translations::Entity::find()
+            .filter(problem_translations::Column::ProblemId.eq(self.id))
+            .order_by_asc(problem_translations::Column::Locale)
+            .find_also_related(problem_descriptions::Entity)
+            .all(db)
+            .await
+            .map_err(transform_db_error)?;
+
+        Ok(translations
+            .into_iter()
+            .filter_map(|(t, d)| Some((t.locale, d?)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locales(locales: &[&str]) -> Vec<String> {
+        locales.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn normalize_locale() {
+        assert_eq!(normalize("zh_TW"), Some("zh-tw".to_string()));
+        assert_eq!(normalize(" en "), Some("en".to_string()));
+        assert_eq!(normalize("*"), None);
+        assert_eq!(normalize("en--us"), None);
+        assert_eq!(normalize(""), None);
+    }
+
+    #[test]
+    fn parse_accept_language_header() {
+        assert_eq!(
+            parse_accept_language("en;q=0.5, zh-TW, zh;q=0.8, *;q=0.1, fr;q=0"),
+            locales(&["zh-tw", "zh", "en"])
+        );
+        assert_eq!(parse_accept_language(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn negotiate_locale() {
+        let available = locales(&["en", "zh-tw"]);
+        assert_eq!(
+            negotiate(&available, &locales(&["zh-tw", "en"])),
+            Some("zh-tw")
+        );
+        assert_eq!(
+            negotiate(&available, &locales(&["zh", "en"])),
+            Some("zh-tw")
+        );
+        assert_eq!(negotiate(&available, &locales(&["en-us"])), Some("en"));
+        assert_eq!(negotiate(&available, &locales(&["ja", "en"])), Some("en"));
+        assert_eq!(negotiate(&available, &locales(&["ja"])), None);
+    }
+}
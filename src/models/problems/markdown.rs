@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use ammonia::Builder;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+/// Classes marking math, left for the frontend to typeset with KaTeX/MathJax
+const MATH_CLASSES: [&str; 3] = ["math", "math-inline", "math-display"];
+
+/// Scheme of links referring to attachments of the problem being rendered,
+/// e.g. `![figure](attachment:figure.png)`
+const ATTACHMENT_SCHEME: &str = "attachment:";
+
+/// Render Markdown with `$inline$` and `$$display$$` math into HTML safe to
+/// embed in pages
+///
+/// Raw HTML is allowed in the source, but anything able to run script, e.g.
+/// `<script>`, event handlers or `javascript:` links, is removed.
+#[must_use]
+pub fn render(source: &str) -> String {
+    render_with(source, None)
+}
+
+/// Same as [`render`], and links to `attachment:<name>` are resolved to the
+/// download route of the problem's attachment
+#[must_use]
+pub fn render_problem(source: &str, problem_id: i32) -> String {
+    render_with(source, Some(problem_id))
+}
+
+fn resolve_attachment(url: CowStr<'_>, problem_id: Option<i32>) -> CowStr<'_> {
+    match (problem_id, url.strip_prefix(ATTACHMENT_SCHEME)) {
+        (Some(id), Some(name)) => format!("/api/problems/{id}/attachments/{name}").into(),
+        _ => url,
+    }
+}
+
+fn render_with(source: &str, problem_id: Option<i32>) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH;
+    let events = Parser::new_ext(source, options).map(|event| match event {
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: resolve_attachment(dest_url, problem_id),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: resolve_attachment(dest_url, problem_id),
+            title,
+            id,
+        }),
+        _ => event,
+    });
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, events);
+
+    Builder::default()
+        .allowed_classes([("span", MATH_CLASSES.into_iter().collect::<HashSet<_>>())].into())
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_markdown() {
+        assert_eq!(
+            render("# Title\n\nSome **bold** text"),
+            "<h1>Title</h1>\n<p>Some <strong>bold</strong> text</p>\n"
+        );
+    }
+
+    #[test]
+    fn keep_math() {
+        let html = render("Sum of $a_i$ is\n\n$$\\sum_{i=1}^n a_i$$");
+        assert!(html.contains(r#"<span class="math math-inline">a_i</span>"#));
+        assert!(html.contains(r#"<span class="math math-display">\sum_{i=1}^n a_i</span>"#));
+    }
+
+    #[test]
+    fn remove_script() {
+        let html = render(
+            "<script>alert(1)</script>\n\n<img src=\"a.png\" onerror=\"alert(1)\">\n\n[link](javascript:alert(1))",
+        );
+        assert!(!html.contains("script"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("javascript"));
+        assert!(html.contains(r#"<img src="a.png">"#));
+    }
+
+    #[test]
+    fn resolve_attachments() {
+        assert_eq!(
+            render_problem("![fig](attachment:fig.png) [data](attachment:data.zip)", 3),
+            "<p><img src=\"/api/problems/3/attachments/fig.png\" alt=\"fig\"> <a href=\"/api/problems/3/attachments/data.zip\" rel=\"noopener noreferrer\">data</a></p>\n"
+        );
+        // unknown scheme without problem, dropped by sanitizer
+        assert_eq!(
+            render("[data](attachment:data.zip)"),
+            "<p><a rel=\"noopener noreferrer\">data</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn remove_unknown_classes() {
+        assert_eq!(
+            render(r#"<span class="math evil">x</span>"#),
+            "<p><span class=\"math\">x</span></p>\n"
+        );
+    }
+}
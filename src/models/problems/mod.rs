@@ -1,17 +1,48 @@
+pub mod archive;
+pub mod attachments;
+pub mod bundle;
+pub mod collaborators;
 pub mod descriptions;
+pub mod generators;
+pub mod kattis;
+pub mod locales;
+pub mod markdown;
+pub mod permission;
+pub mod revisions;
+pub mod search;
+pub mod solutions;
+pub mod stats;
 pub mod tasks;
+pub mod templates;
 pub mod test_case;
-
-use std::{collections::HashSet, path::PathBuf};
-
-use super::_entities::{self, prelude::Problems, problems};
+pub mod validators;
+pub mod versions;
+
+use std::{
+    io::{Read, Seek},
+    path::PathBuf,
+};
+
+use super::{
+    _entities::{self, course_problems, courses, prelude::Problems, problem_tags, problems, tags},
+    clamp_page_size,
+    users::Role,
+    SortOrder,
+};
 use crate::models::transform_db_error;
 
 pub use _entities::problems::{ActiveModel, Model};
-use axum::body::Bytes;
-use loco_rs::model::{ModelError, ModelResult};
+use loco_rs::{
+    app::AppContext,
+    model::{ModelError, ModelResult},
+};
 use num_derive::FromPrimitive;
-use sea_orm::{entity::prelude::*, ActiveValue, Order, QueryOrder, TransactionTrait};
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{Query, SelectStatement},
+    ActiveValue, Condition, IntoActiveModel, ItemsAndPagesNumber, JoinType, Order, QueryOrder,
+    QuerySelect, TransactionTrait,
+};
 use serde::Deserialize;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
@@ -20,6 +51,12 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 pub enum BadTestCase {
     #[error("error reading zip file: {0}")]
     ZipError(#[from] zip::result::ZipError),
+    #[error("invalid meta.json: {0}")]
+    InvalidManifest(String),
+    #[error("{} test input(s) rejected by validator", .0.len())]
+    InvalidInput(Vec<validators::InvalidInput>),
+    #[error("invalid problem package: {0}")]
+    InvalidPackage(String),
     #[error("{0}")]
     Custom(String),
 }
@@ -33,6 +70,10 @@ pub enum Error {
     BadTestCase(BadTestCase),
     #[error("test case hasn't been uploaded for problem")]
     NoTestCase,
+    #[error("default description can't be removed")]
+    DefaultDescription,
+    #[error("owner can't be a collaborator of the problem")]
+    OwnerCollaborator,
 }
 
 #[derive(Clone, Copy, Debug, Serialize_repr, Deserialize_repr, PartialEq, Eq, FromPrimitive)]
@@ -60,27 +101,93 @@ pub struct AddParams {
     /// list of course names
     pub courses: Vec<String>,
     pub name: String,
-    /// Problem status, control its visibility
+    /// Problem status, control its visibility, hidden if not given
     pub status: Option<Visibility>,
     /// Problem description struct
     pub description: descriptions::AddParams,
+    /// [`Type::Normal`] if not given
     pub r#type: Option<Type>,
     pub allowed_language: Option<i32>,
     pub quota: Option<i32>,
+    /// Locale of `description`, [`locales::DEFAULT_LOCALE`] if not given
+    pub default_locale: Option<String>,
     pub tasks: Vec<tasks::AddParams>,
 }
 
+/// Fields to change of a problem, `None` keeps the current value
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateParams {
+    pub name: Option<String>,
+    pub status: Option<Visibility>,
+    pub description: Option<descriptions::AddParams>,
+    pub r#type: Option<Type>,
+    pub allowed_language: Option<i32>,
+    pub quota: Option<i32>,
+    /// Replace all tasks. The uploaded test case is dropped if it no longer
+    /// fits the new tasks, see [`tasks::layout_changed`].
+    pub tasks: Option<Vec<tasks::AddParams>>,
+    /// Replace courses containing this problem, by course names
+    pub courses: Option<Vec<String>>,
+}
+
+/// How problems are matched against multiple tags
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Problems having all of the tags
+    #[default]
+    All,
+    /// Problems having any of the tags
+    Any,
+}
+
+/// Field to sort problem list by
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    Id,
+    Name,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListParams {
+    /// Only problems viewable by this user are listed, see
+    /// [`Model::permission`]
     pub viewer: _entities::users::Model,
-    pub offset: Option<usize>,
-    /// how many problems to return, -1 to return all
-    pub count: Option<i32>,
+    /// 1-based page number
+    pub page: u64,
+    pub page_size: u64,
+    #[serde(default)]
+    pub sort_by: SortBy,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Part of problem name, case-insensitive
     pub name: Option<String>,
+    /// Search text over problem name and description, results are ordered by
+    /// relevance before `sort_by`
+    pub search: Option<String>,
     pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
+    /// Name of course containing the problems
     pub course: Option<String>,
 }
 
+/// Problems in courses matching `course_cond`
+fn in_courses(course_cond: Condition) -> SelectStatement {
+    Query::select()
+        .column((course_problems::Entity, course_problems::Column::ProblemId))
+        .from(course_problems::Entity)
+        .inner_join(
+            courses::Entity,
+            Expr::col((courses::Entity, courses::Column::Id))
+                .equals((course_problems::Entity, course_problems::Column::CourseId)),
+        )
+        .cond_where(course_cond)
+        .to_owned()
+}
+
 impl _entities::problems::Model {
     /// Create a problem without test case binary
     ///
@@ -92,8 +199,6 @@ impl _entities::problems::Model {
         db: &C,
         params: &AddParams,
     ) -> ModelResult<Self> {
-        use super::users::Role;
-
         let txn = db.begin().await?;
 
         if !matches!(params.owner.role, Role::Teacher | Role::Admin) {
@@ -105,17 +210,19 @@ impl _entities::problems::Model {
         let problem = ActiveModel {
             name: ActiveValue::set(params.name.to_string()),
             owner_id: ActiveValue::set(params.owner.id),
-            r#type: params
-                .r#type
-                .map_or(ActiveValue::NotSet, |t| ActiveValue::set(t as i32)),
-            status: params
-                .status
-                .map_or(ActiveValue::NotSet, |s| ActiveValue::set(s as i32)),
+            r#type: ActiveValue::set(params.r#type.unwrap_or(Type::Normal) as i32),
+            status: ActiveValue::set(params.status.unwrap_or(Visibility::Hidden) as i32),
             description_id: ActiveValue::set(description.id),
             allowed_language: params
                 .allowed_language
                 .map_or(ActiveValue::NotSet, ActiveValue::set),
             quota: params.quota.map_or(ActiveValue::NotSet, ActiveValue::set),
+            default_locale: ActiveValue::set(
+                params
+                    .default_locale
+                    .clone()
+                    .unwrap_or_else(|| locales::DEFAULT_LOCALE.to_string()),
+            ),
             ..Default::default()
         }
         .insert(&txn)
@@ -124,40 +231,159 @@ impl _entities::problems::Model {
 
         tasks::Model::add_many(&txn, problem.id, &params.tasks).await?;
 
+        let courses = courses::Model::find_by_names(&txn, &params.courses).await?;
+        problem.set_courses(&txn, &courses).await?;
+
+        revisions::Model::record(&txn, &problem, params.owner.id).await?;
+
         txn.commit().await.map_err(transform_db_error)?;
 
         Ok(problem)
     }
 
-    /// List problems
+    /// List a page of problems, along with the total number of matched
+    /// problems and pages
     ///
     /// # Errors
     ///
     /// When cloud not query problems from DB
-    pub async fn list<C: ConnectionTrait>(db: &C, params: &ListParams) -> ModelResult<Vec<Self>> {
-        // TODO: check course && tags
+    pub async fn list<C: ConnectionTrait>(
+        db: &C,
+        params: &ListParams,
+    ) -> ModelResult<(Vec<Self>, ItemsAndPagesNumber)> {
+        let sort_by = match params.sort_by {
+            SortBy::Id => problems::Column::Id,
+            SortBy::Name => problems::Column::Name,
+        };
+        let mut q = Problems::find()
+            .filter(problems::Column::DeletedAt.is_null())
+            .filter(permission::viewable_condition(&params.viewer));
 
-        let mut q = Problems::find().order_by(problems::Column::Id, Order::Asc);
+        if let Some(course) = &params.course {
+            let named = Expr::col((courses::Entity, courses::Column::Name)).eq(course.as_str());
+            q = q.filter(problems::Column::Id.in_subquery(in_courses(Condition::all().add(named))));
+        }
 
-        // TODO: fuzz search
         if let Some(name) = &params.name {
-            q = q.filter(problems::Column::Name.eq(name));
+            q = q.filter(search::name_condition(name));
         }
 
-        let problems = q.all(db).await?.into_iter();
-        // TODO: permission check
+        if let Some(text) = params
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+        {
+            let (cond, rank) = search::search_condition(db.get_database_backend(), text);
+            q = q
+                .join(
+                    JoinType::InnerJoin,
+                    problems::Relation::ProblemDescriptions.def(),
+                )
+                .filter(cond)
+                .order_by(rank, Order::Desc);
+        }
 
-        let offset = params.offset.unwrap_or(0);
-        let count = params.count.unwrap_or(10);
-        #[allow(clippy::cast_sign_loss)]
-        let count = if count < 0 {
-            usize::MAX
-        } else {
-            count as usize
-        };
-        let problems = problems.skip(offset).take(count);
+        if let Some(names) = params.tags.as_ref().filter(|t| !t.is_empty()) {
+            let mut names = names.clone();
+            names.sort();
+            names.dedup();
+
+            let mut tagged = Query::select();
+            tagged
+                .column((problem_tags::Entity, problem_tags::Column::ProblemId))
+                .from(problem_tags::Entity)
+                .inner_join(
+                    tags::Entity,
+                    Expr::col((tags::Entity, tags::Column::Id))
+                        .equals((problem_tags::Entity, problem_tags::Column::TagId)),
+                )
+                .and_where(Expr::col((tags::Entity, tags::Column::Name)).is_in(names.clone()));
+            if params.tag_match == TagMatch::All {
+                // a tag is attached to a problem at most once, so counting
+                // matched rows tells whether all tags are present
+                tagged
+                    .group_by_col((problem_tags::Entity, problem_tags::Column::ProblemId))
+                    .and_having(
+                        Expr::expr(
+                            Expr::col((problem_tags::Entity, problem_tags::Column::TagId)).count(),
+                        )
+                        .eq(i64::try_from(names.len()).unwrap_or(i64::MAX)),
+                    );
+            }
+            q = q.filter(problems::Column::Id.in_subquery(tagged.to_owned()));
+        }
+
+        let q = q
+            .order_by(sort_by, params.order.into())
+            // keep pages stable when sorted values are equal
+            .order_by(problems::Column::Id, Order::Asc);
+        let pages = q.paginate(db, clamp_page_size(params.page_size));
+        let total = pages.num_items_and_pages().await?;
+        let problems = pages.fetch_page(params.page.saturating_sub(1)).await?;
 
-        Ok(problems.collect())
+        Ok((problems, total))
+    }
+
+    /// Update a problem
+    ///
+    /// # Errors
+    ///
+    /// When could not save the problem into DB
+    pub async fn update<C: ConnectionTrait + TransactionTrait>(
+        self,
+        db: &C,
+        params: &UpdateParams,
+    ) -> ModelResult<Self> {
+        let txn = db.begin().await?;
+
+        if let Some(description) = &params.description {
+            self.find_related(_entities::problem_descriptions::Entity)
+                .one(&txn)
+                .await
+                .map_err(transform_db_error)?
+                .ok_or(ModelError::EntityNotFound)?
+                .update(&txn, description)
+                .await?;
+        }
+
+        let mut test_case_outdated = false;
+        if let Some(names) = &params.courses {
+            let courses = courses::Model::find_by_names(&txn, names).await?;
+            self.set_courses(&txn, &courses).await?;
+        }
+
+        if let Some(new_tasks) = &params.tasks {
+            let old_tasks = self.tasks(&txn).await?;
+            test_case_outdated = tasks::layout_changed(&old_tasks, new_tasks);
+            self.replace_tasks(&txn, new_tasks).await?;
+        }
+
+        let mut problem = self.into_active_model();
+        if let Some(name) = &params.name {
+            problem.name = ActiveValue::set(name.to_string());
+        }
+        if let Some(status) = params.status {
+            problem.status = ActiveValue::set(status as i32);
+        }
+        if let Some(t) = params.r#type {
+            problem.r#type = ActiveValue::set(t as i32);
+        }
+        if let Some(allowed_language) = params.allowed_language {
+            problem.allowed_language = ActiveValue::set(allowed_language);
+        }
+        if let Some(quota) = params.quota {
+            problem.quota = ActiveValue::set(quota);
+        }
+        if test_case_outdated {
+            problem.test_case_id = ActiveValue::set(None);
+            problem.verification = ActiveValue::set(None);
+        }
+        let problem = problem.update(&txn).await.map_err(transform_db_error)?;
+
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(problem)
     }
 
     /// Find a problem by its primary id
@@ -175,6 +401,114 @@ impl _entities::problems::Model {
         p.ok_or(ModelError::EntityNotFound)
     }
 
+    /// Same as [`Self::find_by_id`], but deleted problems are not found
+    ///
+    /// # Errors
+    ///
+    /// - When cloud not query problem from DB
+    /// - When the problem with id does not exist or is deleted
+    pub async fn find_active_by_id<C: ConnectionTrait>(db: &C, id: i32) -> ModelResult<Self> {
+        let p = Problems::find()
+            .filter(problems::Column::Id.eq(id))
+            .filter(problems::Column::DeletedAt.is_null())
+            .one(db)
+            .await
+            .map_err(transform_db_error)?;
+        p.ok_or(ModelError::EntityNotFound)
+    }
+
+    /// Replace all courses containing this problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn set_courses<C: ConnectionTrait + TransactionTrait>(
+        &self,
+        db: &C,
+        courses: &[courses::Model],
+    ) -> ModelResult<()> {
+        let txn = db.begin().await?;
+        course_problems::Entity::delete_many()
+            .filter(course_problems::Column::ProblemId.eq(self.id))
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        if !courses.is_empty() {
+            course_problems::Entity::insert_many(courses.iter().map(|c| {
+                course_problems::ActiveModel {
+                    course_id: ActiveValue::set(c.id),
+                    problem_id: ActiveValue::set(self.id),
+                }
+            }))
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        }
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(())
+    }
+
+    /// Names of courses containing this problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn course_names<C: ConnectionTrait>(&self, db: &C) -> ModelResult<Vec<String>> {
+        let courses = self
+            .find_related(courses::Entity)
+            .order_by(courses::Column::Name, Order::Asc)
+            .all(db)
+            .await?;
+
+        Ok(courses.into_iter().map(|c| c.name).collect())
+    }
+
+    /// Replace all tags of this problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn set_tags<C: ConnectionTrait + TransactionTrait>(
+        &self,
+        db: &C,
+        tags: &[tags::Model],
+    ) -> ModelResult<()> {
+        let txn = db.begin().await?;
+        problem_tags::Entity::delete_many()
+            .filter(problem_tags::Column::ProblemId.eq(self.id))
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        if !tags.is_empty() {
+            problem_tags::Entity::insert_many(tags.iter().map(|t| problem_tags::ActiveModel {
+                problem_id: ActiveValue::set(self.id),
+                tag_id: ActiveValue::set(t.id),
+            }))
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        }
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(())
+    }
+
+    /// Names of tags attached to this problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn tag_names<C: ConnectionTrait>(&self, db: &C) -> ModelResult<Vec<String>> {
+        let tags = self
+            .find_related(tags::Entity)
+            .order_by(tags::Column::Name, Order::Asc)
+            .all(db)
+            .await?;
+
+        Ok(tags.into_iter().map(|t| t.name).collect())
+    }
+
     /// Find problem tasks from DB
     ///
     /// # Errors
@@ -190,89 +524,280 @@ impl _entities::problems::Model {
         Ok(tasks)
     }
 
-    /// Validate test case binary according to current problem test case meta.
+    /// Validate test case binary. If the package contains a manifest, it's
+    /// validated against the manifest, which is returned for the caller to
+    /// update problem tasks. Otherwise, it's validated against current problem
+    /// tasks.
     ///
     /// # Errors
     ///
     /// - When the given binary is not a zip file
+    /// - When the manifest is invalid
     /// - When the zip file contains invalid files
     /// - When there is missing/extra files inside zip
-    pub async fn validate_test_case<C: ConnectionTrait>(
+    /// - When there are generated cases but no main solution, or they could
+    ///   not be generated
+    /// - When any input is rejected by problem's validator
+    pub async fn validate_test_case<C: ConnectionTrait, R: Read + Seek + Send>(
         &self,
         db: &C,
-        test_case: &Bytes,
-    ) -> loco_rs::Result<()> {
-        let wrap_zip_error =
-            |e| loco_rs::Error::Any(Box::new(Error::BadTestCase(BadTestCase::ZipError(e))));
-        let custom_error =
-            |e| loco_rs::Error::Any(Box::new(Error::BadTestCase(BadTestCase::Custom(e))));
-
-        let cursor = std::io::Cursor::new(test_case);
-        let mut zipfile = zip::ZipArchive::new(cursor).map_err(wrap_zip_error)?;
-
-        // TODO: valiadte according to problem test case meta
-        let tasks = self.tasks(db).await?;
-        let mut expected_input_output = tasks
-            .iter()
-            .enumerate()
-            .flat_map(|(i, t)| {
-                (0..t.test_case_count).flat_map(move |j| {
-                    vec![
-                        format!("test-case/{i:02}{j:02}/STDIN"),
-                        format!("test-case/{i:02}{j:02}/STDOUT"),
-                    ]
-                })
-            })
-            .collect::<HashSet<_>>();
-
-        for i in 0..zipfile.len() {
-            let file = zipfile.by_index(i).map_err(wrap_zip_error)?;
-            if file.is_symlink() {
-                return Err(custom_error(format!(
-                    "symlink is not allowed: {}",
-                    file.name()
+        test_case: R,
+    ) -> loco_rs::Result<Option<test_case::Manifest>> {
+        let wrap_error = |e| loco_rs::Error::Any(Box::new(Error::BadTestCase(e)));
+
+        let mut zipfile =
+            zip::ZipArchive::new(test_case).map_err(|e| wrap_error(BadTestCase::ZipError(e)))?;
+
+        let manifest = test_case::Manifest::from_archive(&mut zipfile).map_err(wrap_error)?;
+        let (case_dirs, extra_files) = match &manifest {
+            Some(m) => (m.packaged_case_dirs(), m.extra_files()),
+            None => (
+                self.tasks(db)
+                    .await?
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, t)| {
+                        t.case_meta()
+                            .into_iter()
+                            .enumerate()
+                            .map(move |(j, c)| c.dir(i, j))
+                    })
+                    .collect(),
+                vec![],
+            ),
+        };
+        test_case::validate_files(&mut zipfile, &case_dirs, &extra_files).map_err(wrap_error)?;
+
+        let has_generated_cases = manifest
+            .as_ref()
+            .is_some_and(|m| !m.generated_cases().is_empty());
+        let main_solution = if has_generated_cases {
+            let Some(main_solution) = solutions::Model::find_main(db, self.id).await? else {
+                return Err(wrap_error(BadTestCase::Custom(
+                    "generated test cases require a main solution".to_string(),
                 )));
+            };
+            Some(main_solution)
+        } else {
+            None
+        };
+        let validator = validators::Model::find_by_problem(db, self.id).await?;
+        if validator.is_none() && main_solution.is_none() {
+            return Ok(manifest);
+        }
+
+        // validator and generators run in sandbox, do it on a blocking thread
+        // with an owned copy of the package
+        let mut package = vec![];
+        let mut test_case = zipfile.into_inner();
+        test_case
+            .rewind()
+            .and_then(|()| test_case.read_to_end(&mut package))
+            .map_err(|e| loco_rs::Error::Any(e.into()))?;
+        let generation_manifest = manifest.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut zipfile = zip::ZipArchive::new(std::io::Cursor::new(package))?;
+            if let Some(validator) = &validator {
+                let invalid_inputs = validator.validate_inputs(&mut zipfile, &case_dirs)?;
+                if !invalid_inputs.is_empty() {
+                    return Err(BadTestCase::InvalidInput(invalid_inputs));
+                }
             }
-            // skip directory for now
-            if file.is_dir() {
-                continue;
+            // generate cases once here, so that a broken generator rejects the
+            // upload instead of failing the first judge
+            if let (Some(manifest), Some(main_solution)) = (&generation_manifest, &main_solution) {
+                let dir = tempfile::tempdir().map_err(|e| {
+                    BadTestCase::Custom(format!("failed to extract test case: {e}"))
+                })?;
+                zipfile.extract(dir.path())?;
+                generators::materialize(dir.path(), manifest, main_solution, validator.as_ref())?;
             }
-            let name = file.enclosed_name().ok_or_else(|| {
-                custom_error(format!("invalid path found in zip file: {}", file.name()))
-            })?;
-            let name = name.to_str().ok_or_else(|| {
-                custom_error(format!(
-                    "invalid path found in zip file (maybe non-UTF8 path?): {}",
-                    file.name()
-                ))
-            })?;
-
-            if !expected_input_output.remove(name) {
-                return Err(custom_error(format!(
-                    "duplicated or extra file found: {}",
-                    file.name()
-                )));
+            Ok(())
+        })
+        .await
+        .map_err(|e| loco_rs::Error::Any(e.into()))?
+        .map_err(wrap_error)?;
+
+        Ok(manifest)
+    }
+
+    /// Validate and store a test case package as the current test case of
+    /// this problem, recording it as a new version. Problem tasks are
+    /// replaced if the package contains a manifest. The package is stored
+    /// first, then DB changes are made in one transaction, so a failure
+    /// leaves the problem untouched. `db` could be a transaction of the
+    /// caller, who should remove the stored package if it's rolled back.
+    ///
+    /// # Errors
+    ///
+    /// - When the package is invalid, see [`Model::validate_test_case`]
+    /// - When there is DB or storage error
+    pub async fn store_test_case<C, R>(
+        self,
+        ctx: &AppContext,
+        db: &C,
+        uploader_id: i32,
+        mut content: R,
+    ) -> loco_rs::Result<Self>
+    where
+        C: ConnectionTrait + TransactionTrait,
+        R: Read + Seek + Send,
+    {
+        let manifest = self.validate_test_case(db, &mut content).await?;
+        tracing::info!(problem_id = self.id, "test case validated");
+
+        // app's storage only takes whole content, read it after validation
+        let mut file_content = vec![];
+        content
+            .rewind()
+            .and_then(|()| content.read_to_end(&mut file_content))
+            .map_err(|e| loco_rs::Error::Any(e.into()))?;
+
+        let test_case_id = uuid::Uuid::new_v4().to_string();
+        let path = test_case::storage_path(&test_case_id);
+        ctx.storage
+            .as_ref()
+            .upload(path.as_path(), &axum::body::Bytes::from(file_content))
+            .await?;
+        tracing::info!(test_case_id, "test case uploaded");
+
+        let result = self
+            .switch_test_case(db, &test_case_id, uploader_id, manifest.as_ref())
+            .await;
+        if result.is_err() {
+            // nothing refers to the package yet
+            if let Err(e) = ctx.storage.as_ref().delete(path.as_path()).await {
+                tracing::warn!(test_case_id, error = %e, "failed to remove stored test case");
             }
         }
 
-        if !expected_input_output.is_empty() {
-            return Err(custom_error(format!(
-                "missing files: {}",
-                expected_input_output
-                    .into_iter()
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )));
+        Ok(result?)
+    }
+
+    /// Make a stored test case package current, replacing tasks from its
+    /// manifest and recording the new version, all in one transaction. A shown
+    /// problem is hidden if it's no longer verified.
+    async fn switch_test_case<C: ConnectionTrait + TransactionTrait>(
+        self,
+        db: &C,
+        test_case_id: &str,
+        uploader_id: i32,
+        manifest: Option<&test_case::Manifest>,
+    ) -> ModelResult<Self> {
+        let txn = db.begin().await?;
+        if let Some(manifest) = manifest {
+            let tasks = self.replace_tasks(&txn, &manifest.task_params()).await?;
+            tracing::info!(
+                problem_id = self.id,
+                task_count = tasks.len(),
+                "problem tasks updated from test case manifest"
+            );
         }
 
-        Ok(())
+        let prob = self
+            .into_active_model()
+            .update_test_case_id(&txn, Some(test_case_id.to_string()))
+            .await?
+            .hide_if_unverified(&txn)
+            .await?;
+        let tasks = prob.tasks(&txn).await?;
+        versions::Model::add(&txn, prob.id, test_case_id, uploader_id, &tasks).await?;
+        revisions::Model::record(&txn, &prob, uploader_id).await?;
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(prob)
+    }
+
+    /// Replace all tasks of this problem.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn replace_tasks<C: ConnectionTrait + TransactionTrait>(
+        &self,
+        db: &C,
+        params: &[tasks::AddParams],
+    ) -> ModelResult<Vec<tasks::Model>> {
+        let txn = db.begin().await?;
+        _entities::problem_tasks::Entity::delete_many()
+            .filter(_entities::problem_tasks::Column::ProblemId.eq(self.id))
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        let tasks = tasks::Model::add_many(&txn, self.id, params).await?;
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(tasks)
+    }
+
+    /// Make a previously uploaded test case current again, restoring the
+    /// tasks it was uploaded with. A shown problem is hidden if it's no longer
+    /// verified.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn rollback_test_case<C: ConnectionTrait + TransactionTrait>(
+        self,
+        db: &C,
+        version: &versions::Model,
+    ) -> ModelResult<Self> {
+        let txn = db.begin().await?;
+        self.replace_tasks(&txn, &version.task_params()).await?;
+        let prob = self
+            .into_active_model()
+            .update_test_case_id(&txn, Some(version.test_case_id.clone()))
+            .await?
+            .hide_if_unverified(&txn)
+            .await?;
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(prob)
+    }
+
+    /// Latest verification of reference solutions, if any
+    #[must_use]
+    pub fn verification(&self) -> Option<solutions::Verification> {
+        self.verification
+            .clone()
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    /// Whether the problem is allowed to be shown regarding its reference
+    /// solutions. If it has any, they must have passed a verification against
+    /// current test case.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn is_verified<C: ConnectionTrait>(&self, db: &C) -> ModelResult<bool> {
+        if solutions::Model::list_by_problem(db, self.id)
+            .await?
+            .is_empty()
+        {
+            return Ok(true);
+        }
+
+        Ok(self
+            .verification()
+            .is_some_and(|v| Some(&v.test_case_id) == self.test_case_id.as_ref() && v.passed()))
+    }
+
+    /// Hide a shown problem which is no longer verified, e.g. its test case
+    /// was switched. It could be shown again after passing verification.
+    async fn hide_if_unverified<C: ConnectionTrait>(self, db: &C) -> ModelResult<Self> {
+        if self.status != Visibility::Show as i32 || self.is_verified(db).await? {
+            return Ok(self);
+        }
+        tracing::info!(problem_id = self.id, "unverified problem hidden");
+        self.into_active_model()
+            .update_status(db, Visibility::Hidden)
+            .await
     }
 
     #[must_use]
     pub fn test_case_path(&self) -> Option<PathBuf> {
-        self.test_case_id
-            .as_ref()
-            .map(|i| PathBuf::from("test-case").join(format!("{i}.zip")))
+        self.test_case_id.as_deref().map(test_case::storage_path)
     }
 }
 
@@ -290,6 +815,49 @@ impl ActiveModel {
         self.test_case_id = ActiveValue::set(test_case_id);
         Ok(self.update(db).await?)
     }
+
+    /// Update verification of reference solutions, `None` to clear it.
+    ///
+    /// # Errors
+    ///
+    /// When has DB query error.
+    pub async fn update_verification(
+        mut self,
+        db: &impl ConnectionTrait,
+        verification: Option<&solutions::Verification>,
+    ) -> ModelResult<Model> {
+        let verification = verification
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(Box::from)?;
+        self.verification = ActiveValue::set(verification);
+        Ok(self.update(db).await?)
+    }
+
+    /// Soft delete a problem. It's hidden from everywhere but its submissions
+    /// are kept.
+    ///
+    /// # Errors
+    ///
+    /// When has DB query error.
+    pub async fn soft_delete(mut self, db: &impl ConnectionTrait) -> ModelResult<Model> {
+        self.deleted_at = ActiveValue::set(Some(chrono::Utc::now().naive_utc()));
+        Ok(self.update(db).await?)
+    }
+
+    /// Update problem visibility
+    ///
+    /// # Errors
+    ///
+    /// When has DB query error.
+    pub async fn update_status(
+        mut self,
+        db: &impl ConnectionTrait,
+        status: Visibility,
+    ) -> ModelResult<Model> {
+        self.status = ActiveValue::set(status as i32);
+        Ok(self.update(db).await?)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,131 @@
+// Who can see or manage a problem
+
+use loco_rs::model::ModelResult;
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{Query, SelectStatement},
+    Condition,
+};
+use serde::Deserialize;
+
+use super::{collaborators::Role as CollaboratorRole, in_courses, Model, Visibility};
+use crate::models::{
+    _entities::{
+        course_members, courses, prelude::Problems, problem_collaborators, problems, users,
+    },
+    users::Role,
+};
+
+/// What a user can do with a problem, ordered from the least to the most
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    None,
+    /// Read the problem without its test case details
+    View,
+    /// Read everything of the problem and edit it
+    Manage,
+}
+
+/// Where users of a role can create problems, see `problem.create` in
+/// settings
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CreatePolicy {
+    /// In any course or none
+    Anywhere,
+    /// Only in courses taught by the creator, at least one is required
+    TaughtCourses,
+    Deny,
+}
+
+/// Whether a user can create a problem placed in `courses` under `policy`.
+/// Students can never create problems, whatever the policy is.
+#[must_use]
+pub fn can_create(policy: CreatePolicy, user: &users::Model, courses: &[courses::Model]) -> bool {
+    if !matches!(user.role, Role::Teacher | Role::Admin) {
+        return false;
+    }
+
+    match policy {
+        CreatePolicy::Anywhere => true,
+        CreatePolicy::TaughtCourses => {
+            !courses.is_empty() && courses.iter().all(|c| c.teacher_id == user.id)
+        }
+        CreatePolicy::Deny => false,
+    }
+}
+
+/// Problems the user collaborates on with at least `role`
+fn collaborated(user: &users::Model, role: CollaboratorRole) -> SelectStatement {
+    Query::select()
+        .column(problem_collaborators::Column::ProblemId)
+        .from(problem_collaborators::Entity)
+        .and_where(problem_collaborators::Column::UserId.eq(user.id))
+        .and_where(problem_collaborators::Column::Role.gte(role as i32))
+        .to_owned()
+}
+
+/// Condition of problems a user can manage: admins manage all problems,
+/// others manage their own problems, those in courses they teach and those
+/// they are editors of.
+fn manageable_condition(user: &users::Model) -> Condition {
+    if user.role == Role::Admin {
+        return Condition::all();
+    }
+
+    let taught = Expr::col((courses::Entity, courses::Column::TeacherId)).eq(user.id);
+    Condition::any()
+        .add(problems::Column::OwnerId.eq(user.id))
+        .add(problems::Column::Id.in_subquery(in_courses(Condition::all().add(taught))))
+        .add(problems::Column::Id.in_subquery(collaborated(user, CollaboratorRole::Editor)))
+}
+
+/// Condition of problems a user can view: the manageable ones, the ones the
+/// user collaborates on, plus shown problems in courses the user belongs to.
+/// Other hidden problems are only visible to who can manage them.
+pub fn viewable_condition(user: &users::Model) -> Condition {
+    let joined = Query::select()
+        .column(course_members::Column::CourseId)
+        .from(course_members::Entity)
+        .and_where(course_members::Column::UserId.eq(user.id))
+        .to_owned();
+
+    let in_joined = Expr::col((courses::Entity, courses::Column::Id)).in_subquery(joined);
+    let shown_in_joined = Condition::all()
+        .add(problems::Column::Status.eq(Visibility::Show as i32))
+        .add(problems::Column::Id.in_subquery(in_courses(Condition::all().add(in_joined))));
+
+    Condition::any()
+        .add(manageable_condition(user))
+        .add(problems::Column::Id.in_subquery(collaborated(user, CollaboratorRole::Viewer)))
+        .add(shown_in_joined)
+}
+
+impl Model {
+    /// Permission of a user on this problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn permission<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        user: &users::Model,
+    ) -> ModelResult<Permission> {
+        for (permission, condition) in [
+            (Permission::Manage, manageable_condition(user)),
+            (Permission::View, viewable_condition(user)),
+        ] {
+            let count = Problems::find()
+                .filter(problems::Column::Id.eq(self.id))
+                .filter(condition)
+                .count(db)
+                .await?;
+            if count > 0 {
+                return Ok(permission);
+            }
+        }
+
+        Ok(Permission::None)
+    }
+}
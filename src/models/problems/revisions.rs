@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use loco_rs::model::{ModelError, ModelResult};
+use num_traits::FromPrimitive;
+use sea_orm::{
+    entity::prelude::*, ActiveValue, IntoActiveModel, Order, QueryOrder, QuerySelect,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::TextDiff;
+
+use super::{descriptions, tasks, Type, UpdateParams};
+use crate::models::{
+    _entities::{problem_revisions, problems, users},
+    transform_db_error,
+};
+
+pub use problem_revisions::{ActiveModel, Model};
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+/// Content of a problem at some point. Visibility, tags and courses are not
+/// part of it, restoring an old revision shouldn't publish or hide a problem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub r#type: i32,
+    pub allowed_language: i32,
+    pub quota: i32,
+    pub default_locale: String,
+    pub description: descriptions::AddParams,
+    /// Descriptions in other locales
+    #[serde(default)]
+    pub translations: BTreeMap<String, descriptions::AddParams>,
+    pub tasks: Vec<tasks::AddParams>,
+    pub test_case_id: Option<String>,
+}
+
+impl Snapshot {
+    /// Take a snapshot of current problem content
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn of_problem<C: ConnectionTrait>(
+        db: &C,
+        problem: &problems::Model,
+    ) -> ModelResult<Self> {
+        let description = problem
+            .description_in(db, &problem.default_locale)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        Ok(Self {
+            name: problem.name.clone(),
+            r#type: problem.r#type,
+            allowed_language: problem.allowed_language,
+            quota: problem.quota,
+            default_locale: problem.default_locale.clone(),
+            description: description.to_params(),
+            translations: problem
+                .translations(db)
+                .await?
+                .into_iter()
+                .map(|(locale, d)| (locale, d.to_params()))
+                .collect(),
+            tasks: problem
+                .tasks(db)
+                .await?
+                .iter()
+                .map(tasks::Model::to_params)
+                .collect(),
+            test_case_id: problem.test_case_id.clone(),
+        })
+    }
+}
+
+/// A field changed between two revisions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Path of the field, e.g. `description.input` or `tasks.0.score`
+    pub field: String,
+    /// `None` if the field is added
+    pub from: Option<Value>,
+    /// `None` if the field is removed
+    pub to: Option<Value>,
+    /// Unified line diff when both sides are strings
+    pub diff: Option<String>,
+}
+
+/// Flatten a JSON value into leaf values by their dotted paths
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{prefix}.{key}")
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                flatten(&join(k), v, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                flatten(&join(&i.to_string()), v, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Fields changed from one snapshot to another, ordered by field path
+#[must_use]
+pub fn diff(from: &Value, to: &Value) -> Vec<Change> {
+    let (mut old, mut new) = (BTreeMap::new(), BTreeMap::new());
+    flatten("", from, &mut old);
+    flatten("", to, &mut new);
+
+    old.keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|field| {
+            let (a, b) = (old.get(field), new.get(field));
+            if a == b {
+                return None;
+            }
+            let diff = match (a, b) {
+                (Some(Value::String(a)), Some(Value::String(b))) => Some(
+                    TextDiff::from_lines(a, b)
+                        .unified_diff()
+                        .header("from", "to")
+                        .to_string(),
+                ),
+                _ => None,
+            };
+            Some(Change {
+                field: field.clone(),
+                from: a.cloned(),
+                to: b.cloned(),
+                diff,
+            })
+        })
+        .collect()
+}
+
+impl Model {
+    /// Record current content of a problem as a new revision. Nothing is
+    /// recorded if it's the same as the latest revision, which is returned
+    /// instead. The problem is re-read and locked until the revision is
+    /// saved, so concurrent edits get consecutive numbers.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn record<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        problem: &problems::Model,
+        author_id: i32,
+    ) -> ModelResult<Self> {
+        let txn = db.begin().await?;
+        let problem = problems::Entity::find_by_id(problem.id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(transform_db_error)?
+            .ok_or(ModelError::EntityNotFound)?;
+
+        let snapshot = Snapshot::of_problem(&txn, &problem).await?;
+        let snapshot = serde_json::to_value(snapshot).map_err(Box::from)?;
+        let latest = Self::latest(&txn, problem.id).await?;
+        if let Some(latest) = latest.as_ref().filter(|r| r.snapshot == snapshot) {
+            return Ok(latest.clone());
+        }
+
+        let revision = ActiveModel {
+            problem_id: ActiveValue::set(problem.id),
+            number: ActiveValue::set(latest.map_or(1, |r| r.number + 1)),
+            author_id: ActiveValue::set(author_id),
+            snapshot: ActiveValue::set(snapshot),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .map_err(transform_db_error)?;
+        txn.commit().await.map_err(transform_db_error)?;
+        tracing::info!(
+            problem_id = problem.id,
+            number = revision.number,
+            "problem revision recorded"
+        );
+
+        Ok(revision)
+    }
+
+    /// Latest revision of a problem, if any
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn latest<C: ConnectionTrait>(db: &C, problem_id: i32) -> ModelResult<Option<Self>> {
+        problem_revisions::Entity::find()
+            .filter(problem_revisions::Column::ProblemId.eq(problem_id))
+            .order_by(problem_revisions::Column::Number, Order::Desc)
+            .one(db)
+            .await
+            .map_err(transform_db_error)
+    }
+
+    /// List revisions of a problem with their authors, newest first
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn list_by_problem<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+    ) -> ModelResult<Vec<(Self, Option<users::Model>)>> {
+        problem_revisions::Entity::find()
+            .filter(problem_revisions::Column::ProblemId.eq(problem_id))
+            .find_also_related(users::Entity)
+            .order_by(problem_revisions::Column::Number, Order::Desc)
+            .all(db)
+            .await
+            .map_err(transform_db_error)
+    }
+
+    /// Find a revision of a problem by its number
+    ///
+    /// # Errors
+    ///
+    /// - When the revision does not exist
+    /// - When there is DB error.
+    pub async fn find_by_number<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        number: i32,
+    ) -> ModelResult<Self> {
+        problem_revisions::Entity::find()
+            .filter(problem_revisions::Column::ProblemId.eq(problem_id))
+            .filter(problem_revisions::Column::Number.eq(number))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?
+            .ok_or(ModelError::EntityNotFound)
+    }
+
+    /// Problem content of this revision
+    ///
+    /// # Errors
+    ///
+    /// When the stored snapshot is malformed
+    pub fn snapshot(&self) -> ModelResult<Snapshot> {
+        Ok(serde_json::from_value(self.snapshot.clone()).map_err(Box::from)?)
+    }
+}
+
+impl problems::Model {
+    /// Bring problem content back to a revision, recorded as a new revision
+    /// by `author_id`. Test case is switched back as well, verification of
+    /// reference solutions is reset if it changed. The default locale never
+    /// changes after creation, so it's kept as is.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn restore_revision<C: ConnectionTrait + TransactionTrait>(
+        self,
+        db: &C,
+        revision: &Model,
+        author_id: i32,
+    ) -> ModelResult<(Self, Model)> {
+        let snapshot = revision.snapshot()?;
+        let txn = db.begin().await?;
+
+        let test_case_changed = self.test_case_id != snapshot.test_case_id;
+        let params = UpdateParams {
+            name: Some(snapshot.name.clone()),
+            description: Some(snapshot.description.clone()),
+            r#type: Type::from_i32(snapshot.r#type),
+            allowed_language: Some(snapshot.allowed_language),
+            quota: Some(snapshot.quota),
+            tasks: Some(snapshot.tasks.clone()),
+            ..Default::default()
+        };
+        let problem = self.update(&txn, &params).await?;
+
+        for (locale, _) in problem.translations(&txn).await? {
+            if !snapshot.translations.contains_key(&locale) {
+                problem.remove_description(&txn, &locale).await?;
+            }
+        }
+        for (locale, description) in &snapshot.translations {
+            problem.set_description(&txn, locale, description).await?;
+        }
+
+        let mut problem = problem.into_active_model();
+        problem.test_case_id = ActiveValue::set(snapshot.test_case_id.clone());
+        if test_case_changed {
+            problem.verification = ActiveValue::set(None);
+        }
+        let problem = problem.update(&txn).await.map_err(transform_db_error)?;
+
+        let new_revision = Model::record(&txn, &problem, author_id).await?;
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok((problem, new_revision))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn diff_snapshots() {
+        let from = json!({
+            "name": "A + B",
+            "description": {"input": "A\nB\n"},
+            "tasks": [{"score": 100}],
+        });
+        let to = json!({
+            "name": "A + B",
+            "description": {"input": "A\nC\n"},
+            "tasks": [{"score": 40}, {"score": 60}],
+        });
+
+        assert_eq!(
+            diff(&from, &to),
+            vec![
+                Change {
+                    field: "description.input".to_string(),
+                    from: Some(json!("A\nB\n")),
+                    to: Some(json!("A\nC\n")),
+                    diff: Some("--- from\n+++ to\n@@ -1,2 +1,2 @@\n A\n-B\n+C\n".to_string()),
+                },
+                Change {
+                    field: "tasks.0.score".to_string(),
+                    from: Some(json!(100)),
+                    to: Some(json!(40)),
+                    diff: None,
+                },
+                Change {
+                    field: "tasks.1.score".to_string(),
+                    from: None,
+                    to: Some(json!(60)),
+                    diff: None,
+                },
+            ]
+        );
+        assert!(diff(&from, &from).is_empty());
+    }
+}
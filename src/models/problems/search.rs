@@ -0,0 +1,68 @@
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{Func, IntoColumnRef, LikeExpr, SimpleExpr},
+    Condition, DbBackend,
+};
+
+use crate::models::_entities::{problem_descriptions, problems};
+
+/// Text searched by full-text search, problem name weighs more than
+/// description
+const DOCUMENT: &str = r#"setweight(to_tsvector('simple', "problems"."name"), 'A') || setweight(to_tsvector('simple', "problem_descriptions"."description"), 'B')"#;
+
+/// Case-insensitive `LIKE` pattern of strings containing `text`
+fn containing(text: &str) -> LikeExpr {
+    let escaped = text
+        .to_lowercase()
+        .replace('\\', r"\\")
+        .replace('%', r"\%")
+        .replace('_', r"\_");
+    LikeExpr::new(format!("%{escaped}%")).escape('\\')
+}
+
+fn lower_contains<T: IntoColumnRef>(col: T, text: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(col))).like(containing(text))
+}
+
+/// Problems whose name contains `name`, ignoring case
+pub fn name_condition(name: &str) -> SimpleExpr {
+    lower_contains((problems::Entity, problems::Column::Name), name)
+}
+
+/// Condition of problems matching search `text`, and an expression ranking
+/// the matched problems, higher is better
+///
+/// Both refer to problem descriptions, which must be joined in the query.
+/// Full-text search is only available on Postgres, other backends fallback
+/// to substring match.
+pub fn search_condition(backend: DbBackend, text: &str) -> (Condition, SimpleExpr) {
+    match backend {
+        DbBackend::Postgres => {
+            let matched = Expr::cust_with_values(
+                format!("{DOCUMENT} @@ plainto_tsquery('simple', $1)"),
+                [text],
+            );
+            let rank = Expr::cust_with_values(
+                format!("ts_rank({DOCUMENT}, plainto_tsquery('simple', $1))"),
+                [text],
+            );
+            // full-text search only matches whole words, so partial names
+            // are still accepted
+            let cond = Condition::any().add(matched).add(name_condition(text));
+            (cond, rank)
+        }
+        _ => {
+            let in_name = name_condition(text);
+            let in_description = lower_contains(
+                (
+                    problem_descriptions::Entity,
+                    problem_descriptions::Column::Description,
+                ),
+                text,
+            );
+            let rank = Expr::case(in_name.clone(), 1).finally(0).into();
+            let cond = Condition::any().add(in_name).add(in_description);
+            (cond, rank)
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::models::{
+    submissions::{JudgeResult, Language},
+    transform_db_error,
+};
+
+pub use super::_entities::problem_solutions::{self, ActiveModel, Model};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{
+    entity::prelude::*, sea_query::Expr, ActiveValue, Order, QueryOrder, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+/// Verdicts a reference solution can be expected to get
+pub const EXPECTED_STATUSES: [&str; 7] = ["AC", "WA", "CE", "TLE", "MLE", "RE", "OLE"];
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddParams {
+    pub name: String,
+    pub language: Language,
+    pub code: String,
+    /// Expected verdict of this solution, one of [`EXPECTED_STATUSES`]
+    pub expected_status: String,
+    /// Main solution produces answers of generated test cases. A problem has
+    /// at most one main solution, setting a new one replaces the old.
+    #[serde(default)]
+    pub is_main: bool,
+}
+
+/// Verdict of a single reference solution in a verification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolutionVerdict {
+    pub solution_id: i32,
+    pub name: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Result of judging all reference solutions against a test case
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Verification {
+    /// The test case this verification is done against
+    pub test_case_id: String,
+    pub verdicts: Vec<SolutionVerdict>,
+}
+
+impl Verification {
+    /// Whether every solution gets its expected verdict
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.verdicts.iter().all(|v| v.expected == v.actual)
+    }
+}
+
+/// Overall verdict of judge results: the first non-AC status in case order,
+/// or `AC` if all cases are accepted.
+#[must_use]
+pub fn verdict(results: &[Vec<JudgeResult>]) -> String {
+    results
+        .iter()
+        .flatten()
+        .find(|r| r.status != "AC")
+        .map_or_else(|| "AC".to_string(), |r| r.status.clone())
+}
+
+impl Model {
+    /// Add a reference solution to problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn add<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        problem_id: i32,
+        params: &AddParams,
+    ) -> ModelResult<Self> {
+        let txn = db.begin().await?;
+
+        if params.is_main {
+            problem_solutions::Entity::update_many()
+                .col_expr(problem_solutions::Column::IsMain, Expr::value(false))
+                .filter(problem_solutions::Column::ProblemId.eq(problem_id))
+                .exec(&txn)
+                .await
+                .map_err(transform_db_error)?;
+        }
+
+        let solution = ActiveModel {
+            problem_id: ActiveValue::set(problem_id),
+            name: ActiveValue::set(params.name.clone()),
+            language: ActiveValue::set(params.language.clone()),
+            code: ActiveValue::set(params.code.clone()),
+            expected_status: ActiveValue::set(params.expected_status.clone()),
+            is_main: ActiveValue::set(params.is_main),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .map_err(transform_db_error)?;
+
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(solution)
+    }
+
+    /// Find the main solution of a problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn find_main<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+    ) -> ModelResult<Option<Self>> {
+        let solution = problem_solutions::Entity::find()
+            .filter(problem_solutions::Column::ProblemId.eq(problem_id))
+            .filter(problem_solutions::Column::IsMain.eq(true))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?;
+        Ok(solution)
+    }
+
+    /// List reference solutions of a problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn list_by_problem<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+    ) -> ModelResult<Vec<Self>> {
+        let solutions = problem_solutions::Entity::find()
+            .filter(problem_solutions::Column::ProblemId.eq(problem_id))
+            .order_by(problem_solutions::Column::Id, Order::Asc)
+            .all(db)
+            .await
+            .map_err(transform_db_error)?;
+        Ok(solutions)
+    }
+
+    /// Remove a reference solution of a problem
+    ///
+    /// # Errors
+    ///
+    /// - When the solution does not exist
+    /// - When there is DB error.
+    pub async fn remove<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        solution_id: i32,
+    ) -> ModelResult<()> {
+        let result = problem_solutions::Entity::delete_many()
+            .filter(problem_solutions::Column::ProblemId.eq(problem_id))
+            .filter(problem_solutions::Column::Id.eq(solution_id))
+            .exec(db)
+            .await
+            .map_err(transform_db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ModelError::EntityNotFound);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(status: &str) -> JudgeResult {
+        JudgeResult {
+            status: status.to_string(),
+            stdout: String::new(),
+            stderr: String::new(),
+            duration: 0,
+            mem_usage: 0,
+            task_id: 0,
+            case_id: 0,
+            stdout_truncated: false,
+            stderr_truncated: false,
+            full_output_stored: false,
+        }
+    }
+
+    #[test]
+    fn test_verdict() {
+        assert_eq!(verdict(&[]), "AC");
+        assert_eq!(verdict(&[vec![result("AC"), result("AC")]]), "AC");
+        assert_eq!(
+            verdict(&[vec![result("AC")], vec![result("TLE"), result("WA")]]),
+            "TLE"
+        );
+    }
+
+    #[test]
+    fn test_verification_passed() {
+        let verdict = |expected: &str, actual: &str| SolutionVerdict {
+            solution_id: 1,
+            name: "sol".to_string(),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        };
+        let mut verification = Verification {
+            test_case_id: "tc".to_string(),
+            verdicts: vec![verdict("AC", "AC"), verdict("TLE", "TLE")],
+        };
+        assert!(verification.passed());
+        verification.verdicts.push(verdict("TLE", "AC"));
+        assert!(!verification.passed());
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use loco_rs::model::ModelResult;
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{Func, SimpleExpr},
+    FromQueryResult, QueryOrder, QuerySelect,
+};
+
+use crate::models::_entities::{
+    problems,
+    sea_orm_active_enums::SubmissionStatus,
+    submissions::{self, Entity as Submissions},
+};
+
+/// Number of buckets in [`Stats::time_histogram`]
+pub const HISTOGRAM_BUCKETS: i32 = 10;
+
+/// Submission counters of a problem
+#[derive(Clone, Debug, Default, PartialEq, Eq, FromQueryResult)]
+pub struct Counts {
+    pub problem_id: i32,
+    /// Users having at least one accepted submission
+    pub ac_user: i64,
+    /// Users having submitted at least once
+    pub submitter: i64,
+    pub submit_count: i64,
+    pub high_score: i32,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct VerdictCount {
+    status: SubmissionStatus,
+    count: i64,
+}
+
+/// A range of execution time in ms, `lower` inclusive and `upper` exclusive
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeBucket {
+    pub lower: i32,
+    pub upper: i32,
+    pub count: i64,
+}
+
+/// Detailed statistics of a problem
+#[derive(Clone, Debug)]
+pub struct Stats {
+    pub counts: Counts,
+    /// Number of submissions in each status
+    pub verdicts: Vec<(SubmissionStatus, i64)>,
+    /// Execution time distribution of accepted submissions, evenly split up
+    /// to the largest time limit of the problem's tasks
+    pub time_histogram: Vec<TimeBucket>,
+}
+
+/// Count submissions of problems, problems without any submission are absent
+/// in the returned map
+///
+/// # Errors
+///
+/// When could not query submissions from DB
+pub async fn counts_by_problems<C: ConnectionTrait>(
+    db: &C,
+    problem_ids: &[i32],
+) -> ModelResult<HashMap<i32, Counts>> {
+    if problem_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let counts = Submissions::find()
+        .select_only()
+        .column(submissions::Column::ProblemId)
+        .column_as(
+            // `CASE` without `ELSE` is null for other statuses, which is not counted
+            SimpleExpr::from(Func::count_distinct(Expr::case(
+                submissions::Column::Status.eq(SubmissionStatus::Accepted),
+                Expr::col(submissions::Column::UserId),
+            ))),
+            "ac_user",
+        )
+        .column_as(
+            SimpleExpr::from(Func::count_distinct(Expr::col(submissions::Column::UserId))),
+            "submitter",
+        )
+        .column_as(submissions::Column::Id.count(), "submit_count")
+        .column_as(submissions::Column::Score.max(), "high_score")
+        .filter(submissions::Column::ProblemId.is_in(problem_ids.iter().copied()))
+        .group_by(submissions::Column::ProblemId)
+        .into_model::<Counts>()
+        .all(db)
+        .await?;
+
+    Ok(counts.into_iter().map(|c| (c.problem_id, c)).collect())
+}
+
+impl problems::Model {
+    /// Submission counters of this problem
+    ///
+    /// # Errors
+    ///
+    /// When could not query submissions from DB
+    pub async fn counts<C: ConnectionTrait>(&self, db: &C) -> ModelResult<Counts> {
+        let counts = counts_by_problems(db, &[self.id]).await?;
+        Ok(counts.get(&self.id).cloned().unwrap_or(Counts {
+            problem_id: self.id,
+            ..Default::default()
+        }))
+    }
+
+    /// Detailed statistics of this problem
+    ///
+    /// # Errors
+    ///
+    /// When could not query submissions or tasks from DB
+    pub async fn stats<C: ConnectionTrait>(&self, db: &C) -> ModelResult<Stats> {
+        let counts = self.counts(db).await?;
+
+        let verdicts = Submissions::find()
+            .select_only()
+            .column(submissions::Column::Status)
+            .column_as(submissions::Column::Id.count(), "count")
+            .filter(submissions::Column::ProblemId.eq(self.id))
+            .group_by(submissions::Column::Status)
+            .order_by_asc(submissions::Column::Status)
+            .into_model::<VerdictCount>()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|v| (v.status, v.count))
+            .collect();
+
+        let time_limit = self
+            .tasks(db)
+            .await?
+            .iter()
+            .map(|t| t.time_limit)
+            .max()
+            .unwrap_or(0);
+        let width = (time_limit / HISTOGRAM_BUCKETS).max(1);
+        let mut time_histogram = (0..HISTOGRAM_BUCKETS)
+            .map(|i| TimeBucket {
+                lower: i * width,
+                upper: (i + 1) * width,
+                count: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let exec_times = Submissions::find()
+            .select_only()
+            .column(submissions::Column::ExecTime)
+            .filter(submissions::Column::ProblemId.eq(self.id))
+            .filter(submissions::Column::Status.eq(SubmissionStatus::Accepted))
+            .into_tuple::<i32>()
+            .all(db)
+            .await?;
+        for t in exec_times {
+            // runs slightly over the limit still go to the last bucket
+            let i = (t.max(0) / width).min(HISTOGRAM_BUCKETS - 1);
+            time_histogram[usize::try_from(i).unwrap_or_default()].count += 1;
+        }
+
+        Ok(Stats {
+            counts,
+            verdicts,
+            time_histogram,
+        })
+    }
+}
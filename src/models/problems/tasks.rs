@@ -1,16 +1,80 @@
+use super::test_case;
 use crate::models::transform_db_error;
 
 pub use super::_entities::problem_tasks::{ActiveModel, Model};
 use loco_rs::model::ModelResult;
-use sea_orm::{entity::prelude::*, ActiveValue, IntoActiveModel, TransactionTrait};
-use serde::Deserialize;
+use sea_orm::{entity::prelude::*, ActiveValue, TransactionTrait};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, DeriveIntoActiveModel, Clone)]
+/// Metadata of a single test case inside a task
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaseMeta {
+    /// Directory name under `test-case/`, defaults to `TTCC` (2-digit task
+    /// index and 2-digit case index)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Public cases (e.g. samples) show their input, expected output and
+    /// contestant's output in submission detail. Others stay hidden.
+    #[serde(default)]
+    pub public: bool,
+    /// Generator invocation producing the input of this case, e.g.
+    /// `gen.py 10 100`. Generated cases ship neither `STDIN` nor `STDOUT` in
+    /// test case package, the answer is produced by problem's main solution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generate: Option<String>,
+}
+
+impl CaseMeta {
+    /// Directory containing `STDIN` & `STDOUT` of this case inside test case
+    /// package
+    #[must_use]
+    pub fn dir(&self, task_id: usize, case_id: usize) -> String {
+        self.name.as_ref().map_or_else(
+            || test_case::case_dir(task_id, case_id),
+            |name| format!("test-case/{name}"),
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AddParams {
     pub test_case_count: i32,
     pub score: i32,
     pub time_limit: i32,
     pub memory_limit: i32,
+    /// Per-case metadata, cases not listed here use the default one
+    #[serde(default)]
+    pub cases: Vec<CaseMeta>,
+}
+
+impl AddParams {
+    /// Metadata of each test case in this task, see [`Model::case_meta`]
+    #[must_use]
+    pub fn case_meta(&self) -> Vec<CaseMeta> {
+        let mut cases = self.cases.clone();
+        #[allow(clippy::cast_sign_loss)]
+        cases.resize(self.test_case_count.max(0) as usize, CaseMeta::default());
+        cases
+    }
+}
+
+/// Whether a test case package made for `old` tasks can't be used by `new`
+/// tasks, i.e. the number of tasks or cases changes, or any case is moved to
+/// another directory or generated differently.
+#[must_use]
+pub fn layout_changed(old: &[Model], new: &[AddParams]) -> bool {
+    let layout = |cases: Vec<CaseMeta>| {
+        cases
+            .into_iter()
+            .map(|c| (c.name, c.generate))
+            .collect::<Vec<_>>()
+    };
+
+    old.len() != new.len()
+        || old
+            .iter()
+            .zip(new)
+            .any(|(o, n)| layout(o.case_meta()) != layout(n.case_meta()))
 }
 
 impl Model {
@@ -27,16 +91,114 @@ impl Model {
         let txn = db.begin().await?;
         let mut tasks = vec![];
         for p in params {
-            let mut task = p.clone().into_active_model();
-            task.problem_id = ActiveValue::set(problem_id);
-            let task = task.insert(&txn).await.map_err(transform_db_error)?;
+            let task = ActiveModel {
+                test_case_count: ActiveValue::set(p.test_case_count),
+                score: ActiveValue::set(p.score),
+                time_limit: ActiveValue::set(p.time_limit),
+                memory_limit: ActiveValue::set(p.memory_limit),
+                problem_id: ActiveValue::set(problem_id),
+                cases: ActiveValue::set(Some(serde_json::to_value(&p.cases).map_err(Box::from)?)),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await
+            .map_err(transform_db_error)?;
             tasks.push(task);
         }
         txn.commit().await?;
         Ok(tasks)
     }
+
+    /// Metadata of each test case in this task. The length is always equal to
+    /// `test_case_count`.
+    #[must_use]
+    pub fn case_meta(&self) -> Vec<CaseMeta> {
+        let mut cases = self
+            .cases
+            .clone()
+            .and_then(|c| serde_json::from_value::<Vec<CaseMeta>>(c).ok())
+            .unwrap_or_default();
+        #[allow(clippy::cast_sign_loss)]
+        cases.resize(self.test_case_count.max(0) as usize, CaseMeta::default());
+        cases
+    }
+
+    /// Parameters re-creating this task, e.g. when restoring an old test case
+    #[must_use]
+    pub fn to_params(&self) -> AddParams {
+        AddParams {
+            test_case_count: self.test_case_count,
+            score: self.score,
+            time_limit: self.time_limit,
+            memory_limit: self.memory_limit,
+            cases: self.case_meta(),
+        }
+    }
+
+    /// Indices of public test cases in this task
+    #[must_use]
+    pub fn public_cases(&self) -> Vec<i32> {
+        self.case_meta()
+            .iter()
+            .zip(0..)
+            .filter(|(c, _)| c.public)
+            .map(|(_, i)| i)
+            .collect()
+    }
 }
 
 impl ActiveModelBehavior for ActiveModel {
     // extend activemodel below (keep comment for generators)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(test_case_count: i32, cases: Option<serde_json::Value>) -> Model {
+        Model {
+            id: 1,
+            test_case_count,
+            score: 100,
+            time_limit: 1000,
+            memory_limit: 65535,
+            problem_id: 1,
+            cases,
+        }
+    }
+
+    #[test]
+    fn test_case_meta_pad_to_test_case_count() {
+        let t = task(3, Some(serde_json::json!([{ "public": true }])));
+        assert_eq!(
+            t.case_meta(),
+            vec![
+                CaseMeta {
+                    public: true,
+                    ..Default::default()
+                },
+                CaseMeta::default(),
+                CaseMeta::default(),
+            ],
+        );
+        assert_eq!(t.public_cases(), vec![0]);
+    }
+
+    #[test]
+    fn test_case_dir() {
+        let named = CaseMeta {
+            name: Some("sample".to_string()),
+            public: true,
+            ..Default::default()
+        };
+        assert_eq!(named.dir(0, 1), "test-case/sample");
+        assert_eq!(CaseMeta::default().dir(1, 2), "test-case/0102");
+    }
+
+    #[test]
+    fn test_case_meta_without_cases() {
+        let t = task(2, None);
+        assert_eq!(t.case_meta(), vec![CaseMeta::default(); 2]);
+        assert!(t.public_cases().is_empty());
+    }
+}
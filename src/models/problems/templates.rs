@@ -0,0 +1,107 @@
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{entity::prelude::*, ActiveValue, IntoActiveModel, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+pub use super::_entities::problem_templates::{self, ActiveModel, Model};
+use crate::models::{submissions::Language, transform_db_error};
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetParams {
+    pub language: Language,
+    pub code: String,
+}
+
+impl Model {
+    /// List starter templates of a problem, at most one per language
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn list_by_problem<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+    ) -> ModelResult<Vec<Self>> {
+        let templates = problem_templates::Entity::find()
+            .filter(problem_templates::Column::ProblemId.eq(problem_id))
+            .order_by_asc(problem_templates::Column::Language)
+            .all(db)
+            .await
+            .map_err(transform_db_error)?;
+
+        Ok(templates)
+    }
+
+    /// Find the template of a problem in a language
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn find<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        language: &Language,
+    ) -> ModelResult<Option<Self>> {
+        problem_templates::Entity::find()
+            .filter(problem_templates::Column::ProblemId.eq(problem_id))
+            .filter(problem_templates::Column::Language.eq(language.clone()))
+            .one(db)
+            .await
+            .map_err(transform_db_error)
+    }
+
+    /// Create or replace the template of a problem in `params.language`. The
+    /// caller should make sure it compiles.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn set<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        params: &SetParams,
+    ) -> ModelResult<Self> {
+        let template = match Self::find(db, problem_id, &params.language).await? {
+            Some(t) => {
+                let mut t = t.into_active_model();
+                t.code = ActiveValue::set(params.code.clone());
+                t.update(db).await
+            }
+            None => {
+                ActiveModel {
+                    problem_id: ActiveValue::set(problem_id),
+                    language: ActiveValue::set(params.language.clone()),
+                    code: ActiveValue::set(params.code.clone()),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await
+            }
+        }
+        .map_err(transform_db_error)?;
+
+        Ok(template)
+    }
+
+    /// Remove the template of a problem in a language
+    ///
+    /// # Errors
+    ///
+    /// - When the problem has no template in the language
+    /// - When there is DB error.
+    pub async fn remove<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        language: &Language,
+    ) -> ModelResult<()> {
+        let template = Self::find(db, problem_id, language)
+            .await?
+            .ok_or(ModelError::EntityNotFound)?;
+        template.delete(db).await.map_err(transform_db_error)?;
+
+        Ok(())
+    }
+}
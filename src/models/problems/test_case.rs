@@ -1 +1,540 @@
 // Problem test case
+
+use std::{
+    collections::HashSet,
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use zip::{result::ZipError, ZipArchive};
+
+use super::{tasks, BadTestCase};
+use crate::models::submissions::Language;
+
+/// File name of test case manifest, placed at the root of test case package
+pub const MANIFEST_NAME: &str = "meta.json";
+/// Directory containing generator sources inside test case package
+pub const GENERATOR_DIR: &str = "generators";
+
+/// Input and expected output of a single test case
+#[derive(Debug, Clone)]
+pub struct CaseIo {
+    pub input: String,
+    pub answer: String,
+}
+
+/// How to compare contestant's output with expected output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Checker {
+    /// Ignore trailing whitespaces of each line and trailing empty lines
+    #[default]
+    Diff,
+    /// Outputs must be byte-to-byte identical
+    Exact,
+}
+
+/// Optional manifest of a test case package. When it exists, problem tasks are
+/// derived from it instead of the ones declared on problem creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub checker: Checker,
+    pub tasks: Vec<TaskManifest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskManifest {
+    pub score: i32,
+    pub time_limit: i32,
+    pub memory_limit: i32,
+    pub cases: Vec<tasks::CaseMeta>,
+}
+
+/// A generator invocation parsed from [`tasks::CaseMeta::generate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generation {
+    /// File name of generator source under [`GENERATOR_DIR`]
+    pub program: String,
+    /// Arguments passed to generator. Sandbox does not support command line
+    /// arguments, so generators read them from stdin instead.
+    pub args: String,
+}
+
+impl Generation {
+    /// Parse a generator invocation like `gen.py 10 100`.
+    ///
+    /// # Errors
+    ///
+    /// When the program name is invalid or in unsupported language
+    pub fn parse(command: &str) -> Result<Self, BadTestCase> {
+        let command = command.trim();
+        let (program, args) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let valid = !program.starts_with('.')
+            && program
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(BadTestCase::InvalidManifest(format!(
+                "invalid generator: {program}"
+            )));
+        }
+        let generation = Self {
+            program: program.to_string(),
+            args: args.trim().to_string(),
+        };
+        generation.language()?;
+
+        Ok(generation)
+    }
+
+    /// Language of generator, inferred from its file extension
+    ///
+    /// # Errors
+    ///
+    /// When the extension is not supported
+    pub fn language(&self) -> Result<Language, BadTestCase> {
+        match Path::new(&self.program)
+            .extension()
+            .and_then(|e| e.to_str())
+        {
+            Some("c") => Ok(Language::C),
+            Some("cpp" | "cc") => Ok(Language::Cpp),
+            Some("py") => Ok(Language::Python),
+            _ => Err(BadTestCase::InvalidManifest(format!(
+                "unsupported generator language: {}",
+                self.program
+            ))),
+        }
+    }
+
+    /// Path of generator source inside test case package
+    #[must_use]
+    pub fn source_path(&self) -> String {
+        format!("{GENERATOR_DIR}/{}", self.program)
+    }
+}
+
+impl Manifest {
+    /// Read and validate manifest from test case zip, returns `None` if the
+    /// package does not contain one.
+    ///
+    /// # Errors
+    ///
+    /// When the manifest could not be read or it's invalid
+    pub fn from_archive<R: Read + Seek>(
+        archive: &mut ZipArchive<R>,
+    ) -> Result<Option<Self>, BadTestCase> {
+        let mut file = match archive.by_name(MANIFEST_NAME) {
+            Ok(f) => f,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut content = vec![];
+        file.read_to_end(&mut content)
+            .map_err(|e| BadTestCase::InvalidManifest(e.to_string()))?;
+        let manifest = serde_json::from_slice::<Self>(&content)
+            .map_err(|e| BadTestCase::InvalidManifest(e.to_string()))?;
+        manifest.validate()?;
+
+        Ok(Some(manifest))
+    }
+
+    /// Read manifest from an extracted test case directory.
+    ///
+    /// # Errors
+    ///
+    /// When the manifest exists but could not be read or parsed
+    pub fn from_dir(dir: &Path) -> Result<Option<Self>, BadTestCase> {
+        let path = dir.join(MANIFEST_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read(path).map_err(|e| BadTestCase::Custom(e.to_string()))?;
+        let manifest = serde_json::from_slice::<Self>(&content)
+            .map_err(|e| BadTestCase::InvalidManifest(e.to_string()))?;
+
+        Ok(Some(manifest))
+    }
+
+    fn validate(&self) -> Result<(), BadTestCase> {
+        let invalid = |msg: String| Err(BadTestCase::InvalidManifest(msg));
+
+        if self.tasks.is_empty() {
+            return invalid("at least one task is required".to_string());
+        }
+
+        let mut names = HashSet::new();
+        for (i, task) in self.tasks.iter().enumerate() {
+            if task.cases.is_empty() {
+                return invalid(format!("task {i} has no test case"));
+            }
+            if task.score < 0 || task.time_limit <= 0 || task.memory_limit <= 0 {
+                return invalid(format!("task {i} has invalid score or limits"));
+            }
+            for (j, case) in task.cases.iter().enumerate() {
+                if let Some(name) = &case.name {
+                    let valid = !name.is_empty()
+                        && name
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                    if !valid {
+                        return invalid(format!("invalid case name: {name}"));
+                    }
+                }
+                if let Some(command) = &case.generate {
+                    Generation::parse(command)?;
+                    if case.public {
+                        return invalid(format!(
+                            "generated case can not be public: {}",
+                            case.dir(i, j)
+                        ));
+                    }
+                }
+                if !names.insert(case.dir(i, j)) {
+                    return invalid(format!("duplicated case: {}", case.dir(i, j)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Problem tasks declared by this manifest
+    #[must_use]
+    pub fn task_params(&self) -> Vec<tasks::AddParams> {
+        self.tasks
+            .iter()
+            .map(|t| tasks::AddParams {
+                test_case_count: t.cases.len().try_into().unwrap_or(i32::MAX),
+                score: t.score,
+                time_limit: t.time_limit,
+                memory_limit: t.memory_limit,
+                cases: t.cases.clone(),
+            })
+            .collect()
+    }
+
+    /// Directories of all test cases, in task order
+    #[must_use]
+    pub fn case_dirs(&self) -> Vec<String> {
+        self.cases().map(|(dir, _)| dir).collect()
+    }
+
+    /// Directories of test cases whose `STDIN` & `STDOUT` are shipped in
+    /// package, i.e. not generated.
+    #[must_use]
+    pub fn packaged_case_dirs(&self) -> Vec<String> {
+        self.cases()
+            .filter(|(_, c)| c.generate.is_none())
+            .map(|(dir, _)| dir)
+            .collect()
+    }
+
+    /// Generated test cases and their generator invocations, in task order
+    #[must_use]
+    pub fn generated_cases(&self) -> Vec<(String, Generation)> {
+        self.cases()
+            .filter_map(|(dir, c)| {
+                // already validated on upload
+                let generation = Generation::parse(c.generate.as_ref()?).ok()?;
+                Some((dir, generation))
+            })
+            .collect()
+    }
+
+    /// Files other than test case I/O in package, i.e. the manifest itself
+    /// and used generators.
+    #[must_use]
+    pub fn extra_files(&self) -> Vec<String> {
+        let mut files = self
+            .generated_cases()
+            .into_iter()
+            .map(|(_, g)| g.source_path())
+            .collect::<Vec<_>>();
+        files.sort();
+        files.dedup();
+        files.push(MANIFEST_NAME.to_string());
+        files
+    }
+
+    fn cases(&self) -> impl Iterator<Item = (String, &tasks::CaseMeta)> {
+        self.tasks.iter().enumerate().flat_map(|(i, t)| {
+            t.cases
+                .iter()
+                .enumerate()
+                .map(move |(j, c)| (c.dir(i, j), c))
+        })
+    }
+}
+
+/// Path of a test case package in app's storage
+#[must_use]
+pub fn storage_path(test_case_id: &str) -> PathBuf {
+    PathBuf::from("test-case").join(format!("{test_case_id}.zip"))
+}
+
+/// Path of the default directory containing a test case's `STDIN` & `STDOUT`
+#[must_use]
+pub fn case_dir(task_id: usize, case_id: usize) -> String {
+    format!("test-case/{task_id:02}{case_id:02}")
+}
+
+/// Check that zip contains exactly `STDIN` & `STDOUT` of each case under
+/// `case_dirs`, plus `extra_files` (e.g. the manifest).
+///
+/// # Errors
+///
+/// - When the zip file contains invalid files
+/// - When there is missing/extra files inside zip
+pub fn validate_files<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    case_dirs: &[String],
+    extra_files: &[String],
+) -> Result<(), BadTestCase> {
+    let mut expected_input_output = case_dirs
+        .iter()
+        .flat_map(|d| vec![format!("{d}/STDIN"), format!("{d}/STDOUT")])
+        .collect::<HashSet<_>>();
+    expected_input_output.extend(extra_files.iter().cloned());
+
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if file.is_symlink() {
+            return Err(BadTestCase::Custom(format!(
+                "symlink is not allowed: {}",
+                file.name()
+            )));
+        }
+        // skip directory for now
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.enclosed_name().ok_or_else(|| {
+            BadTestCase::Custom(format!("invalid path found in zip file: {}", file.name()))
+        })?;
+        let name = name.to_str().ok_or_else(|| {
+            BadTestCase::Custom(format!(
+                "invalid path found in zip file (maybe non-UTF8 path?): {}",
+                file.name()
+            ))
+        })?;
+
+        if !expected_input_output.remove(name) {
+            return Err(BadTestCase::Custom(format!(
+                "duplicated or extra file found: {}",
+                file.name()
+            )));
+        }
+    }
+
+    if !expected_input_output.is_empty() {
+        return Err(BadTestCase::Custom(format!(
+            "missing files: {}",
+            expected_input_output
+                .into_iter()
+                .collect::<Vec<_>>()
+                .join(",")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Read input and expected output of a test case from test case zip.
+///
+/// # Errors
+///
+/// When the files of that test case could not be read from zip
+pub fn read_case_io<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    dir: &str,
+) -> Result<CaseIo, BadTestCase> {
+    let mut read = |name: &str| -> Result<String, BadTestCase> {
+        let mut file = archive.by_name(&format!("{dir}/{name}"))?;
+        let mut content = vec![];
+        file.read_to_end(&mut content)
+            .map_err(|e| BadTestCase::Custom(format!("failed to read {dir}/{name}: {e}")))?;
+        Ok(String::from_utf8_lossy(&content).to_string())
+    };
+
+    Ok(CaseIo {
+        input: read("STDIN")?,
+        answer: read("STDOUT")?,
+    })
+}
+
+/// Same as [`read_case_io`], but the test case is extracted under `root`.
+///
+/// # Errors
+///
+/// When the files of that test case could not be read
+pub fn read_dir_case_io(root: &Path, dir: &str) -> Result<CaseIo, BadTestCase> {
+    let read = |name: &str| -> Result<String, BadTestCase> {
+        let content = std::fs::read(root.join(dir).join(name))
+            .map_err(|e| BadTestCase::Custom(format!("failed to read {dir}/{name}: {e}")))?;
+        Ok(String::from_utf8_lossy(&content).to_string())
+    };
+
+    Ok(CaseIo {
+        input: read("STDIN")?,
+        answer: read("STDOUT")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    fn make_zip(files: &[(&str, &str)]) -> ZipArchive<std::io::Cursor<Vec<u8>>> {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            for (name, content) in files {
+                zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+                zip.write_all(content.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        ZipArchive::new(buf).unwrap()
+    }
+
+    #[test]
+    fn test_manifest_with_named_cases() {
+        let meta = r#"{
+            "checker": "exact",
+            "tasks": [
+                { "score": 40, "time_limit": 1000, "memory_limit": 65536,
+                  "cases": [{ "name": "sample", "public": true }, {}] },
+                { "score": 60, "time_limit": 2000, "memory_limit": 65536,
+                  "cases": [{ "name": "large" }] }
+            ]
+        }"#;
+        let mut archive = make_zip(&[
+            (MANIFEST_NAME, meta),
+            ("test-case/sample/STDIN", "1 2"),
+            ("test-case/sample/STDOUT", "3"),
+            ("test-case/0001/STDIN", "2 2"),
+            ("test-case/0001/STDOUT", "4"),
+            ("test-case/large/STDIN", "1 1"),
+            ("test-case/large/STDOUT", "2"),
+        ]);
+
+        let manifest = Manifest::from_archive(&mut archive).unwrap().unwrap();
+        assert_eq!(manifest.checker, Checker::Exact);
+        assert_eq!(
+            manifest.case_dirs(),
+            vec!["test-case/sample", "test-case/0001", "test-case/large"],
+        );
+        let tasks = manifest.task_params();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].test_case_count, 2);
+        assert!(tasks[0].cases[0].public);
+        assert_eq!(tasks[1].time_limit, 2000);
+        validate_files(
+            &mut archive,
+            &manifest.packaged_case_dirs(),
+            &manifest.extra_files(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_manifest_rejects_invalid_case_name() {
+        let meta = r#"{ "tasks": [{ "score": 100, "time_limit": 1000, "memory_limit": 65536,
+            "cases": [{ "name": "../escape" }] }] }"#;
+        let mut archive = make_zip(&[(MANIFEST_NAME, meta)]);
+
+        assert!(matches!(
+            Manifest::from_archive(&mut archive),
+            Err(BadTestCase::InvalidManifest(_)),
+        ));
+    }
+
+    #[test]
+    fn test_manifest_rejects_duplicated_case() {
+        let meta = r#"{ "tasks": [{ "score": 100, "time_limit": 1000, "memory_limit": 65536,
+            "cases": [{ "name": "a" }, { "name": "a" }] }] }"#;
+        let mut archive = make_zip(&[(MANIFEST_NAME, meta)]);
+
+        assert!(matches!(
+            Manifest::from_archive(&mut archive),
+            Err(BadTestCase::InvalidManifest(_)),
+        ));
+    }
+
+    #[test]
+    fn test_package_without_manifest() {
+        let mut archive = make_zip(&[
+            ("test-case/0000/STDIN", "1 2"),
+            ("test-case/0000/STDOUT", "3"),
+        ]);
+
+        assert!(Manifest::from_archive(&mut archive).unwrap().is_none());
+        validate_files(&mut archive, &[case_dir(0, 0)], &[]).unwrap();
+        assert!(validate_files(&mut archive, &[case_dir(0, 0), case_dir(0, 1)], &[]).is_err());
+    }
+
+    #[test]
+    fn test_manifest_with_generated_cases() {
+        let meta = r#"{ "tasks": [{ "score": 100, "time_limit": 1000, "memory_limit": 65536,
+            "cases": [{}, { "generate": "gen.py 10 100" }, { "generate": "gen.py 20" }] }] }"#;
+        let mut archive = make_zip(&[
+            (MANIFEST_NAME, meta),
+            ("test-case/0000/STDIN", "1 2"),
+            ("test-case/0000/STDOUT", "3"),
+            ("generators/gen.py", "print(input())"),
+        ]);
+
+        let manifest = Manifest::from_archive(&mut archive).unwrap().unwrap();
+        assert_eq!(manifest.packaged_case_dirs(), vec!["test-case/0000"]);
+        assert_eq!(
+            manifest.generated_cases(),
+            vec![
+                (
+                    "test-case/0001".to_string(),
+                    Generation {
+                        program: "gen.py".to_string(),
+                        args: "10 100".to_string(),
+                    }
+                ),
+                (
+                    "test-case/0002".to_string(),
+                    Generation {
+                        program: "gen.py".to_string(),
+                        args: "20".to_string(),
+                    }
+                ),
+            ],
+        );
+        assert_eq!(
+            manifest.extra_files(),
+            vec!["generators/gen.py", MANIFEST_NAME]
+        );
+        validate_files(
+            &mut archive,
+            &manifest.packaged_case_dirs(),
+            &manifest.extra_files(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_parse_generation() {
+        assert_eq!(Generation::parse("gen.cpp").unwrap().args, "");
+        assert_eq!(
+            Generation::parse("gen.c").unwrap().language().unwrap(),
+            Language::C
+        );
+        assert!(Generation::parse("../gen.py 1").is_err());
+        assert!(Generation::parse("gen.rs 1").is_err());
+        assert!(Generation::parse("").is_err());
+    }
+}
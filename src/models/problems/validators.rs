@@ -0,0 +1,217 @@
+use std::{
+    io::{Read, Seek, Write},
+    path::Path,
+};
+
+use crate::{
+    models::{submissions::Language, transform_db_error},
+    sandbox,
+};
+
+pub use super::_entities::problem_validators::{self, ActiveModel, Model};
+use super::BadTestCase;
+use loco_rs::model::ModelResult;
+use sea_orm::{entity::prelude::*, ActiveValue, IntoActiveModel};
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+use zip::ZipArchive;
+
+/// Time limit (ms) of running validator on a single input
+const TIME_LIMIT: i32 = 10_000;
+/// Memory limit (KB) of running validator on a single input
+const MEMORY_LIMIT: i32 = 512 * 1024;
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetParams {
+    pub language: Language,
+    pub code: String,
+}
+
+/// A test input rejected by validator
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidInput {
+    /// Directory of the test case inside test case package
+    pub case: String,
+    /// Why the validator rejects it, generally its stderr
+    pub message: String,
+}
+
+impl Model {
+    /// Find the validator of a problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn find_by_problem<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+    ) -> ModelResult<Option<Self>> {
+        let validator = problem_validators::Entity::find()
+            .filter(problem_validators::Column::ProblemId.eq(problem_id))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?;
+        Ok(validator)
+    }
+
+    /// Create or replace the validator of a problem
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn set<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        params: &SetParams,
+    ) -> ModelResult<Self> {
+        let validator = match Self::find_by_problem(db, problem_id).await? {
+            Some(v) => {
+                let mut v = v.into_active_model();
+                v.language = ActiveValue::set(params.language.clone());
+                v.code = ActiveValue::set(params.code.clone());
+                v.update(db).await
+            }
+            None => {
+                ActiveModel {
+                    problem_id: ActiveValue::set(problem_id),
+                    language: ActiveValue::set(params.language.clone()),
+                    code: ActiveValue::set(params.code.clone()),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await
+            }
+        }
+        .map_err(transform_db_error)?;
+
+        Ok(validator)
+    }
+
+    /// Remove the validator of a problem, do nothing if there isn't one.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn remove<C: ConnectionTrait>(db: &C, problem_id: i32) -> ModelResult<()> {
+        problem_validators::Entity::delete_many()
+            .filter(problem_validators::Column::ProblemId.eq(problem_id))
+            .exec(db)
+            .await
+            .map_err(transform_db_error)?;
+        Ok(())
+    }
+
+    /// Run validator on `STDIN` of each test case under `case_dirs`. The
+    /// validator reads a test input from stdin, and should exit with non-zero
+    /// code if the input is invalid.
+    ///
+    /// # Errors
+    ///
+    /// When the validator could not be compiled or run
+    pub fn validate_inputs<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        case_dirs: &[String],
+    ) -> Result<Vec<InvalidInput>, BadTestCase> {
+        let dir = self.compile()?;
+        let input_path = dir.path().join("input");
+        let mut invalid_inputs = vec![];
+        for case in case_dirs {
+            let mut content = vec![];
+            archive
+                .by_name(&format!("{case}/STDIN"))?
+                .read_to_end(&mut content)
+                .map_err(|e| sandbox_error(e.into()))?;
+            std::fs::File::create(&input_path)
+                .and_then(|mut f| f.write_all(&content))
+                .map_err(|e| sandbox_error(e.into()))?;
+
+            invalid_inputs.extend(self.run(dir.path(), &input_path, case)?);
+        }
+
+        Ok(invalid_inputs)
+    }
+
+    /// Same as [`Self::validate_inputs`], but the test cases are under an
+    /// extracted directory `root`
+    ///
+    /// # Errors
+    ///
+    /// When the validator could not be compiled or run
+    pub fn validate_dir_inputs(
+        &self,
+        root: &Path,
+        case_dirs: &[String],
+    ) -> Result<Vec<InvalidInput>, BadTestCase> {
+        let dir = self.compile()?;
+        let mut invalid_inputs = vec![];
+        for case in case_dirs {
+            let input_path = root.join(case).join("STDIN");
+            invalid_inputs.extend(self.run(dir.path(), &input_path, case)?);
+        }
+
+        Ok(invalid_inputs)
+    }
+
+    /// Compile the validator into a temporary directory
+    fn compile(&self) -> Result<TempDir, BadTestCase> {
+        let dir = tempfile::tempdir().map_err(|e| sandbox_error(e.into()))?;
+        let compile_output =
+            sandbox::compile(dir.path(), &self.language, &self.code).map_err(sandbox_error)?;
+        if !compile_output.success {
+            return Err(BadTestCase::Custom(format!(
+                "validator could not be compiled: {}",
+                compile_output.stderr
+            )));
+        }
+
+        Ok(dir)
+    }
+
+    /// Run the compiled validator in `dir` on a single input
+    fn run(
+        &self,
+        dir: &Path,
+        input_path: &Path,
+        case: &str,
+    ) -> Result<Option<InvalidInput>, BadTestCase> {
+        let output = sandbox::run(
+            dir,
+            &sandbox::RunConfig {
+                language: &self.language,
+                stdin: input_path,
+                time_limit: TIME_LIMIT,
+                memory_limit: MEMORY_LIMIT,
+            },
+        )
+        .map_err(sandbox_error)?;
+        if output.status == "JE" {
+            return Err(BadTestCase::Custom(format!(
+                "validator could not be run: {}",
+                output.stderr
+            )));
+        }
+        if output.exited_normally() {
+            return Ok(None);
+        }
+
+        let message = if output.stderr.is_empty() {
+            output.status
+        } else {
+            output.stderr
+        };
+        Ok(Some(InvalidInput {
+            case: case.to_string(),
+            message,
+        }))
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn sandbox_error(e: eyre::Report) -> BadTestCase {
+    BadTestCase::Custom(format!("validator: {e}"))
+}
@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use super::tasks;
+use crate::models::{_entities::users, transform_db_error};
+
+pub use super::_entities::test_case_versions::{self, ActiveModel, Model};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{entity::prelude::*, ActiveValue, Order, QueryOrder};
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+impl Model {
+    /// Record an uploaded test case with a snapshot of problem tasks at that
+    /// time, so it can be restored later.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn add<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        test_case_id: &str,
+        uploader_id: i32,
+        tasks: &[tasks::Model],
+    ) -> ModelResult<Self> {
+        let params = tasks
+            .iter()
+            .map(tasks::Model::to_params)
+            .collect::<Vec<_>>();
+        let version = ActiveModel {
+            problem_id: ActiveValue::set(problem_id),
+            test_case_id: ActiveValue::set(test_case_id.to_string()),
+            uploader_id: ActiveValue::set(uploader_id),
+            tasks: ActiveValue::set(Some(serde_json::to_value(params).map_err(Box::from)?)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(transform_db_error)?;
+
+        Ok(version)
+    }
+
+    /// List test case versions of a problem with their uploaders, newest first
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn list_by_problem<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+    ) -> ModelResult<Vec<(Self, Option<users::Model>)>> {
+        let versions = test_case_versions::Entity::find()
+            .filter(test_case_versions::Column::ProblemId.eq(problem_id))
+            .find_also_related(users::Entity)
+            .order_by(test_case_versions::Column::Id, Order::Desc)
+            .all(db)
+            .await
+            .map_err(transform_db_error)?;
+
+        Ok(versions)
+    }
+
+    /// Find a test case version of a problem
+    ///
+    /// # Errors
+    ///
+    /// - When the version does not belong to the problem
+    /// - When there is DB error.
+    pub async fn find<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        test_case_id: &str,
+    ) -> ModelResult<Self> {
+        test_case_versions::Entity::find()
+            .filter(test_case_versions::Column::ProblemId.eq(problem_id))
+            .filter(test_case_versions::Column::TestCaseId.eq(test_case_id))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?
+            .ok_or(ModelError::EntityNotFound)
+    }
+
+    /// Problem tasks at the time this version was uploaded
+    #[must_use]
+    pub fn task_params(&self) -> Vec<tasks::AddParams> {
+        self.tasks
+            .clone()
+            .and_then(|t| serde_json::from_value(t).ok())
+            .unwrap_or_default()
+    }
+
+    /// Path of this version's package in app's storage
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        super::test_case::storage_path(&self.test_case_id)
+    }
+}
@@ -1,11 +1,14 @@
+use std::path::PathBuf;
+
 use loco_rs::prelude::*;
-use sea_orm::{Order, QueryOrder};
+use sea_orm::{ItemsAndPagesNumber, Order, PaginatorTrait, QueryOrder, QuerySelect};
 use serde::{Deserialize, Serialize};
 
 use super::_entities::prelude::Submissions;
 use super::_entities::problems;
 pub use super::_entities::sea_orm_active_enums::{Language, SubmissionStatus};
 pub use super::_entities::submissions::{self, ActiveModel, Model};
+use super::{clamp_page_size, SortOrder};
 
 #[derive(Debug, Deserialize)]
 pub struct AddParams {
@@ -15,10 +18,26 @@ pub struct AddParams {
     pub language: Language,
 }
 
+/// Field to sort submission list by
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    Id,
+    Time,
+    Score,
+    ExecTime,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListParams {
-    pub offset: Option<usize>,
-    pub count: Option<usize>,
+    /// 1-based page number
+    pub page: u64,
+    pub page_size: u64,
+    #[serde(default)]
+    pub sort_by: SortBy,
+    #[serde(default)]
+    pub order: SortOrder,
     pub problem: Option<i32>,
     pub user: Option<i32>,
     pub status: Option<SubmissionStatus>,
@@ -35,9 +54,46 @@ pub struct JudgeResult {
     pub stderr: String,
     pub task_id: i32,
     pub case_id: i32,
+    /// Whether `stdout` is truncated before saved
+    #[serde(default)]
+    pub stdout_truncated: bool,
+    /// Whether `stderr` is truncated before saved
+    #[serde(default)]
+    pub stderr_truncated: bool,
+    /// Whether the full outputs are kept in app's storage
+    #[serde(default)]
+    pub full_output_stored: bool,
     // exit_msg: String,
 }
 
+impl JudgeResult {
+    /// Cap stored `stdout` & `stderr` to at most `limit` bytes (excluding the
+    /// truncation marker).
+    pub fn truncate_output(&mut self, limit: usize) {
+        self.stdout_truncated |= truncate_with_marker(&mut self.stdout, limit);
+        self.stderr_truncated |= truncate_with_marker(&mut self.stderr, limit);
+    }
+}
+
+/// Truncate `s` to at most `limit` bytes without breaking a UTF-8 character,
+/// and append a marker telling how many bytes are dropped.
+///
+/// Returns whether `s` is truncated.
+fn truncate_with_marker(s: &mut String, limit: usize) -> bool {
+    if s.len() <= limit {
+        return false;
+    }
+
+    let mut end = limit;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    let dropped = s.len() - end;
+    s.truncate(end);
+    s.push_str(&format!("\n... ({dropped} bytes truncated)"));
+    true
+}
+
 impl ActiveModelBehavior for ActiveModel {
     // extend activemodel below (keep comment for generators)
 }
@@ -132,6 +188,15 @@ impl ActiveModel {
 }
 
 impl Model {
+    /// Path to full output of a test case in app's storage
+    #[must_use]
+    pub fn output_path(&self, task_id: i32, case_id: i32, stream: &str) -> PathBuf {
+        PathBuf::from("submission-output")
+            .join(self.id.to_string())
+            .join(format!("{task_id:02}{case_id:02}"))
+            .join(stream)
+    }
+
     /// Create a submission
     ///
     /// # Errors
@@ -158,13 +223,26 @@ impl Model {
         Ok(submission)
     }
 
-    /// List submissions
+    /// List a page of submissions, along with the total number of matched
+    /// submissions and pages
     ///
     /// # Errors
     ///
     /// When cloud not query submissions from DB
-    pub async fn list<C: ConnectionTrait>(db: &C, params: &ListParams) -> ModelResult<Vec<Self>> {
-        let mut q = Submissions::find().order_by(submissions::Column::Id, Order::Asc);
+    pub async fn list<C: ConnectionTrait>(
+        db: &C,
+        params: &ListParams,
+    ) -> ModelResult<(Vec<Self>, ItemsAndPagesNumber)> {
+        let sort_by = match params.sort_by {
+            SortBy::Id => submissions::Column::Id,
+            SortBy::Time => submissions::Column::Timestamp,
+            SortBy::Score => submissions::Column::Score,
+            SortBy::ExecTime => submissions::Column::ExecTime,
+        };
+        let mut q = Submissions::find()
+            .order_by(sort_by, params.order.into())
+            // keep pages stable when sorted values are equal
+            .order_by(submissions::Column::Id, Order::Asc);
 
         if let Some(problem) = params.problem {
             q = q.filter(submissions::Column::ProblemId.eq(problem));
@@ -179,13 +257,37 @@ impl Model {
             q = q.filter(submissions::Column::Language.eq(language.clone()));
         }
 
-        let submissions = q.all(db).await?.into_iter();
+        let pages = q.paginate(db, clamp_page_size(params.page_size));
+        let total = pages.num_items_and_pages().await?;
+        let submissions = pages.fetch_page(params.page.saturating_sub(1)).await?;
 
-        let offset = params.offset.unwrap_or(0);
-        let count = params.count.unwrap_or(usize::MAX);
-        let submissions = submissions.skip(offset).take(count);
+        Ok((submissions, total))
+    }
 
-        Ok(submissions.collect())
+    /// List ids of at most `limit` submissions of a problem which could be
+    /// judged (i.e. with code), in ascending order after id `after`. Pass the
+    /// last id of previous batch as `after` to get the next batch.
+    ///
+    /// # Errors
+    ///
+    /// When cloud not query submissions from DB
+    pub async fn list_judgeable_ids<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        after: i32,
+        limit: u64,
+    ) -> ModelResult<Vec<i32>> {
+        Ok(Submissions::find()
+            .select_only()
+            .column(submissions::Column::Id)
+            .filter(submissions::Column::ProblemId.eq(problem_id))
+            .filter(submissions::Column::Id.gt(after))
+            .filter(submissions::Column::Code.ne(""))
+            .order_by(submissions::Column::Id, Order::Asc)
+            .limit(limit)
+            .into_tuple()
+            .all(db)
+            .await?)
     }
 
     /// Get submission by id
@@ -209,3 +311,42 @@ impl Model {
         submission.ok_or(ModelError::EntityNotFound)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn judge_result(stdout: &str, stderr: &str) -> JudgeResult {
+        JudgeResult {
+            status: "WA".to_string(),
+            duration: 0,
+            mem_usage: 0,
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            task_id: 0,
+            case_id: 0,
+            stdout_truncated: false,
+            stderr_truncated: false,
+            full_output_stored: false,
+        }
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let mut r = judge_result("aaaaa", "bb");
+        r.truncate_output(3);
+        assert_eq!(r.stdout, "aaa\n... (2 bytes truncated)");
+        assert!(r.stdout_truncated);
+        assert_eq!(r.stderr, "bb");
+        assert!(!r.stderr_truncated);
+    }
+
+    #[test]
+    fn test_truncate_output_at_char_boundary() {
+        // each character takes 3 bytes
+        let mut r = judge_result("測試", "");
+        r.truncate_output(4);
+        assert_eq!(r.stdout, "測\n... (3 bytes truncated)");
+        assert!(r.stdout_truncated);
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use super::_entities::{problem_tags, tags};
+use crate::models::transform_db_error;
+
+pub use super::_entities::tags::{ActiveModel, Entity, Model};
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{
+    entity::prelude::*, ActiveValue, IntoActiveModel, Order, QueryOrder, TransactionTrait,
+};
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+impl Model {
+    /// Create a tag
+    ///
+    /// # Errors
+    ///
+    /// - When the name is taken by another tag
+    /// - When there is DB error.
+    pub async fn create<C: ConnectionTrait>(db: &C, name: &str) -> ModelResult<Self> {
+        let tag = ActiveModel {
+            name: ActiveValue::set(name.to_string()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(transform_db_error)?;
+
+        Ok(tag)
+    }
+
+    /// List all tags ordered by name
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn list<C: ConnectionTrait>(db: &C) -> ModelResult<Vec<Self>> {
+        let tags = tags::Entity::find()
+            .order_by(tags::Column::Name, Order::Asc)
+            .all(db)
+            .await?;
+
+        Ok(tags)
+    }
+
+    /// Find a tag by id
+    ///
+    /// # Errors
+    ///
+    /// When could not find the tag or DB query error
+    pub async fn find_by_id<C: ConnectionTrait>(db: &C, id: i32) -> ModelResult<Self> {
+        tags::Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(ModelError::EntityNotFound)
+    }
+
+    /// Find tags by names. Unknown names are ignored, the caller should
+    /// compare the result with input if that matters.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn find_by_names<C: ConnectionTrait>(
+        db: &C,
+        names: &[String],
+    ) -> ModelResult<Vec<Self>> {
+        let tags = tags::Entity::find()
+            .filter(tags::Column::Name.is_in(names.iter().cloned()))
+            .order_by(tags::Column::Name, Order::Asc)
+            .all(db)
+            .await?;
+
+        Ok(tags)
+    }
+
+    /// Names of tags attached to each problem, keyed by problem id. Problems
+    /// without tags are absent.
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn names_by_problems<C: ConnectionTrait>(
+        db: &C,
+        problem_ids: &[i32],
+    ) -> ModelResult<HashMap<i32, Vec<String>>> {
+        let rows = problem_tags::Entity::find()
+            .filter(problem_tags::Column::ProblemId.is_in(problem_ids.iter().copied()))
+            .find_also_related(tags::Entity)
+            .order_by(tags::Column::Name, Order::Asc)
+            .all(db)
+            .await?;
+
+        let mut names = HashMap::<_, Vec<_>>::new();
+        for (row, tag) in rows {
+            if let Some(tag) = tag {
+                names.entry(row.problem_id).or_default().push(tag.name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Rename a tag
+    ///
+    /// # Errors
+    ///
+    /// - When the name is taken by another tag
+    /// - When there is DB error.
+    pub async fn rename<C: ConnectionTrait>(self, db: &C, name: &str) -> ModelResult<Self> {
+        let mut tag = self.into_active_model();
+        tag.name = ActiveValue::set(name.to_string());
+
+        tag.update(db).await.map_err(transform_db_error)
+    }
+
+    /// Remove a tag and detach it from all problems
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn remove<C: ConnectionTrait + TransactionTrait>(self, db: &C) -> ModelResult<()> {
+        let txn = db.begin().await?;
+        problem_tags::Entity::delete_many()
+            .filter(problem_tags::Column::TagId.eq(self.id))
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        tags::Entity::delete_by_id(self.id)
+            .exec(&txn)
+            .await
+            .map_err(transform_db_error)?;
+        txn.commit().await.map_err(transform_db_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use loco_rs::model::{ModelError, ModelResult};
+use sea_orm::{entity::prelude::*, ActiveValue, IntoActiveModel};
+use sha2::{Digest, Sha256};
+
+pub use super::_entities::test_case_uploads::{self, ActiveModel, Model};
+use crate::models::transform_db_error;
+
+impl ActiveModelBehavior for ActiveModel {
+    // extend activemodel below (keep comment for generators)
+}
+
+impl Model {
+    /// Start an upload session of a test case with `size` bytes. Chunks are
+    /// kept in a file under `dir` until the session is finished.
+    ///
+    /// # Errors
+    ///
+    /// When could not create the file or save the session into DB
+    pub async fn create<C: ConnectionTrait>(
+        db: &C,
+        dir: &Path,
+        problem_id: i32,
+        user_id: i32,
+        size: i64,
+    ) -> ModelResult<Self> {
+        let upload = ActiveModel {
+            pid: ActiveValue::set(Uuid::new_v4()),
+            problem_id: ActiveValue::set(problem_id),
+            user_id: ActiveValue::set(user_id),
+            size: ActiveValue::set(size),
+            received: ActiveValue::set(0),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(transform_db_error)?;
+
+        fs::create_dir_all(dir)
+            .and_then(|()| fs::File::create(upload.file_path(dir)))
+            .map_err(|e| ModelError::Any(e.into()))?;
+
+        Ok(upload)
+    }
+
+    /// Find an upload session of a problem by its pid
+    ///
+    /// # Errors
+    ///
+    /// - When the pid is invalid or the session does not exist
+    /// - When there is DB error.
+    pub async fn find_by_pid<C: ConnectionTrait>(
+        db: &C,
+        problem_id: i32,
+        pid: &str,
+    ) -> ModelResult<Self> {
+        let pid = Uuid::parse_str(pid).map_err(|_| ModelError::EntityNotFound)?;
+        test_case_uploads::Entity::find()
+            .filter(test_case_uploads::Column::ProblemId.eq(problem_id))
+            .filter(test_case_uploads::Column::Pid.eq(pid))
+            .one(db)
+            .await
+            .map_err(transform_db_error)?
+            .ok_or(ModelError::EntityNotFound)
+    }
+
+    /// Path of the file keeping received chunks
+    #[must_use]
+    pub fn file_path(&self, dir: &Path) -> PathBuf {
+        dir.join(self.pid.to_string())
+    }
+
+    /// Append a chunk to the end of received data. The caller should check
+    /// that the chunk starts at [`Model::received`] and fits in the size.
+    ///
+    /// # Errors
+    ///
+    /// When could not write the file or save the session into DB
+    pub async fn append<C: ConnectionTrait>(
+        self,
+        db: &C,
+        dir: &Path,
+        chunk: &[u8],
+    ) -> ModelResult<Self> {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(self.file_path(dir))
+            .map_err(|e| ModelError::Any(e.into()))?;
+        // drop the tail written by a request interrupted before saving to DB
+        #[allow(clippy::cast_sign_loss)]
+        file.set_len(self.received as u64)
+            .and_then(|()| file.write_all(chunk))
+            .and_then(|()| file.sync_data())
+            .map_err(|e| ModelError::Any(e.into()))?;
+
+        let received = self.received + i64::try_from(chunk.len()).unwrap_or(i64::MAX);
+        let mut upload = self.into_active_model();
+        upload.received = ActiveValue::set(received);
+        // sessions without progress for a while are considered abandoned
+        upload.updated_at = ActiveValue::set(chrono::Utc::now().naive_utc());
+        upload.update(db).await.map_err(transform_db_error)
+    }
+
+    /// Hex encoded SHA-256 of received data
+    ///
+    /// # Errors
+    ///
+    /// When could not read the file
+    pub fn sha256(&self, dir: &Path) -> std::io::Result<String> {
+        let mut file = fs::File::open(self.file_path(dir))?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Remove sessions (of any problem) which have not received any chunk
+    /// within `expire_after`, along with their files
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn discard_stale<C: ConnectionTrait>(
+        db: &C,
+        dir: &Path,
+        expire_after: Duration,
+    ) -> ModelResult<()> {
+        let Some(deadline) = chrono::Duration::from_std(expire_after)
+            .ok()
+            .and_then(|d| chrono::Utc::now().naive_utc().checked_sub_signed(d))
+        else {
+            return Ok(());
+        };
+        let stale_uploads = test_case_uploads::Entity::find()
+            .filter(test_case_uploads::Column::UpdatedAt.lt(deadline))
+            .all(db)
+            .await
+            .map_err(transform_db_error)?;
+        for upload in stale_uploads {
+            tracing::info!(upload = %upload.pid, "discard abandoned test case upload");
+            upload.discard(db, dir).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove the session and its file
+    ///
+    /// # Errors
+    ///
+    /// When there is DB error.
+    pub async fn discard<C: ConnectionTrait>(self, db: &C, dir: &Path) -> ModelResult<()> {
+        if let Err(e) = fs::remove_file(self.file_path(dir)) {
+            tracing::warn!(error = ?e, upload = %self.pid, "failed to remove uploaded chunks");
+        }
+        test_case_uploads::Entity::delete_by_id(self.id)
+            .exec(db)
+            .await
+            .map_err(transform_db_error)?;
+        Ok(())
+    }
+}
@@ -296,7 +296,9 @@ impl super::_entities::users::Model {
                 r => r,
             }?;
 
-            // TODO: update course info
+            if let Some(course) = &params.course {
+                course.add_member(&tx, new_user.id).await?;
+            }
 
             new_users.push(new_user);
         }
@@ -0,0 +1,201 @@
+//! Helpers to compile & run programs (submissions, validators, ...) with the
+//! [sandbox](https://github.com/normal-OJ/sandbox-rs) binary.
+use std::{fs::File, io::Write, path::Path, process::Command};
+
+use eyre::{eyre, Result};
+
+use crate::models::submissions::Language;
+
+/// Output of compiler
+#[derive(Debug, Clone)]
+pub struct CompileOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Limits & I/O of a single sandbox execution
+#[derive(Debug, Clone)]
+pub struct RunConfig<'a> {
+    pub language: &'a Language,
+    pub stdin: &'a Path,
+    /// time limit in ms
+    pub time_limit: i32,
+    /// memory limit in KB
+    pub memory_limit: i32,
+}
+
+/// Result of a single sandbox execution
+#[derive(Debug, Clone)]
+pub struct RunOutput {
+    /// Status reported by sandbox, or `JE` if the sandbox itself failed
+    pub status: String,
+    /// in ms
+    pub duration: i32,
+    /// in KB
+    pub mem_usage: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl RunOutput {
+    /// Whether the program exits normally without exceeding any limit
+    #[must_use]
+    pub fn exited_normally(&self) -> bool {
+        !matches!(self.status.as_str(), "TLE" | "MLE" | "RE" | "OLE" | "JE")
+    }
+
+    fn judge_error(stdout: String, stderr: String) -> Self {
+        Self {
+            status: "JE".to_string(),
+            duration: -1,
+            mem_usage: -1,
+            stdout,
+            stderr,
+        }
+    }
+}
+
+/// Name of the source file expected by compiler & sandbox
+#[must_use]
+pub const fn source_file_name(language: &Language) -> &'static str {
+    match language {
+        Language::C => "main.c",
+        Language::Cpp => "main.cpp",
+        Language::Python => "main.py",
+    }
+}
+
+/// Write source code into `dir` and compile it if needed. Languages which do
+/// not need compilation are always compiled successfully.
+///
+/// # Errors
+///
+/// When could not write the source code or invoke the compiler
+pub fn compile(dir: &Path, language: &Language, code: &str) -> Result<CompileOutput> {
+    let source_path = dir.join(source_file_name(language));
+    let mut source_file = File::create(source_path.as_path())
+        .map_err(|e| eyre!("failed to create source code: {e}"))?;
+    source_file
+        .write_all(code.as_bytes())
+        .map_err(|e| eyre!("failed to write source code: {e}"))?;
+
+    let (compiler, std) = match language {
+        Language::C => ("gcc", "-std=c11"),
+        Language::Cpp => ("g++", "-std=c++17"),
+        Language::Python => {
+            return Ok(CompileOutput {
+                success: true,
+                stdout: String::new(),
+                stderr: String::new(),
+            })
+        }
+    };
+    let output = Command::new(compiler)
+        .args([
+            "-DONLINE_JUDGE",
+            "-O2",
+            "-w",
+            "-fmax-errors=3",
+            std,
+            source_file_name(language),
+            "-lm",
+            "-o",
+            "main",
+        ])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| eyre!("failed to compile {compiler} source: {e}"))?;
+
+    Ok(CompileOutput {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+/// Run the program compiled in `dir` inside sandbox.
+///
+/// # Errors
+///
+/// When could not prepare sandbox config or read sandbox outputs. The failure
+/// of sandbox itself is reported as `JE` in [`RunOutput`].
+pub fn run(dir: &Path, config: &RunConfig<'_>) -> Result<RunOutput> {
+    let output_dir = tempfile::tempdir().map_err(|e| eyre!("failed to create output dir: {e}"))?;
+    let stdout_path = output_dir.path().join("stdout");
+    let stderr_path = output_dir.path().join("stderr");
+    let output_path = output_dir.path().join("output");
+    let stdin_str = config.stdin.to_string_lossy();
+    let stdout_str = stdout_path.to_string_lossy();
+    let stderr_str = stderr_path.to_string_lossy();
+    let output_str = output_path.to_string_lossy();
+    let time_limit = config.time_limit;
+    let memory_limit = config.memory_limit;
+    let lang: i32 = config.language.clone().into();
+
+    // save config to disk
+    let sandbox_config = toml::toml! {
+        cwd = "."
+        large-stack = true
+        max-process = 10
+        memory-limit = memory_limit
+        output-size-limit = 10000
+        runtime-limit = time_limit
+        lang = lang
+        stdin = stdin_str
+        stdout = stdout_str
+        stderr = stderr_str
+        output = output_str
+    };
+    let config_path = output_dir.path().join("noj.toml");
+    let mut config_file = File::create(&config_path)?;
+    config_file.write_all(&toml::to_string(&sandbox_config).map(String::into_bytes)?)?;
+
+    // invoke sandbox process
+    // TODO: configurable sandbox path
+    let sandbox_output = Command::new("sandbox")
+        .args(["--env-path", config_path.to_string_lossy().as_ref()])
+        .current_dir(dir)
+        .output();
+    let o = match sandbox_output {
+        Ok(o) => o,
+        Err(e) => return Ok(RunOutput::judge_error(String::new(), e.to_string())),
+    };
+    if !o.status.success() {
+        return Ok(RunOutput::judge_error(
+            String::from_utf8_lossy(&o.stdout).to_string(),
+            String::from_utf8_lossy(&o.stderr).to_string(),
+        ));
+    }
+
+    let sandbox_status_raw_string = std::fs::read_to_string(&output_path).map_err(|e| {
+        eyre!(
+            "failed to read sandbox result @{}: {}",
+            output_path.display(),
+            e
+        )
+    })?;
+    let sandbox_status_raw_string = sandbox_status_raw_string.lines().collect::<Vec<_>>();
+    if sandbox_status_raw_string.len() < 4 {
+        return Err(eyre!(
+            "invalid sandbox result: {}",
+            sandbox_status_raw_string.join("\n")
+        ));
+    }
+
+    let duration_ms: i32 = sandbox_status_raw_string[2].parse()?;
+    let mem_usage_kb: i32 = sandbox_status_raw_string[3].parse()?;
+    let _exit_msg = sandbox_status_raw_string[1].to_string();
+    let stdout =
+        std::fs::read_to_string(stdout_path).map_err(|e| eyre!("failed to read stdout: {e}"))?;
+    let stderr =
+        std::fs::read_to_string(stderr_path).map_err(|e| eyre!("failed to read stderr: {e}"))?;
+
+    Ok(RunOutput {
+        status: sandbox_status_raw_string[0].to_string(),
+        duration: duration_ms,
+        mem_usage: mem_usage_kb,
+        stdout,
+        stderr,
+    })
+}
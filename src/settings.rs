@@ -0,0 +1,146 @@
+//! Application specific settings, read from the `settings` section of loco's
+//! config file.
+use std::path::PathBuf;
+
+use loco_rs::{app::AppContext, Error, Result};
+use serde::Deserialize;
+
+use crate::models::{problems::permission::CreatePolicy, users::Role};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub judge: JudgeSettings,
+    #[serde(default)]
+    pub upload: UploadSettings,
+    #[serde(default)]
+    pub archive: ArchiveSettings,
+    #[serde(default)]
+    pub attachment: AttachmentSettings,
+    #[serde(default)]
+    pub problem: ProblemSettings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JudgeSettings {
+    /// Max bytes of stdout/stderr stored for each test case in DB
+    pub max_stored_output: usize,
+    /// Whether to keep the full output of failed test cases in app's storage
+    pub keep_failed_output: bool,
+}
+
+impl Default for JudgeSettings {
+    fn default() -> Self {
+        Self {
+            max_stored_output: 4096,
+            keep_failed_output: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UploadSettings {
+    /// Where chunks of unfinished test case uploads are kept
+    pub dir: PathBuf,
+    /// Max bytes of a test case uploaded in chunks
+    pub max_size: i64,
+    /// Seconds without any chunk received after which an unfinished upload
+    /// is considered abandoned and removed
+    pub expire_after: u64,
+}
+
+impl Default for UploadSettings {
+    fn default() -> Self {
+        Self {
+            dir: std::env::temp_dir().join("noj-test-case-uploads"),
+            max_size: 1024 * 1024 * 1024,
+            expire_after: 24 * 60 * 60,
+        }
+    }
+}
+
+/// Limits on uploaded archives (test cases, problem packages), so that a
+/// small archive can't expand into something unbounded
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ArchiveSettings {
+    /// Max total bytes of all files after decompression
+    pub max_unpacked_size: u64,
+    /// Max number of entries, including directories
+    pub max_entries: usize,
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            max_unpacked_size: 2 * 1024 * 1024 * 1024,
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AttachmentSettings {
+    /// Max bytes of a problem attachment
+    pub max_size: usize,
+}
+
+impl Default for AttachmentSettings {
+    fn default() -> Self {
+        Self {
+            max_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProblemSettings {
+    #[serde(default)]
+    pub create: CreatePolicies,
+}
+
+/// Where each role can create problems. Students can't create problems, so
+/// there is no policy for them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CreatePolicies {
+    pub admin: CreatePolicy,
+    pub teacher: CreatePolicy,
+}
+
+impl Default for CreatePolicies {
+    fn default() -> Self {
+        Self {
+            admin: CreatePolicy::Anywhere,
+            teacher: CreatePolicy::TaughtCourses,
+        }
+    }
+}
+
+impl CreatePolicies {
+    #[must_use]
+    pub fn for_role(&self, role: &Role) -> CreatePolicy {
+        match role {
+            Role::Admin => self.admin,
+            Role::Teacher => self.teacher,
+            Role::Student => CreatePolicy::Deny,
+        }
+    }
+}
+
+impl Settings {
+    /// Load settings from app context, fallback to default if not specified.
+    ///
+    /// # Errors
+    ///
+    /// When the `settings` section has invalid format
+    pub fn from_ctx(ctx: &AppContext) -> Result<Self> {
+        ctx.config.settings.as_ref().map_or_else(
+            || Ok(Self::default()),
+            |s| serde_json::from_value(s.clone()).map_err(|e| Error::Any(e.into())),
+        )
+    }
+}
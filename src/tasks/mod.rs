@@ -1 +1,2 @@
+pub mod problem_bundle;
 pub mod seed;
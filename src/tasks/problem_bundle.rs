@@ -0,0 +1,71 @@
+//! This task exports a problem as bundle or imports one, so problems can move
+//! between instances, e.g. from staging to production.
+//!
+//! # Example
+//!
+//! Export problem 1 into a file:
+//! ```sh
+//! cargo run task problem_bundle action:export problem:1 path:problem-1.zip
+//! ```
+//!
+//! Import it on another instance, owned by user `teacher1`:
+//! ```sh
+//! cargo run task problem_bundle action:import path:problem-1.zip owner:teacher1
+//! ```
+use std::{collections::BTreeMap, io::Cursor};
+
+use loco_rs::prelude::*;
+
+use crate::models::{
+    problems::{
+        self,
+        bundle::{self, Bundle},
+    },
+    users,
+};
+
+#[allow(clippy::module_name_repetitions)]
+pub struct ProblemBundle;
+#[async_trait]
+impl Task for ProblemBundle {
+    fn task(&self) -> TaskInfo {
+        TaskInfo {
+            name: "problem_bundle".to_string(),
+            detail: "Task for exporting or importing problem bundles".to_string(),
+        }
+    }
+
+    async fn run(&self, app_context: &AppContext, vars: &BTreeMap<String, String>) -> Result<()> {
+        let var = |name: &str| {
+            vars.get(name)
+                .ok_or_else(|| Error::Message(format!("missing variable: {name}")))
+        };
+
+        match var("action")?.as_str() {
+            "export" => {
+                let problem_id = var("problem")?
+                    .parse::<i32>()
+                    .map_err(|e| Error::Message(format!("invalid problem id: {e}")))?;
+                let problem = problems::Model::find_by_id(&app_context.db, problem_id).await?;
+                let content = bundle::export(app_context, &problem).await?;
+                std::fs::write(var("path")?, content)?;
+                println!("problem {problem_id} exported to {}", var("path")?);
+            }
+            "import" => {
+                let owner = users::Model::find_by_username(&app_context.db, var("owner")?).await?;
+                let content = std::fs::read(var("path")?)?;
+                let (bundle, files) = Bundle::read(Cursor::new(content))
+                    .map_err(|e| Error::Message(format!("invalid bundle: {e}")))?;
+                let problem = bundle::import(app_context, owner, &bundle, files).await?;
+                println!("bundle imported as problem {}", problem.id);
+            }
+            action => {
+                return Err(Error::Message(format!(
+                    "unknown action: {action}, expected export or import"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 pub mod auth;
 pub mod problems;
 pub mod submission;
+pub mod tags;
 pub mod user;
 
 use loco_rs::controller::views::pagination::PagerMeta;
@@ -10,6 +11,9 @@ use serde::{Deserialize, Serialize};
 pub struct PaginatedResponse<T> {
     pub results: Vec<T>,
     pub pagination: PagerMeta,
+    /// Number of items on all pages, if the list counts them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_items: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
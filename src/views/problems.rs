@@ -1,13 +1,17 @@
+use std::collections::HashMap;
+
+use loco_rs::controller::views::pagination::PagerMeta;
 use num_traits::FromPrimitive;
 use sea_orm::entity::prelude::DateTime;
 use serde::Serialize;
+use serde_json::Value;
 
 use crate::models::{
-    problems::{self, Type, Visibility},
+    problems::{self, permission::Permission, stats, Type, Visibility},
     users,
 };
 
-use super::NojResponseBuilder;
+use super::{NojResponseBuilder, PaginatedResponse};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,12 +20,12 @@ pub struct ProblemListResponseItem {
     pub name: String,
     pub status: Visibility,
     #[serde(rename = "ACUser")]
-    pub ac_user: i32,
-    pub submitter: i32,
+    pub ac_user: i64,
+    pub submitter: i64,
     pub tags: Vec<String>,
     pub r#type: Type,
     pub quota: i32,
-    pub submit_count: i32,
+    pub submit_count: i64,
 }
 
 pub struct ProblemListResponse {}
@@ -30,24 +34,36 @@ impl ProblemListResponse {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(problems: &[problems::Model]) -> NojResponseBuilder<Vec<ProblemListResponseItem>> {
-        let data = problems
+    pub fn new(
+        problems: &[problems::Model],
+        tags: &HashMap<i32, Vec<String>>,
+        counts: &HashMap<i32, stats::Counts>,
+        pagination: PagerMeta,
+        total_items: u64,
+    ) -> NojResponseBuilder<PaginatedResponse<ProblemListResponseItem>> {
+        let results = problems
             .iter()
-            .map(|p| ProblemListResponseItem {
-                id: p.id,
-                name: p.name.to_string(),
-                status: Visibility::from_i32(p.status).unwrap(),
-                r#type: Type::from_i32(p.r#type).unwrap(),
-                quota: p.quota,
-                // TODO: impl following fields
-                ac_user: 0,
-                submit_count: 0,
-                submitter: 0,
-                tags: vec![],
+            .map(|p| {
+                let counts = counts.get(&p.id).cloned().unwrap_or_default();
+                ProblemListResponseItem {
+                    id: p.id,
+                    name: p.name.to_string(),
+                    status: Visibility::from_i32(p.status).unwrap(),
+                    r#type: Type::from_i32(p.r#type).unwrap(),
+                    quota: p.quota,
+                    ac_user: counts.ac_user,
+                    submit_count: counts.submit_count,
+                    submitter: counts.submitter,
+                    tags: tags.get(&p.id).cloned().unwrap_or_default(),
+                }
             })
             .collect();
 
-        NojResponseBuilder::new(data)
+        NojResponseBuilder::new(PaginatedResponse {
+            results,
+            pagination,
+            total_items: Some(total_items),
+        })
     }
 }
 
@@ -71,6 +87,11 @@ pub struct ProblemTaskView {
     pub score: i32,
     pub time_limit: i32,
     pub memory_limit: i32,
+    /// indices of public test cases
+    pub public_cases: Vec<i32>,
+    /// metadata of each case, only for users managing the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cases: Option<Vec<problems::tasks::CaseMeta>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -78,6 +99,10 @@ pub struct ProblemTaskView {
 pub struct ProblemDetailResponse {
     problem_name: String,
     description: ProblemDescriptionView,
+    /// locale of `description`
+    locale: String,
+    /// all locales the description is available in, default first
+    locales: Vec<String>,
     /// username of problem owner
     owner: String,
     tags: Vec<String>,
@@ -88,7 +113,12 @@ pub struct ProblemDetailResponse {
     status: Visibility,
     r#type: Type,
     test_case: Vec<ProblemTaskView>,
-    submit_count: i32,
+    /// starter code shown to students, at most one per language
+    templates: Vec<ProblemTemplateView>,
+    /// id of current test case package, only for users managing the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    test_case_id: Option<String>,
+    submit_count: i64,
     high_score: i32,
 }
 
@@ -96,12 +126,20 @@ impl ProblemDetailResponse {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     #[allow(clippy::new_ret_no_self)]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         problem: &problems::Model,
         description: &problems::descriptions::Model,
+        (locale, locales): (&str, &[String]),
         owner: &users::Model,
         tasks: &[problems::tasks::Model],
+        templates: &[problems::templates::Model],
+        tags: &[String],
+        courses: &[String],
+        counts: &stats::Counts,
+        permission: Permission,
     ) -> NojResponseBuilder<Self> {
+        let can_manage = permission == Permission::Manage;
         let problems::descriptions::Model {
             description,
             input,
@@ -114,6 +152,7 @@ impl ProblemDetailResponse {
             ..
         } = description.clone();
         let to_task_view = |t: &problems::tasks::Model| {
+            let public_cases = t.public_cases();
             let problems::tasks::Model {
                 test_case_count,
                 score,
@@ -127,6 +166,8 @@ impl ProblemDetailResponse {
                 score,
                 time_limit,
                 memory_limit,
+                public_cases,
+                cases: can_manage.then(|| t.case_meta()),
             }
         };
 
@@ -142,17 +183,411 @@ impl ProblemDetailResponse {
                 created_at,
                 updated_at,
             },
+            locale: locale.to_string(),
+            locales: locales.to_vec(),
             owner: owner.name.clone(),
-            tags: vec![],
+            tags: tags.to_vec(),
             allowed_language: problem.allowed_language,
-            courses: vec![],
+            courses: courses.to_vec(),
             quota: problem.quota,
             status: Visibility::from_i32(problem.status).unwrap(),
             r#type: Type::from_i32(problem.r#type).unwrap(),
             test_case: tasks.iter().map(to_task_view).collect(),
-            submit_count: 0,
-            high_score: 0,
+            templates: templates.iter().map(ProblemTemplateView::new).collect(),
+            test_case_id: problem.test_case_id.clone().filter(|_| can_manage),
+            submit_count: counts.submit_count,
+            high_score: counts.high_score,
         };
         NojResponseBuilder::new(resp)
     }
 }
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemValidatorResponse {
+    pub language: i32,
+    pub code: String,
+}
+
+impl ProblemValidatorResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(validator: &problems::validators::Model) -> NojResponseBuilder<Self> {
+        NojResponseBuilder::new(Self {
+            language: validator.language.clone().into(),
+            code: validator.code.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemTemplateView {
+    pub language: i32,
+    pub code: String,
+}
+
+impl ProblemTemplateView {
+    #[must_use]
+    pub fn new(template: &problems::templates::Model) -> Self {
+        Self {
+            language: template.language.clone().into(),
+            code: template.code.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemSolutionView {
+    pub id: i32,
+    pub name: String,
+    pub language: i32,
+    pub code: String,
+    pub expected_status: String,
+    pub is_main: bool,
+}
+
+impl ProblemSolutionView {
+    #[must_use]
+    pub fn new(solution: &problems::solutions::Model) -> Self {
+        Self {
+            id: solution.id,
+            name: solution.name.clone(),
+            language: solution.language.clone().into(),
+            code: solution.code.clone(),
+            expected_status: solution.expected_status.clone(),
+            is_main: solution.is_main,
+        }
+    }
+}
+
+pub struct ProblemSolutionListResponse {}
+
+impl ProblemSolutionListResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        solutions: &[problems::solutions::Model],
+    ) -> NojResponseBuilder<Vec<ProblemSolutionView>> {
+        let data = solutions.iter().map(ProblemSolutionView::new).collect();
+
+        NojResponseBuilder::new(data)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemAttachmentView {
+    pub name: String,
+    pub content_type: String,
+    pub size: i64,
+    /// download route of the attachment
+    pub url: String,
+    pub created_at: DateTime,
+}
+
+impl ProblemAttachmentView {
+    #[must_use]
+    pub fn new(attachment: &problems::attachments::Model) -> Self {
+        Self {
+            name: attachment.name.clone(),
+            content_type: attachment.content_type.clone(),
+            size: attachment.size,
+            url: format!(
+                "/api/problems/{}/attachments/{}",
+                attachment.problem_id, attachment.name
+            ),
+            created_at: attachment.created_at,
+        }
+    }
+}
+
+pub struct ProblemAttachmentListResponse {}
+
+impl ProblemAttachmentListResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        attachments: &[problems::attachments::Model],
+    ) -> NojResponseBuilder<Vec<ProblemAttachmentView>> {
+        let data = attachments.iter().map(ProblemAttachmentView::new).collect();
+
+        NojResponseBuilder::new(data)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestCaseVersionView {
+    pub test_case_id: String,
+    /// username of uploader
+    pub uploader: Option<String>,
+    pub created_at: DateTime,
+    /// Whether this is the test case currently used by problem
+    pub current: bool,
+}
+
+pub struct TestCaseVersionListResponse {}
+
+impl TestCaseVersionListResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        problem: &problems::Model,
+        versions: &[(problems::versions::Model, Option<users::Model>)],
+    ) -> NojResponseBuilder<Vec<TestCaseVersionView>> {
+        let data = versions
+            .iter()
+            .map(|(v, uploader)| TestCaseVersionView {
+                test_case_id: v.test_case_id.clone(),
+                uploader: uploader.as_ref().map(|u| u.name.clone()),
+                created_at: v.created_at,
+                current: problem.test_case_id.as_ref() == Some(&v.test_case_id),
+            })
+            .collect();
+
+        NojResponseBuilder::new(data)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemCollaboratorView {
+    pub username: Option<String>,
+    pub role: Option<problems::collaborators::Role>,
+    pub created_at: DateTime,
+}
+
+impl ProblemCollaboratorView {
+    #[must_use]
+    pub fn new(collaborator: &problems::collaborators::Model, user: Option<&users::Model>) -> Self {
+        Self {
+            username: user.map(|u| u.name.clone()),
+            role: collaborator.role(),
+            created_at: collaborator.created_at,
+        }
+    }
+}
+
+pub struct ProblemCollaboratorListResponse {}
+
+impl ProblemCollaboratorListResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        collaborators: &[(problems::collaborators::Model, Option<users::Model>)],
+    ) -> NojResponseBuilder<Vec<ProblemCollaboratorView>> {
+        let data = collaborators
+            .iter()
+            .map(|(c, u)| ProblemCollaboratorView::new(c, u.as_ref()))
+            .collect();
+
+        NojResponseBuilder::new(data)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemRevisionView {
+    pub number: i32,
+    /// username of author
+    pub author: Option<String>,
+    pub created_at: DateTime,
+    pub test_case_id: Option<String>,
+}
+
+pub struct ProblemRevisionListResponse {}
+
+impl ProblemRevisionListResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        revisions: &[(problems::revisions::Model, Option<users::Model>)],
+    ) -> NojResponseBuilder<Vec<ProblemRevisionView>> {
+        let data = revisions
+            .iter()
+            .map(|(r, author)| ProblemRevisionView {
+                number: r.number,
+                author: author.as_ref().map(|u| u.name.clone()),
+                created_at: r.created_at,
+                test_case_id: r
+                    .snapshot
+                    .get("test_case_id")
+                    .and_then(Value::as_str)
+                    .map(ToString::to_string),
+            })
+            .collect();
+
+        NojResponseBuilder::new(data)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemRevisionDetail {
+    pub number: i32,
+    pub created_at: DateTime,
+    /// Problem content of this revision
+    pub snapshot: Value,
+}
+
+pub struct ProblemRevisionResponse {}
+
+impl ProblemRevisionResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(revision: &problems::revisions::Model) -> NojResponseBuilder<ProblemRevisionDetail> {
+        NojResponseBuilder::new(ProblemRevisionDetail {
+            number: revision.number,
+            created_at: revision.created_at,
+            snapshot: revision.snapshot.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemRevisionChangeView {
+    /// Path of the field, e.g. `description.input`
+    pub field: String,
+    pub from: Option<Value>,
+    pub to: Option<Value>,
+    /// Unified line diff of text fields
+    pub diff: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemRevisionDiff {
+    pub from: i32,
+    pub to: i32,
+    pub changes: Vec<ProblemRevisionChangeView>,
+}
+
+pub struct ProblemRevisionDiffResponse {}
+
+impl ProblemRevisionDiffResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        from: &problems::revisions::Model,
+        to: &problems::revisions::Model,
+        changes: &[problems::revisions::Change],
+    ) -> NojResponseBuilder<ProblemRevisionDiff> {
+        let changes = changes
+            .iter()
+            .map(|c| ProblemRevisionChangeView {
+                field: c.field.clone(),
+                from: c.from.clone(),
+                to: c.to.clone(),
+                diff: c.diff.clone(),
+            })
+            .collect();
+
+        NojResponseBuilder::new(ProblemRevisionDiff {
+            from: from.number,
+            to: to.number,
+            changes,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerdictCountView {
+    pub status: i32,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeBucketView {
+    /// inclusive lower bound in ms
+    pub lower: i32,
+    /// exclusive upper bound in ms
+    pub upper: i32,
+    pub count: i64,
+}
+
+/// Problem description with Markdown sections rendered as sanitized HTML
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedDescriptionResponse {
+    /// locale of the rendered description
+    pub locale: String,
+    /// all locales the description is available in, default first
+    pub locales: Vec<String>,
+    pub description: String,
+    pub input: String,
+    pub output: String,
+    pub hint: String,
+    pub sample_input: Vec<String>,
+    pub sample_output: Vec<String>,
+}
+
+impl RenderedDescriptionResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        problem: &problems::Model,
+        desc: &problems::descriptions::Model,
+        (locale, locales): (&str, &[String]),
+    ) -> NojResponseBuilder<Self> {
+        let rendered = desc.render(problem.id);
+        NojResponseBuilder::new(Self {
+            locale: locale.to_string(),
+            locales: locales.to_vec(),
+            description: rendered.description,
+            input: rendered.input,
+            output: rendered.output,
+            hint: rendered.hint,
+            sample_input: desc.sample_input.clone(),
+            sample_output: desc.sample_output.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemStatsResponse {
+    #[serde(rename = "ACUser")]
+    pub ac_user: i64,
+    pub submitter: i64,
+    pub submit_count: i64,
+    pub high_score: i32,
+    pub verdicts: Vec<VerdictCountView>,
+    /// execution time distribution of accepted submissions
+    pub time_histogram: Vec<TimeBucketView>,
+}
+
+impl ProblemStatsResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(stats: &problems::stats::Stats) -> NojResponseBuilder<Self> {
+        let counts = &stats.counts;
+        NojResponseBuilder::new(Self {
+            ac_user: counts.ac_user,
+            submitter: counts.submitter,
+            submit_count: counts.submit_count,
+            high_score: counts.high_score,
+            verdicts: stats
+                .verdicts
+                .iter()
+                .map(|(status, count)| VerdictCountView {
+                    status: status.clone().into(),
+                    count: *count,
+                })
+                .collect(),
+            time_histogram: stats
+                .time_histogram
+                .iter()
+                .map(|b| TimeBucketView {
+                    lower: b.lower,
+                    upper: b.upper,
+                    count: b.count,
+                })
+                .collect(),
+        })
+    }
+}
@@ -1,13 +1,17 @@
+use std::collections::HashMap;
+
 use eyre::eyre;
+use loco_rs::controller::views::pagination::PagerMeta;
 use serde::Serialize;
 
-use super::NojResponseBuilder;
+use super::{NojResponseBuilder, PaginatedResponse};
 use crate::models::{
-    problems,
+    problems::{self, test_case::CaseIo},
     submissions::{self, status_str_to_i32, JudgeResult, Language, SubmissionStatus},
     users,
 };
 use crate::views::user::UserInfoResponse;
+use crate::workers::submission::SubmissionWorker;
 
 impl From<SubmissionStatus> for i32 {
     fn from(val: SubmissionStatus) -> Self {
@@ -83,22 +87,20 @@ pub struct SubmissionListResponseItem {
 }
 
 #[derive(Debug, Serialize)]
+#[serde(transparent)]
 #[allow(clippy::module_name_repetitions)]
-#[serde(rename_all = "camelCase")]
-pub struct SubmissionListResponse {
-    submissions: Vec<SubmissionListResponseItem>,
-    submission_count: i32,
-}
+pub struct SubmissionListResponse(PaginatedResponse<SubmissionListResponseItem>);
 
 impl SubmissionListResponse {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
-    #[allow(clippy::new_ret_no_self)]
     pub fn new(
         submissions: &[submissions::Model],
         users: &[users::Model],
+        pagination: PagerMeta,
+        total_items: u64,
     ) -> NojResponseBuilder<Self> {
-        let submissions = submissions
+        let results = submissions
             .iter()
             .zip(users)
             .map(|(p, u)| SubmissionListResponseItem {
@@ -116,11 +118,11 @@ impl SubmissionListResponse {
             })
             .collect();
 
-        NojResponseBuilder::new(Self {
-            submissions,
-            // TODO: use real data
-            submission_count: 123,
-        })
+        NojResponseBuilder::new(Self(PaginatedResponse {
+            results,
+            pagination,
+            total_items: Some(total_items),
+        }))
     }
 }
 
@@ -131,6 +133,54 @@ pub struct SubmissionCaseResponse {
     exec_time: i32,
     memory_usage: i32,
     status: i32,
+    /// I/O of this case, only exists for public cases
+    #[serde(skip_serializing_if = "Option::is_none")]
+    io: Option<SubmissionCaseIoResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::module_name_repetitions)]
+pub struct SubmissionCaseIoResponse {
+    input: String,
+    expected_output: String,
+    output: String,
+    output_truncated: bool,
+    /// The first different line between expected & contestant's output
+    diff: Option<OutputDiffResponse>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDiffResponse {
+    /// 1-based line number
+    line: usize,
+    expected: String,
+    actual: String,
+    /// Whether it's found on truncated output, so it may not be the real first
+    /// difference
+    truncated: bool,
+}
+
+impl SubmissionCaseIoResponse {
+    fn new(io: &CaseIo, result: &JudgeResult) -> Self {
+        let diff = SubmissionWorker::first_difference(&io.answer, &result.stdout).map(
+            |(line, expected, actual)| OutputDiffResponse {
+                line,
+                expected,
+                actual,
+                truncated: result.stdout_truncated,
+            },
+        );
+
+        Self {
+            input: io.input.clone(),
+            expected_output: io.answer.clone(),
+            output: result.stdout.clone(),
+            output_truncated: result.stdout_truncated,
+            diff,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -164,6 +214,9 @@ pub struct SubmissionDetailResponse {
 }
 
 impl SubmissionDetailResponse {
+    /// `public_io` contains I/O of public cases, keyed by (task index, case
+    /// index).
+    ///
     /// # Panics
     ///
     /// When failed to deserialize submission tasks model
@@ -172,13 +225,15 @@ impl SubmissionDetailResponse {
         submission: &submissions::Model,
         user: &users::Model,
         problem_tasks: &[problems::tasks::Model],
+        public_io: &HashMap<(usize, usize), CaseIo>,
     ) -> NojResponseBuilder<Self> {
         let tasks = submission.tasks.clone().unwrap_or(serde_json::json!([]));
         let tasks = serde_json::from_value::<Vec<Vec<JudgeResult>>>(tasks).unwrap();
         let tasks = problem_tasks
             .iter()
             .zip(&tasks)
-            .map(|(pt, tt)| {
+            .enumerate()
+            .map(|(i, (pt, tt))| {
                 let score = if tt.iter().all(|t| t.status == "AC") {
                     pt.score
                 } else {
@@ -187,10 +242,14 @@ impl SubmissionDetailResponse {
 
                 let cases = tt
                     .iter()
-                    .map(|t| SubmissionCaseResponse {
+                    .enumerate()
+                    .map(|(j, t)| SubmissionCaseResponse {
                         memory_usage: t.mem_usage,
                         exec_time: t.duration,
                         status: status_str_to_i32(&t.status),
+                        io: public_io
+                            .get(&(i, j))
+                            .map(|io| SubmissionCaseIoResponse::new(io, t)),
                     })
                     .collect::<Vec<_>>();
 
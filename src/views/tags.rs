@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+use crate::models::tags;
+
+use super::NojResponseBuilder;
+
+#[derive(Debug, Serialize)]
+pub struct TagView {
+    pub id: i32,
+    pub name: String,
+}
+
+pub struct TagListResponse {}
+
+impl TagListResponse {
+    #[must_use]
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(tags: &[tags::Model]) -> NojResponseBuilder<Vec<TagView>> {
+        let data = tags
+            .iter()
+            .map(|t| TagView {
+                id: t.id,
+                name: t.name.clone(),
+            })
+            .collect();
+
+        NojResponseBuilder::new(data)
+    }
+}
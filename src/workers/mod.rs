@@ -1,3 +1,4 @@
 pub mod downloader;
 
 pub mod submission;
+pub mod verify;
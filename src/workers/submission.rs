@@ -1,12 +1,17 @@
-use std::{fs::File, io::Write, path::PathBuf, process::Command};
+use std::path::PathBuf;
 
+use axum::body::Bytes;
 use eyre::eyre;
 use loco_rs::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::models::{
-    problems,
-    submissions::{self, JudgeResult, Language},
+use crate::{
+    models::{
+        problems::{self, solutions, test_case::Checker, validators},
+        submissions::{self, JudgeResult, Language},
+    },
+    sandbox,
+    settings::Settings,
 };
 
 /// Response to execute submissions
@@ -20,6 +25,10 @@ pub struct SubmissionWorker {
 pub struct SubmissionWorkerArgs {
     /// ID of submissions this work need to process
     pub submission_id: i32,
+    /// Problem revision current when the work is enqueued, the submission is
+    /// recorded as judged on it
+    #[serde(default)]
+    pub revision_id: Option<i32>,
 }
 
 impl worker::AppWorker<SubmissionWorkerArgs> for SubmissionWorker {
@@ -42,293 +51,298 @@ impl SubmissionWorker {
     pub fn compare_output(expected: &str, actual: &str) -> bool {
         Self::preprocess(expected).eq(Self::preprocess(actual))
     }
-}
 
-#[async_trait]
-impl worker::Worker<SubmissionWorkerArgs> for SubmissionWorker {
-    #[allow(clippy::too_many_lines)]
-    async fn perform(&self, args: SubmissionWorkerArgs) -> worker::Result<()> {
-        let db = &self.ctx.db;
+    /// Find the first line which makes two outputs different, using the same
+    /// rule as [`Self::compare_output`]. Returns its line number (1-based),
+    /// expected line and actual line.
+    #[must_use]
+    pub fn first_difference(expected: &str, actual: &str) -> Option<(usize, String, String)> {
+        let mut expected = Self::preprocess(expected).collect::<Vec<_>>();
+        expected.reverse();
+        let mut actual = Self::preprocess(actual).collect::<Vec<_>>();
+        actual.reverse();
 
-        // get submission && problem
-        let subm = submissions::Model::find_by_id(db, args.submission_id)
+        (0..expected.len().max(actual.len()))
+            .find(|&i| expected.get(i) != actual.get(i))
+            .map(|i| {
+                (
+                    i + 1,
+                    expected.get(i).copied().unwrap_or_default().to_string(),
+                    actual.get(i).copied().unwrap_or_default().to_string(),
+                )
+            })
+    }
+
+    /// Cap outputs stored in DB and save the judge results. The full outputs
+    /// of failed cases are kept in storage if it's enabled in settings.
+    /// `revision_id` is the problem revision the submission was judged on.
+    async fn save_results(
+        &self,
+        subm: submissions::Model,
+        problem: &problems::Model,
+        revision_id: Option<i32>,
+        mut results: Vec<Vec<JudgeResult>>,
+    ) -> worker::Result<()> {
+        let settings = Settings::from_ctx(&self.ctx).map_err(Box::from)?.judge;
+
+        for (i, task_results) in results.iter_mut().enumerate() {
+            for (j, r) in task_results.iter_mut().enumerate() {
+                let exceeded = r.stdout.len() > settings.max_stored_output
+                    || r.stderr.len() > settings.max_stored_output;
+                if settings.keep_failed_output && exceeded && r.status != "AC" {
+                    let task_id = i.try_into().unwrap();
+                    let case_id = j.try_into().unwrap();
+                    for (stream, content) in [("stdout", &r.stdout), ("stderr", &r.stderr)] {
+                        let path = subm.output_path(task_id, case_id, stream);
+                        self.ctx
+                            .storage
+                            .upload(path.as_path(), &Bytes::from(content.clone()))
+                            .await
+                            .map_err(|e| Box::from(eyre!("failed to store full output: {e}")))?;
+                    }
+                    r.full_output_stored = true;
+                }
+                r.truncate_output(settings.max_stored_output);
+            }
+        }
+
+        let mut subm = subm.into_active_model();
+        subm.revision_id = ActiveValue::set(revision_id);
+        subm.update_sandbox_result(&self.ctx.db, problem, results)
             .await
             .map_err(Box::from)?;
-        let problem = problems::Model::find_by_id(db, subm.problem_id)
+
+        Ok(())
+    }
+}
+
+/// Local directory where the current test case of problem is extracted by
+/// [`prepare_test_case`], it may not exist yet.
+#[must_use]
+pub fn test_case_dir(problem: &problems::Model) -> Option<PathBuf> {
+    problem.test_case_id.as_ref().map(|test_case_id| {
+        PathBuf::from("problem")
+            .join(problem.id.to_string())
+            .join(test_case_id)
+    })
+}
+
+/// Extract test case of problem to local disk if it's not there yet, returns
+/// the extracted directory. Generated cases are materialized at the same time.
+/// Each test case package gets its own directory, so uploading or rolling back
+/// never leaves a stale one in use.
+///
+/// # Errors
+///
+/// When the test case could not be downloaded, extracted or generated
+pub async fn prepare_test_case(
+    ctx: &AppContext,
+    problem: &problems::Model,
+) -> worker::Result<PathBuf> {
+    let (Some(test_case_id), Some(test_case_path), Some(problem_dir)) = (
+        problem.test_case_id.as_deref(),
+        problem.test_case_path(),
+        test_case_dir(problem),
+    ) else {
+        return Err(Box::from(problems::Error::NoTestCase))?;
+    };
+    let parent_dir = PathBuf::from("problem").join(problem.id.to_string());
+    if !problem_dir.exists() {
+        // get test case binary and unzip it
+        let test_case: Vec<u8> = ctx
+            .storage
+            .download(test_case_path.as_path())
             .await
+            .map_err(|e| Box::from(eyre!("failed to download test case: {e}")))?;
+
+        // prepare in a staging dir, so that a half-done one is never used, and
+        // it's removed on any error
+        std::fs::create_dir_all(&parent_dir)
+            .map_err(|e| Box::from(eyre!("failed to create problem dir: {e}")))?;
+        let staging_dir = tempfile::Builder::new()
+            .prefix(&format!("{test_case_id}."))
+            .tempdir_in(&parent_dir)
+            .map_err(|e| Box::from(eyre!("failed to create staging dir: {e}")))?;
+        let cursor = std::io::Cursor::new(test_case);
+        let mut problem_zip = zip::ZipArchive::new(cursor)
+            .map_err(|e| problems::Error::BadTestCase(problems::BadTestCase::ZipError(e)))
             .map_err(Box::from)?;
-        let tasks = problem.tasks(db).await.map_err(Box::from)?;
+        problem_zip
+            .extract(staging_dir.path())
+            .map_err(|e| Box::from(eyre!("failed to extract problem zip: {e}")))?;
 
-        let submission_dir = tempfile::tempdir()
-            .map_err(|e| Box::from(eyre!("failed to create submission dir: {e}")))?;
-        // extarct submission source
-        let source_path = match subm.language {
-            Language::C => submission_dir.path().join("main.c"),
-            Language::Cpp => submission_dir.path().join("main.cpp"),
-            Language::Python => submission_dir.path().join("main.py"),
-        };
-        let mut source_file = File::create(source_path.as_path())
-            .map_err(|e| Box::from(eyre!("failed to create source code: {e}")))?;
-        source_file
-            .write_all(subm.code.as_bytes())
-            .map_err(|e| Box::from(eyre!("failed to write source code: {e}")))?;
-        // compile submission if needed
-        let dup_judge_results = |r: &JudgeResult, p: &[problems::tasks::Model]| {
-            let mut all = vec![];
-            for (i, t) in p.iter().enumerate() {
-                let mut seg = vec![];
-                for j in 0..t.test_case_count {
-                    let mut rs = r.clone();
-                    rs.task_id = i.try_into().unwrap();
-                    rs.case_id = j;
-                    seg.push(rs);
-                }
-                all.push(seg);
+        let manifest = problems::test_case::Manifest::from_dir(staging_dir.path())
+            .map_err(|e| Box::from(problems::Error::BadTestCase(e)))?;
+        if let Some(manifest) = manifest.filter(|m| !m.generated_cases().is_empty()) {
+            let Some(main_solution) = solutions::Model::find_main(&ctx.db, problem.id)
+                .await
+                .map_err(Box::from)?
+            else {
+                return Err(Box::from(problems::Error::BadTestCase(
+                    problems::BadTestCase::Custom("main solution not found".to_string()),
+                )))?;
+            };
+            let validator = validators::Model::find_by_problem(&ctx.db, problem.id)
+                .await
+                .map_err(Box::from)?;
+            problems::generators::materialize(
+                staging_dir.path(),
+                &manifest,
+                &main_solution,
+                validator.as_ref(),
+            )
+            .map_err(|e| Box::from(problems::Error::BadTestCase(e)))?;
+        }
+
+        match std::fs::rename(staging_dir.path(), &problem_dir) {
+            // it's moved, nothing to clean up
+            Ok(()) => {
+                let _ = staging_dir.into_path();
             }
-            all
+            // prepared by another worker meanwhile, staging dir is dropped
+            Err(_) if problem_dir.exists() => {}
+            Err(e) => return Err(Box::from(eyre!("failed to move test case: {e}")))?,
+        }
+
+        tracing::info!(
+            problem_id = problem.id,
+            problem_name = problem.name,
+            test_case_id,
+            "extract problem test case"
+        );
+    }
+
+    Ok(problem_dir.canonicalize().map_err(Box::from)?)
+}
+
+/// Compile `code` and run it against all test cases of `problem`. Compile
+/// error is reported as `CE` of every case.
+///
+/// # Errors
+///
+/// When the test case could not be prepared or sandbox could not be invoked
+pub async fn judge(
+    ctx: &AppContext,
+    problem: &problems::Model,
+    tasks: &[problems::tasks::Model],
+    language: &Language,
+    code: &str,
+) -> worker::Result<Vec<Vec<JudgeResult>>> {
+    let submission_dir = tempfile::tempdir()
+        .map_err(|e| Box::from(eyre!("failed to create submission dir: {e}")))?;
+    // extarct submission source & compile it if needed
+    let compile_output =
+        sandbox::compile(submission_dir.path(), language, code).map_err(Box::from)?;
+    if !compile_output.success {
+        // dup CE result
+        let result = JudgeResult {
+            status: "CE".to_string(),
+            stdout: compile_output.stdout,
+            stderr: compile_output.stderr,
+            task_id: 0,
+            case_id: 0,
+            // TODO: use real data
+            duration: 1000,
+            mem_usage: 32768,
+            stdout_truncated: false,
+            stderr_truncated: false,
+            full_output_stored: false,
         };
-        match subm.language {
-            Language::C => {
-                let output = Command::new("gcc")
-                    .args([
-                        "-DONLINE_JUDGE",
-                        "-O2",
-                        "-w",
-                        "-fmax-errors=3",
-                        "-std=c11",
-                        "main.c",
-                        "-lm",
-                        "-o",
-                        "main",
-                    ])
-                    .current_dir(&submission_dir)
-                    .output()
-                    .map_err(|e| Box::from(eyre!("failed to compile c submission: {e}")))?;
-
-                if !output.status.success() {
-                    // dup CE result
-                    let result: JudgeResult = JudgeResult {
-                        status: "CE".to_string(),
-                        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                        task_id: 0,
-                        case_id: 0,
-                        // TODO: use real data
-                        duration: 1000,
-                        mem_usage: 32768,
-                    };
-                    let all_results = dup_judge_results(&result, &tasks);
-                    subm.into_active_model()
-                        .update_sandbox_result(db, &problem, all_results)
-                        .await
-                        .map_err(Box::from)?;
-                    return Ok(());
-                }
+        let mut all_results = vec![];
+        for (i, t) in tasks.iter().enumerate() {
+            let mut seg = vec![];
+            for j in 0..t.test_case_count {
+                let mut rs = result.clone();
+                rs.task_id = i.try_into().unwrap();
+                rs.case_id = j;
+                seg.push(rs);
             }
-            Language::Cpp => {
-                let output = Command::new("g++")
-                    .args([
-                        "-DONLINE_JUDGE",
-                        "-O2",
-                        "-w",
-                        "-fmax-errors=3",
-                        "-std=c++17",
-                        "main.cpp",
-                        "-lm",
-                        "-o",
-                        "main",
-                    ])
-                    .current_dir(&submission_dir)
-                    .output()
-                    .map_err(|e| Box::from(eyre!("failed to compile c++ submission: {e}")))?;
-                if !output.status.success() {
-                    // dup CE result
-                    let result = JudgeResult {
-                        status: "CE".to_string(),
-                        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                        task_id: 0,
-                        case_id: 0,
-                        // TODO: use real data
-                        duration: 1000,
-                        mem_usage: 32768,
-                    };
-                    let all_results = dup_judge_results(&result, &tasks);
-                    subm.into_active_model()
-                        .update_sandbox_result(db, &problem, all_results)
-                        .await
-                        .map_err(Box::from)?;
-                    return Ok(());
-                }
-            }
-            Language::Python => {}
+            all_results.push(seg);
         }
+        return Ok(all_results);
+    }
 
-        // check problems test case
-        let problem_dir = PathBuf::from("problem").join(problem.id.to_string());
-        if !problem_dir.exists() {
-            // get test case binary and unzip it
-            let Some(test_case_path) = problem.test_case_path() else {
-                return Err(Box::from(problems::Error::NoTestCase))?;
-            };
-            let test_case: Vec<u8> = self
-                .ctx
-                .storage
-                .download(test_case_path.as_path())
-                .await
-                .map_err(|e| Box::from(eyre!("failed to download test case: {e}")))?;
+    // check problems test case
+    let problem_dir = prepare_test_case(ctx, problem).await?;
+    let checker = problems::test_case::Manifest::from_dir(&problem_dir)
+        .map_err(|e| Box::from(problems::Error::BadTestCase(e)))?
+        .map(|m| m.checker)
+        .unwrap_or_default();
 
-            let cursor = std::io::Cursor::new(test_case);
-            let mut problem_zip = zip::ZipArchive::new(cursor)
-                .map_err(|e| problems::Error::BadTestCase(problems::BadTestCase::ZipError(e)))
-                .map_err(Box::from)?;
-            problem_zip
-                .extract(problem_dir.as_path())
-                .map_err(|e| Box::from(eyre!("failed to extract problem zip: {e}")))?;
-
-            tracing::info!(
-                problem_id = problem.id,
-                problem_name = problem.name,
-                test_case_id = problem.test_case_id,
-                "extract problem test case"
-            );
-
-            // TODO: validate directory structure?
-        }
-        let problem_dir = problem_dir.canonicalize().map_err(Box::from)?;
-
-        // collect judge results
-        let mut all_judge_results: Vec<Vec<JudgeResult>> = vec![];
-        for (i, task) in tasks.iter().enumerate() {
-            let mut task_results = vec![];
-            for j in 0..task.test_case_count {
-                let case_id = format!("{i:02}{j:02}");
-                let stdin_path = problem_dir.join("test-case").join(&case_id).join("STDIN");
-                let answer_path = problem_dir.join("test-case").join(&case_id).join("STDOUT");
-                let stdin_str = stdin_path.to_string_lossy();
-                let output_dir = tempfile::tempdir()
-                    .map_err(|e| Box::from(eyre!("failed to create output dir: {e}")))?;
-                let stdout_path = output_dir.path().join("stdout");
-                let stderr_path = output_dir.path().join("stderr");
-                let output_path = output_dir.path().join("output");
-                let stdout_str = stdout_path.to_string_lossy();
-                let stderr_str = stderr_path.to_string_lossy();
-                let output_str = output_path.to_string_lossy();
-                let time_limit = task.time_limit;
-                let memory_limit = task.memory_limit;
-                let lang: i32 = subm.language.clone().into();
-
-                // save config to disk
-                let config = toml::toml! {
-                    cwd = "."
-                    large-stack = true
-                    max-process = 10
-                    memory-limit = memory_limit
-                    output-size-limit = 10000
-                    runtime-limit = time_limit
-                    lang = lang
-                    stdin = stdin_str
-                    stdout = stdout_str
-                    stderr = stderr_str
-                    output = output_str
+    // collect judge results
+    let mut all_judge_results: Vec<Vec<JudgeResult>> = vec![];
+    for (i, task) in tasks.iter().enumerate() {
+        let mut task_results = vec![];
+        for (j, case) in (0..task.test_case_count).zip(task.case_meta()) {
+            #[allow(clippy::cast_sign_loss)]
+            let case_dir = problem_dir.join(case.dir(i, j as usize));
+            let stdin_path = case_dir.join("STDIN");
+            let answer_path = case_dir.join("STDOUT");
+            let output = sandbox::run(
+                submission_dir.path(),
+                &sandbox::RunConfig {
+                    language,
+                    stdin: &stdin_path,
+                    time_limit: task.time_limit,
+                    memory_limit: task.memory_limit,
+                },
+            )
+            .map_err(Box::from)?;
+
+            let status = if output.exited_normally() {
+                let answer = std::fs::read_to_string(answer_path)
+                    .map_err(|e| Box::from(eyre!("failed to read answer: {e}")))?;
+                let accepted = match checker {
+                    Checker::Diff => SubmissionWorker::compare_output(&answer, &output.stdout),
+                    Checker::Exact => answer == output.stdout,
                 };
-                let config_path = output_dir.path().join("noj.toml");
-                let mut config_file = File::create(&config_path).map_err(Box::from)?;
-                config_file
-                    .write_all(
-                        &toml::to_string(&config)
-                            .map(std::string::String::into_bytes)
-                            .map_err(Box::from)?,
-                    )
-                    .map_err(Box::from)?;
-
-                // invoke sandbox process
-                // TODO: configurable sandbox path
-                let sandbox_output = Command::new("sandbox")
-                    .args(["--env-path", config_path.to_string_lossy().as_ref()])
-                    .current_dir(&submission_dir)
-                    .output();
-                match sandbox_output {
-                    Ok(o) if !o.status.success() => {
-                        task_results.push(JudgeResult {
-                            status: "JE".to_string(), // judge error
-                            duration: -1,
-                            mem_usage: -1,
-                            stdout: String::from_utf8_lossy(&o.stdout).to_string(),
-                            stderr: String::from_utf8_lossy(&o.stderr).to_string(),
-                            task_id: 0,
-                            case_id: 0,
-                        });
-                    }
-                    Ok(_) => {
-                        let sandbox_status_raw_string = std::fs::read_to_string(&output_path)
-                            .map_err(|e| {
-                                Box::from(eyre!(
-                                    "failed to read sandbox result @{}: {}",
-                                    output_path.display(),
-                                    e
-                                ))
-                            })?;
-                        let sandbox_status_raw_string =
-                            sandbox_status_raw_string.lines().collect::<Vec<_>>();
-
-                        let duration_ms: i32 =
-                            sandbox_status_raw_string[2].parse().map_err(Box::from)?;
-                        let mem_usage_kb: i32 =
-                            sandbox_status_raw_string[3].parse().map_err(Box::from)?;
-                        let _exit_msg = sandbox_status_raw_string[1].to_string();
-                        let stdout = std::fs::read_to_string(stdout_path)
-                            .map_err(|e| Box::from(eyre!("failed to read stdout: {e}")))?;
-                        let stderr = std::fs::read_to_string(stderr_path)
-                            .map_err(|e| Box::from(eyre!("failed to read stderr: {e}")))?;
-                        let status = {
-                            let s = sandbox_status_raw_string[0].to_string();
-                            let answer = std::fs::read_to_string(answer_path)
-                                .map_err(|e| Box::from(eyre!("failed to read answer: {e}")))?;
-
-                            match s.as_str() {
-                                "TLE" | "MLE" | "RE" | "OLE" => s,
-                                _ => {
-                                    if Self::compare_output(&answer, &stdout) {
-                                        "AC".to_string()
-                                    } else {
-                                        "WA".to_string()
-                                    }
-                                }
-                            }
-                        };
-
-                        task_results.push(JudgeResult {
-                            status,
-                            duration: duration_ms,
-                            mem_usage: mem_usage_kb,
-                            stdout,
-                            stderr,
-                            task_id: i.try_into().unwrap(),
-                            case_id: j,
-                        });
-                    }
-                    Err(e) => {
-                        task_results.push(JudgeResult {
-                            status: "JE".to_string(), // judge error
-                            duration: -1,
-                            mem_usage: -1,
-                            stdout: String::new(),
-                            stderr: e.to_string(),
-                            task_id: i.try_into().unwrap(),
-                            case_id: j,
-                        });
-                    }
+                if accepted {
+                    "AC".to_string()
+                } else {
+                    "WA".to_string()
                 }
-            }
-            all_judge_results.push(task_results);
+            } else {
+                output.status
+            };
+
+            task_results.push(JudgeResult {
+                status,
+                duration: output.duration,
+                mem_usage: output.mem_usage,
+                stdout: output.stdout,
+                stderr: output.stderr,
+                task_id: i.try_into().unwrap(),
+                case_id: j,
+                stdout_truncated: false,
+                stderr_truncated: false,
+                full_output_stored: false,
+            });
         }
+        all_judge_results.push(task_results);
+    }
 
-        // upload judge result
-        subm.into_active_model()
-            .update_sandbox_result(db, &problem, all_judge_results)
+    Ok(all_judge_results)
+}
+
+#[async_trait]
+impl worker::Worker<SubmissionWorkerArgs> for SubmissionWorker {
+    async fn perform(&self, args: SubmissionWorkerArgs) -> worker::Result<()> {
+        let db = &self.ctx.db;
+
+        // get submission && problem
+        let subm = submissions::Model::find_by_id(db, args.submission_id)
             .await
             .map_err(Box::from)?;
+        let problem = problems::Model::find_by_id(db, subm.problem_id)
+            .await
+            .map_err(Box::from)?;
+        let tasks = problem.tasks(db).await.map_err(Box::from)?;
+
+        let results = judge(&self.ctx, &problem, &tasks, &subm.language, &subm.code).await?;
+
+        // upload judge result
+        self.save_results(subm, &problem, args.revision_id, results)
+            .await?;
 
         Ok(())
     }
@@ -365,4 +379,20 @@ mod tests {
             assert!(SubmissionWorker::compare_output(a, b) == result);
         }
     }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(
+            SubmissionWorker::first_difference("aaa\nbbb\n", "aaa  \nbbb\n\n"),
+            None
+        );
+        assert_eq!(
+            SubmissionWorker::first_difference("aaa\nbbb\n", "aaa\nccc\n"),
+            Some((2, "bbb".to_string(), "ccc".to_string()))
+        );
+        assert_eq!(
+            SubmissionWorker::first_difference("aaa\nbbb\n", "aaa\n"),
+            Some((2, "bbb".to_string(), String::new()))
+        );
+    }
 }
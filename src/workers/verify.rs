@@ -0,0 +1,80 @@
+use loco_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::problems::{
+        self,
+        solutions::{self, SolutionVerdict, Verification},
+    },
+    workers::submission,
+};
+
+/// Judge reference solutions of a problem against its current test case and
+/// record whether they get their expected verdicts.
+pub struct VerifyWorker {
+    pub ctx: AppContext,
+}
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct VerifyWorkerArgs {
+    pub problem_id: i32,
+}
+
+impl worker::AppWorker<VerifyWorkerArgs> for VerifyWorker {
+    fn build(ctx: &AppContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+}
+
+#[async_trait]
+impl worker::Worker<VerifyWorkerArgs> for VerifyWorker {
+    async fn perform(&self, args: VerifyWorkerArgs) -> worker::Result<()> {
+        let db = &self.ctx.db;
+
+        let problem = problems::Model::find_by_id(db, args.problem_id)
+            .await
+            .map_err(Box::from)?;
+        let Some(test_case_id) = problem.test_case_id.clone() else {
+            return Err(Box::from(problems::Error::NoTestCase))?;
+        };
+        let tasks = problem.tasks(db).await.map_err(Box::from)?;
+        let solutions = solutions::Model::list_by_problem(db, problem.id)
+            .await
+            .map_err(Box::from)?;
+
+        let mut verdicts = vec![];
+        for solution in solutions {
+            let results = submission::judge(
+                &self.ctx,
+                &problem,
+                &tasks,
+                &solution.language,
+                &solution.code,
+            )
+            .await?;
+            verdicts.push(SolutionVerdict {
+                solution_id: solution.id,
+                name: solution.name,
+                expected: solution.expected_status,
+                actual: solutions::verdict(&results),
+            });
+        }
+
+        let verification = Verification {
+            test_case_id,
+            verdicts,
+        };
+        tracing::info!(
+            problem_id = problem.id,
+            passed = verification.passed(),
+            "problem verified"
+        );
+        problem
+            .into_active_model()
+            .update_verification(db, Some(&verification))
+            .await
+            .map_err(Box::from)?;
+
+        Ok(())
+    }
+}
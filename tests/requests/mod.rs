@@ -2,6 +2,7 @@ mod auth;
 mod prepare_data;
 mod problems;
 mod submissions;
+mod tags;
 mod user;
 
 use loco_rs::{
@@ -16,8 +17,8 @@ pub async fn create_token(user: &users::Model, ctx: &AppContext) -> String {
         .unwrap()
 }
 
-pub fn create_cookie(token: &str) -> Cookie {
-    let mut c = Cookie::new("piann", token);
+pub fn create_cookie(token: &str) -> Cookie<'static> {
+    let mut c = Cookie::new("piann", token.to_string());
     c.set_http_only(true);
     c.set_path("/");
     c.set_same_site(SameSite::Lax);
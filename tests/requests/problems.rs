@@ -2,15 +2,20 @@ use super::{create_token, prepare_data};
 use crate::{make_test_case, requests::create_cookie};
 use axum_test::multipart::{MultipartForm, Part};
 use insta::{assert_debug_snapshot, with_settings};
-use loco_rs::testing;
+use loco_rs::{app::AppContext, testing};
 use normal_oj::{
     app::App,
-    models::problems::{self, Type, Visibility},
-    models::users,
+    models::problems::{
+        self,
+        solutions::{SolutionVerdict, Verification},
+        Type, Visibility,
+    },
+    models::{courses, submissions, tags, test_case_uploads, users},
 };
+use sea_orm::{ActiveModelTrait, IntoActiveModel};
 use serde_json::json;
 use serial_test::serial;
-use std::path::Path;
+use std::{io::Write, path::Path};
 
 macro_rules! configure_insta {
     () => {
@@ -40,6 +45,43 @@ fn create_problem_payload() -> serde_json::Value {
     })
 }
 
+/// A shown problem with one task of 2 cases, in no course
+fn problem_params(owner: users::Model) -> problems::AddParams {
+    problems::AddParams {
+        owner,
+        courses: vec![],
+        name: "test-course".to_string(),
+        status: Some(Visibility::Show),
+        description: problems::descriptions::AddParams {
+            description: String::new(),
+            input: String::new(),
+            output: String::new(),
+            hint: String::new(),
+            sample_input: vec![],
+            sample_output: vec![],
+        },
+        r#type: Some(Type::Normal),
+        allowed_language: None,
+        quota: None,
+        default_locale: None,
+        tasks: vec![problems::tasks::AddParams {
+            test_case_count: 2,
+            score: 100,
+            time_limit: 1000,
+            memory_limit: 65535,
+            cases: vec![],
+        }],
+    }
+}
+
+async fn add_problem(ctx: &AppContext, params: problems::AddParams) -> problems::Model {
+    problems::Model::add(&ctx.db, &params).await.unwrap()
+}
+
+async fn create_problem(ctx: &AppContext, owner: users::Model) -> problems::Model {
+    add_problem(ctx, problem_params(owner)).await
+}
+
 #[tokio::test]
 #[serial]
 async fn student_cannot_create_problem() {
@@ -65,6 +107,75 @@ async fn student_cannot_create_problem() {
     .await;
 }
 
+#[tokio::test]
+#[serial]
+async fn teacher_can_create_problem_in_taught_course() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        courses::ActiveModel {
+            name: sea_orm::ActiveValue::set("course2".to_string()),
+            teacher_id: sea_orm::ActiveValue::set(first_admin.id),
+            ..Default::default()
+        }
+        .insert(&ctx.db)
+        .await
+        .unwrap();
+
+        for (courses, allowed) in [
+            (json!([]), false),
+            (json!(["course2"]), false),
+            (json!(["course1", "course2"]), false),
+            (json!(["course1"]), true),
+        ] {
+            let mut payload = create_problem_payload();
+            payload["courses"] = courses;
+            let response = request
+                .post("/api/problems")
+                .add_cookie(cookie.clone())
+                .json(&payload)
+                .await;
+            if allowed {
+                response.assert_status_ok();
+            } else {
+                response.assert_status_forbidden();
+            }
+        }
+
+        // nor move problems into courses they don't teach
+        let problem = add_problem(
+            &ctx,
+            problems::AddParams {
+                courses: vec!["course1".to_string()],
+                ..problem_params(teacher)
+            },
+        )
+        .await;
+        for (courses, allowed) in [(json!(["course2"]), false), (json!(["course1"]), true)] {
+            let response = request
+                .patch(&format!("/api/problems/{}", problem.id))
+                .add_cookie(cookie.clone())
+                .json(&json!({ "courses": courses }))
+                .await;
+            if allowed {
+                response.assert_status_ok();
+            } else {
+                response.assert_status_forbidden();
+            }
+        }
+    })
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn admin_can_create_problem_and_test_case() {
@@ -78,41 +189,14 @@ async fn admin_can_create_problem_and_test_case() {
             .unwrap();
         let token = create_token(&first_admin, &ctx).await;
         let cookie = create_cookie(&token);
-        let problem = problems::Model::add(
-            &ctx.db,
-            &problems::AddParams {
-                owner: first_admin,
-                courses: vec![],
-                name: "test-course".to_string(),
-                status: Some(Visibility::Show),
-                description: problems::descriptions::AddParams {
-                    description: String::new(),
-                    input: String::new(),
-                    output: String::new(),
-                    hint: String::new(),
-                    sample_input: vec![],
-                    sample_output: vec![],
-                },
-                r#type: Some(Type::Normal),
-                allowed_language: None,
-                quota: None,
-                tasks: vec![problems::tasks::AddParams {
-                    test_case_count: 2,
-                    score: 100,
-                    time_limit: 1000,
-                    memory_limit: 65535,
-                }],
-            },
-        )
-        .await
-        .unwrap();
+        let problem = create_problem(&ctx, first_admin).await;
         let test_case_content = make_test_case(&ctx.db, &problem).await.unwrap();
         let test_case = Part::bytes(test_case_content.clone())
             .file_name("test-case.zip")
             .mime_type("application/x-zip");
         let form = MultipartForm::new().add_part("case", test_case);
         let response = request
-            .put(&format!("/api/problems/{}", problem.id))
+            .put(&format!("/api/problems/{}/test-case", problem.id))
             .add_cookie(cookie)
             .multipart(form)
             .await;
@@ -143,34 +227,27 @@ async fn view_single_problem() {
             .await
             .unwrap();
         let cookie = create_cookie(&user.token);
-        let problem = problems::Model::add(
-            &ctx.db,
-            &problems::AddParams {
-                owner: first_admin,
-                courses: vec![],
-                name: "test-course".to_string(),
-                status: Some(Visibility::Show),
-                description: problems::descriptions::AddParams {
-                    description: String::new(),
-                    input: String::new(),
-                    output: String::new(),
-                    hint: String::new(),
-                    sample_input: vec![],
-                    sample_output: vec![],
-                },
-                r#type: Some(Type::Normal),
-                allowed_language: None,
-                quota: None,
-                tasks: vec![problems::tasks::AddParams {
-                    test_case_count: 2,
-                    score: 100,
-                    time_limit: 1000,
-                    memory_limit: 65535,
-                }],
+        let problem = add_problem(
+            &ctx,
+            problems::AddParams {
+                courses: vec!["course1".to_string()],
+                ..problem_params(first_admin)
             },
         )
-        .await
-        .unwrap();
+        .await;
+        // students only view problems in their courses
+        let response = request
+            .get(&format!("/api/problems/{}", problem.id))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_forbidden();
+
+        courses::Model::find_by_name(&ctx.db, "course1")
+            .await
+            .unwrap()
+            .add_member(&ctx.db, user.user.id)
+            .await
+            .unwrap();
         let response = request
             .get(&format!("/api/problems/{}", problem.id))
             .add_cookie(cookie)
@@ -185,3 +262,1948 @@ async fn view_single_problem() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn upload_test_case_with_manifest_updates_tasks() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let token = create_token(&first_admin, &ctx).await;
+        let problem = create_problem(&ctx, first_admin).await;
+
+        let meta = json!({
+            "tasks": [
+                {
+                    "score": 30,
+                    "time_limit": 1000,
+                    "memory_limit": 65536,
+                    "cases": [{ "name": "sample", "public": true }],
+                },
+                {
+                    "score": 70,
+                    "time_limit": 2000,
+                    "memory_limit": 65536,
+                    "cases": [{}, {}],
+                },
+            ],
+        });
+        let mut buf = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            let opt = zip::write::SimpleFileOptions::default();
+            zip.start_file("meta.json", opt).unwrap();
+            zip.write_all(meta.to_string().as_bytes()).unwrap();
+            for dir in ["sample", "0100", "0101"] {
+                zip.start_file(format!("test-case/{dir}/STDIN"), opt)
+                    .unwrap();
+                zip.write_all(b"1 2\n").unwrap();
+                zip.start_file(format!("test-case/{dir}/STDOUT"), opt)
+                    .unwrap();
+                zip.write_all(b"3\n").unwrap();
+            }
+            zip.finish().unwrap();
+        }
+
+        let test_case = Part::bytes(buf.into_inner())
+            .file_name("test-case.zip")
+            .mime_type("application/x-zip");
+        let form = MultipartForm::new().add_part("case", test_case);
+        let response = request
+            .put(&format!("/api/problems/{}/test-case", problem.id))
+            .add_cookie(create_cookie(&token))
+            .multipart(form)
+            .await;
+        response.assert_status_ok();
+
+        let tasks = problem.tasks(&ctx.db).await.unwrap();
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|t| (t.test_case_count, t.score, t.time_limit))
+                .collect::<Vec<_>>(),
+            vec![(1, 30, 1000), (2, 70, 2000)],
+        );
+        assert_eq!(tasks[0].public_cases(), vec![0]);
+        assert_eq!(tasks[0].case_meta()[0].dir(0, 0), "test-case/sample");
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn only_owner_can_manage_validator() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let owner_cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let teacher_cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        let problem = create_problem(&ctx, first_admin).await;
+        let url = format!("/api/problems/{}/validator", problem.id);
+        let validator = json!({
+            "language": 2,
+            "code": "import sys\nassert len(sys.stdin.read().split()) == 2\n",
+        });
+
+        let response = request
+            .put(&url)
+            .add_cookie(teacher_cookie.clone())
+            .json(&validator)
+            .await;
+        response.assert_status_forbidden();
+
+        let response = request.get(&url).add_cookie(owner_cookie.clone()).await;
+        response.assert_status_not_found();
+
+        let response = request
+            .put(&url)
+            .add_cookie(owner_cookie.clone())
+            .json(&validator)
+            .await;
+        response.assert_status_ok();
+
+        let response = request.get(&url).add_cookie(owner_cookie.clone()).await;
+        response.assert_status_ok();
+        let body = response.json::<serde_json::Value>();
+        assert_eq!(body["data"]["language"], 2);
+        assert_eq!(body["data"]["code"], validator["code"]);
+
+        let response = request.get(&url).add_cookie(teacher_cookie).await;
+        response.assert_status_forbidden();
+
+        let response = request.delete(&url).add_cookie(owner_cookie.clone()).await;
+        response.assert_status_ok();
+        let response = request.get(&url).add_cookie(owner_cookie).await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn problem_failing_verification_cannot_be_shown() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let owner_cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let teacher_cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        let problem = add_problem(
+            &ctx,
+            problems::AddParams {
+                status: Some(Visibility::Hidden),
+                ..problem_params(first_admin)
+            },
+        )
+        .await;
+        let problem = problem
+            .into_active_model()
+            .update_test_case_id(&ctx.db, Some("test-case".to_string()))
+            .await
+            .unwrap();
+
+        let url = format!("/api/problems/{}/solutions", problem.id);
+        let solution = json!({
+            "name": "brute force",
+            "language": 2,
+            "code": "print(input())",
+            "expected_status": "TLE",
+        });
+        let response = request
+            .post(&url)
+            .add_cookie(teacher_cookie)
+            .json(&solution)
+            .await;
+        response.assert_status_forbidden();
+        let mut unknown_status = solution.clone();
+        unknown_status["expected_status"] = json!("XD");
+        let response = request
+            .post(&url)
+            .add_cookie(owner_cookie.clone())
+            .json(&unknown_status)
+            .await;
+        response.assert_status_bad_request();
+        let response = request
+            .post(&url)
+            .add_cookie(owner_cookie.clone())
+            .json(&solution)
+            .await;
+        response.assert_status_ok();
+        let solution_id = response.json::<serde_json::Value>()["data"]["id"]
+            .as_i64()
+            .unwrap();
+
+        // unverified problem with reference solutions cannot be shown
+        let status_url = format!("/api/problems/{}/status", problem.id);
+        let response = request
+            .put(&status_url)
+            .add_cookie(owner_cookie.clone())
+            .json(&json!({ "status": 0 }))
+            .await;
+        response.assert_status_bad_request();
+
+        let verification = |test_case_id: &str, actual: &str| Verification {
+            test_case_id: test_case_id.to_string(),
+            verdicts: vec![SolutionVerdict {
+                solution_id: solution_id.try_into().unwrap(),
+                name: "brute force".to_string(),
+                expected: "TLE".to_string(),
+                actual: actual.to_string(),
+            }],
+        };
+        let problem = problem
+            .into_active_model()
+            .update_verification(&ctx.db, Some(&verification("test-case", "AC")))
+            .await
+            .unwrap();
+        let response = request
+            .put(&status_url)
+            .add_cookie(owner_cookie.clone())
+            .json(&json!({ "status": 0 }))
+            .await;
+        response.assert_status_bad_request();
+
+        // passing verification of an outdated test case does not count
+        let problem = problem
+            .into_active_model()
+            .update_verification(&ctx.db, Some(&verification("old-test-case", "TLE")))
+            .await
+            .unwrap();
+        let response = request
+            .put(&status_url)
+            .add_cookie(owner_cookie.clone())
+            .json(&json!({ "status": 0 }))
+            .await;
+        response.assert_status_bad_request();
+
+        let problem = problem
+            .into_active_model()
+            .update_verification(&ctx.db, Some(&verification("test-case", "TLE")))
+            .await
+            .unwrap();
+        let response = request
+            .put(&status_url)
+            .add_cookie(owner_cookie.clone())
+            .json(&json!({ "status": 0 }))
+            .await;
+        response.assert_status_ok();
+
+        // a new test case is not verified yet, the problem is hidden again
+        let test_case = Part::bytes(make_test_case(&ctx.db, &problem).await.unwrap())
+            .file_name("test-case.zip")
+            .mime_type("application/x-zip");
+        let response = request
+            .put(&format!("/api/problems/{}/test-case", problem.id))
+            .add_cookie(owner_cookie.clone())
+            .multipart(MultipartForm::new().add_part("case", test_case))
+            .await;
+        response.assert_status_ok();
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        assert_eq!(problem.status, Visibility::Hidden as i32);
+
+        let response = request
+            .patch(&format!("/api/problems/{}", problem.id))
+            .add_cookie(owner_cookie)
+            .json(&json!({ "name": "renamed", "status": 0 }))
+            .await;
+        response.assert_status_bad_request();
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        assert_ne!(problem.name, "renamed");
+        assert_eq!(problem.status, Visibility::Hidden as i32);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn generated_test_case_is_checked_on_upload() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let problem = create_problem(&ctx, first_admin).await;
+
+        let package = |generator: &str, source: &[u8]| {
+            let meta = json!({
+                "tasks": [{
+                    "score": 100,
+                    "time_limit": 1000,
+                    "memory_limit": 65536,
+                    "cases": [{}, { "generate": format!("{generator} 1000000") }],
+                }],
+            });
+            let mut buf = std::io::Cursor::new(Vec::new());
+            {
+                let mut zip = zip::ZipWriter::new(&mut buf);
+                let opt = zip::write::SimpleFileOptions::default();
+                zip.start_file("meta.json", opt).unwrap();
+                zip.write_all(meta.to_string().as_bytes()).unwrap();
+                zip.start_file("test-case/0000/STDIN", opt).unwrap();
+                zip.write_all(b"1 2\n").unwrap();
+                zip.start_file("test-case/0000/STDOUT", opt).unwrap();
+                zip.write_all(b"3\n").unwrap();
+                zip.start_file(format!("generators/{generator}"), opt)
+                    .unwrap();
+                zip.write_all(source).unwrap();
+                zip.finish().unwrap();
+            }
+            let part = Part::bytes(buf.into_inner())
+                .file_name("test-case.zip")
+                .mime_type("application/x-zip");
+            MultipartForm::new().add_part("case", part)
+        };
+        let url = format!("/api/problems/{}/test-case", problem.id);
+
+        let response = request
+            .put(&url)
+            .add_cookie(cookie.clone())
+            .multipart(package("gen.py", b"n = int(input())\nprint(n, n)\n"))
+            .await;
+        response.assert_status_bad_request();
+
+        let response = request
+            .post(&format!("/api/problems/{}/solutions", problem.id))
+            .add_cookie(cookie.clone())
+            .json(&json!({
+                "name": "std",
+                "language": 2,
+                "code": "a, b = map(int, input().split())\nprint(a + b)\n",
+                "expected_status": "AC",
+                "is_main": true,
+            }))
+            .await;
+        response.assert_status_ok();
+
+        // cases are generated on upload, a broken generator is rejected there
+        let response = request
+            .put(&url)
+            .add_cookie(cookie)
+            .multipart(package("gen.c", b"int main( {"))
+            .await;
+        response.assert_status_bad_request();
+        assert!(response.text().contains("could not be compiled"));
+        let tasks = problem.tasks(&ctx.db).await.unwrap();
+        assert!(tasks[0].case_meta().iter().all(|c| c.generate.is_none()));
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        assert!(problem.test_case_id.is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn upload_test_case_in_tar_gz() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let problem = add_problem(
+            &ctx,
+            problems::AddParams {
+                tasks: vec![problems::tasks::AddParams {
+                    test_case_count: 1,
+                    score: 100,
+                    time_limit: 1000,
+                    memory_limit: 65535,
+                    cases: vec![],
+                }],
+                ..problem_params(first_admin)
+            },
+        )
+        .await;
+
+        let response = request
+            .put(&format!("/api/problems/{}/test-case", problem.id))
+            .add_cookie(cookie.clone())
+            .multipart(MultipartForm::new().add_part(
+                "case",
+                Part::bytes(b"Rar!".to_vec()).file_name("test-case.rar"),
+            ))
+            .await;
+        response.assert_status_bad_request();
+
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in [
+            ("a-plus-b/test-case/0000/STDIN", "1 2\n"),
+            ("a-plus-b/test-case/0000/STDOUT", "3\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        let test_case = builder.into_inner().unwrap().finish().unwrap();
+        let response = request
+            .put(&format!("/api/problems/{}/test-case", problem.id))
+            .add_cookie(cookie)
+            .multipart(
+                MultipartForm::new().add_part(
+                    "case",
+                    Part::bytes(test_case)
+                        .file_name("test-case.tgz")
+                        .mime_type("application/gzip"),
+                ),
+            )
+            .await;
+        response.assert_status_ok();
+
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        let uploaded: Vec<u8> = ctx
+            .storage
+            .download(problem.test_case_path().unwrap().as_path())
+            .await
+            .unwrap();
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(uploaded)).unwrap();
+        assert!(archive
+            .file_names()
+            .any(|name| name == "test-case/0000/STDIN"));
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn upload_test_case_in_chunks() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let problem = create_problem(&ctx, first_admin).await;
+        let test_case = make_test_case(&ctx.db, &problem).await.unwrap();
+        let (first, second) = test_case.split_at(test_case.len() / 2);
+
+        let url = format!("/api/problems/{}/test-case/uploads", problem.id);
+        let response = request
+            .post(&url)
+            .add_cookie(cookie.clone())
+            .json(&json!({ "size": test_case.len() }))
+            .await;
+        response.assert_status_ok();
+        let upload_id = response.json::<serde_json::Value>()["data"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let url = format!("{url}/{upload_id}");
+
+        let response = request
+            .put(&url)
+            .add_query_param("offset", 0)
+            .add_cookie(cookie.clone())
+            .bytes(axum::body::Bytes::copy_from_slice(first))
+            .await;
+        response.assert_status_ok();
+
+        // resend a chunk at wrong offset
+        let response = request
+            .put(&url)
+            .add_query_param("offset", 0)
+            .add_cookie(cookie.clone())
+            .bytes(axum::body::Bytes::copy_from_slice(first))
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["offset"],
+            first.len()
+        );
+
+        let response = request
+            .post(&format!("{url}/finish"))
+            .add_cookie(cookie.clone())
+            .json(&json!({ "sha256": "" }))
+            .await;
+        response.assert_status_bad_request();
+
+        let response = request
+            .put(&url)
+            .add_query_param("offset", first.len())
+            .add_cookie(cookie.clone())
+            .bytes(axum::body::Bytes::copy_from_slice(second))
+            .await;
+        response.assert_status_ok();
+
+        let checksum = {
+            use sha2::Digest;
+            hex::encode(sha2::Sha256::digest(&test_case))
+        };
+        let response = request
+            .post(&format!("{url}/finish"))
+            .add_cookie(cookie.clone())
+            .json(&json!({ "sha256": checksum }))
+            .await;
+        response.assert_status_ok();
+
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        let uploaded: Vec<u8> = ctx
+            .storage
+            .download(problem.test_case_path().unwrap().as_path())
+            .await
+            .unwrap();
+        assert_eq!(uploaded, test_case);
+
+        // session is removed once finished
+        let response = request.get(&url).add_cookie(cookie).await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn abandoned_upload_is_discarded() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let problem = create_problem(&ctx, first_admin).await;
+
+        let url = format!("/api/problems/{}/test-case/uploads", problem.id);
+        let response = request
+            .post(&url)
+            .add_cookie(cookie.clone())
+            .json(&json!({ "size": 10 }))
+            .await;
+        response.assert_status_ok();
+        let upload_id = response.json::<serde_json::Value>()["data"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        // no chunk received for 2 days
+        let upload = test_case_uploads::Model::find_by_pid(&ctx.db, problem.id, &upload_id)
+            .await
+            .unwrap();
+        let updated_at = upload.updated_at - chrono::Duration::days(2);
+        let mut upload = upload.into_active_model();
+        upload.updated_at = sea_orm::ActiveValue::set(updated_at);
+        upload.update(&ctx.db).await.unwrap();
+
+        // starting another upload cleans up the abandoned one
+        let response = request
+            .post(&url)
+            .add_cookie(cookie.clone())
+            .json(&json!({ "size": 10 }))
+            .await;
+        response.assert_status_ok();
+        let response = request
+            .get(&format!("{url}/{upload_id}"))
+            .add_cookie(cookie)
+            .await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn download_and_rollback_test_case() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let teacher_cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        let problem = create_problem(&ctx, first_admin).await;
+        let test_case = make_test_case(&ctx.db, &problem).await.unwrap();
+
+        let url = format!("/api/problems/{}/test-case", problem.id);
+        let response = request.get(&url).add_cookie(cookie.clone()).await;
+        response.assert_status_not_found();
+
+        for _ in 0..2 {
+            let form = MultipartForm::new().add_part(
+                "case",
+                Part::bytes(test_case.clone()).file_name("test-case.zip"),
+            );
+            let response = request
+                .put(&format!("/api/problems/{}/test-case", problem.id))
+                .add_cookie(cookie.clone())
+                .multipart(form)
+                .await;
+            response.assert_status_ok();
+        }
+
+        let response = request.get(&url).add_cookie(cookie.clone()).await;
+        response.assert_status_ok();
+        assert_eq!(response.as_bytes().to_vec(), test_case);
+        let response = request.get(&url).add_cookie(teacher_cookie).await;
+        response.assert_status_forbidden();
+
+        let response = request
+            .get(&format!("{url}/versions"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let versions = response.json::<serde_json::Value>()["data"].clone();
+        assert_eq!(versions.as_array().unwrap().len(), 2);
+        assert_eq!(versions[0]["current"], true);
+        assert_eq!(versions[1]["current"], false);
+        assert_eq!(versions[1]["uploader"], "first_admin");
+
+        // shrink the task, rollback should restore it
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        problem
+            .replace_tasks(
+                &ctx.db,
+                &[problems::tasks::AddParams {
+                    test_case_count: 1,
+                    score: 100,
+                    time_limit: 1000,
+                    memory_limit: 65535,
+                    cases: vec![],
+                }],
+            )
+            .await
+            .unwrap();
+        let old_id = versions[1]["testCaseId"].as_str().unwrap();
+        let response = request
+            .post(&format!("{url}/versions/{old_id}/rollback"))
+            .add_cookie(cookie.clone())
+            .json(&json!({ "rejudge": false }))
+            .await;
+        response.assert_status_ok();
+
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        assert_eq!(problem.test_case_id.as_deref(), Some(old_id));
+        let tasks = problem.tasks(&ctx.db).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].test_case_count, 2);
+
+        let response = request
+            .post(&format!("{url}/versions/not-a-version/rollback"))
+            .add_cookie(cookie)
+            .json(&json!({}))
+            .await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn edit_and_delete_problem() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let teacher_cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        let problem = add_problem(
+            &ctx,
+            problems::AddParams {
+                status: Some(Visibility::Hidden),
+                ..problem_params(teacher)
+            },
+        )
+        .await
+        .into_active_model()
+        .update_test_case_id(&ctx.db, Some("test-case".to_string()))
+        .await
+        .unwrap();
+        let url = format!("/api/problems/{}", problem.id);
+
+        // only changing limits keeps the test case
+        let response = request
+            .patch(&url)
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({
+                "name": "A + B",
+                "status": 0,
+                "tasks": [{
+                    "test_case_count": 2,
+                    "score": 100,
+                    "time_limit": 2000,
+                    "memory_limit": 65535,
+                }],
+            }))
+            .await;
+        response.assert_status_ok();
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        assert_eq!(problem.name, "A + B");
+        assert_eq!(problem.status, Visibility::Show as i32);
+        assert_eq!(problem.test_case_id.as_deref(), Some("test-case"));
+        assert_eq!(problem.tasks(&ctx.db).await.unwrap()[0].time_limit, 2000);
+
+        // admins can edit others' problems, changing case count drops the test case
+        let response = request
+            .put(&url)
+            .add_cookie(cookie.clone())
+            .json(&json!({
+                "tasks": [{
+                    "test_case_count": 3,
+                    "score": 100,
+                    "time_limit": 2000,
+                    "memory_limit": 65535,
+                }],
+            }))
+            .await;
+        response.assert_status_ok();
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+        assert_eq!(problem.test_case_id, None);
+
+        let student = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let student_cookie = create_cookie(&create_token(&student, &ctx).await);
+        let response = request.delete(&url).add_cookie(student_cookie).await;
+        response.assert_status_forbidden();
+
+        let response = request
+            .delete(&url)
+            .add_cookie(teacher_cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let response = request.get(&url).add_cookie(teacher_cookie.clone()).await;
+        response.assert_status_not_found();
+        let response = request
+            .patch(&url)
+            .add_cookie(teacher_cookie)
+            .json(&json!({ "name": "deleted" }))
+            .await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn filter_problems_by_tags() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        for name in ["dp", "graph"] {
+            tags::Model::create(&ctx.db, name).await.unwrap();
+        }
+
+        let mut payload = create_problem_payload();
+        payload["tags"] = json!(["unknown"]);
+        let response = request
+            .post("/api/problems")
+            .add_cookie(cookie.clone())
+            .json(&payload)
+            .await;
+        response.assert_status_bad_request();
+
+        let mut ids = vec![];
+        for tags in [json!(["dp", "graph"]), json!(["graph"]), json!([])] {
+            let mut payload = create_problem_payload();
+            payload["tags"] = tags;
+            let response = request
+                .post("/api/problems")
+                .add_cookie(cookie.clone())
+                .json(&payload)
+                .await;
+            response.assert_status_ok();
+            ids.push(response.json::<serde_json::Value>()["id"].clone());
+        }
+
+        let list = |query: &'static str| {
+            let request = &request;
+            let cookie = cookie.clone();
+            async move {
+                let response = request
+                    .get("/api/problems")
+                    .add_query_param("page_size", 100)
+                    .add_raw_query_param(query)
+                    .add_cookie(cookie)
+                    .await;
+                response.assert_status_ok();
+                response.json::<serde_json::Value>()["data"]["results"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["id"].clone())
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(list("tags=dp,graph").await, vec![ids[0].clone()]);
+        assert_eq!(
+            list("tags=dp,graph&tag_match=any").await,
+            vec![ids[0].clone(), ids[1].clone()],
+        );
+        assert_eq!(
+            list("tags=graph").await,
+            vec![ids[0].clone(), ids[1].clone()]
+        );
+
+        let response = request
+            .put(&format!("/api/problems/{}/tags", ids[2]))
+            .add_cookie(cookie.clone())
+            .json(&json!({ "tags": ["dp"] }))
+            .await;
+        response.assert_status_ok();
+        let response = request
+            .get(&format!("/api/problems/{}", ids[2]))
+            .add_cookie(cookie)
+            .await;
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["tags"],
+            json!(["dp"])
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn list_problems_by_course() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let admin_cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let student = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let student_cookie = create_cookie(&create_token(&student, &ctx).await);
+        courses::Model::find_by_name(&ctx.db, "course1")
+            .await
+            .unwrap()
+            .add_member(&ctx.db, student.id)
+            .await
+            .unwrap();
+
+        let mut ids = vec![];
+        for (courses, status) in [
+            (json!(["course1"]), 0),
+            (json!(["course1"]), 1),
+            (json!([]), 0),
+        ] {
+            let mut payload = create_problem_payload();
+            payload["courses"] = courses;
+            payload["status"] = json!(status);
+            let response = request
+                .post("/api/problems")
+                .add_cookie(admin_cookie.clone())
+                .json(&payload)
+                .await;
+            response.assert_status_ok();
+            ids.push(response.json::<serde_json::Value>()["id"].clone());
+        }
+
+        let list = |query: &'static str, cookie| {
+            let request = &request;
+            async move {
+                let response = request
+                    .get("/api/problems")
+                    .add_query_param("page_size", 100)
+                    .add_raw_query_param(query)
+                    .add_cookie(cookie)
+                    .await;
+                response.assert_status_ok();
+                response.json::<serde_json::Value>()["data"]["results"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["id"].clone())
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(list("", admin_cookie.clone()).await, ids);
+        assert_eq!(
+            list("course=course1", admin_cookie.clone()).await,
+            vec![ids[0].clone(), ids[1].clone()],
+        );
+        // hidden problems and problems outside student's courses are not listed
+        assert_eq!(list("", student_cookie.clone()).await, vec![ids[0].clone()]);
+
+        let response = request
+            .get(&format!("/api/problems/{}", ids[2]))
+            .add_cookie(student_cookie)
+            .await;
+        response.assert_status_forbidden();
+
+        let mut payload = create_problem_payload();
+        payload["courses"] = json!(["no-such-course"]);
+        let response = request
+            .post("/api/problems")
+            .add_cookie(admin_cookie)
+            .json(&payload)
+            .await;
+        response.assert_status_bad_request();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn hidden_problem_is_only_visible_to_managers() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        // teacher of course1
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let teacher_cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        let student = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let student_cookie = create_cookie(&create_token(&student, &ctx).await);
+        courses::Model::find_by_name(&ctx.db, "course1")
+            .await
+            .unwrap()
+            .add_member(&ctx.db, student.id)
+            .await
+            .unwrap();
+
+        let problem = add_problem(
+            &ctx,
+            problems::AddParams {
+                courses: vec!["course1".to_string()],
+                status: Some(Visibility::Hidden),
+                ..problem_params(first_admin)
+            },
+        )
+        .await
+        .into_active_model()
+        .update_test_case_id(&ctx.db, Some("test-case".to_string()))
+        .await
+        .unwrap();
+        let url = format!("/api/problems/{}", problem.id);
+
+        let response = request.get(&url).add_cookie(student_cookie.clone()).await;
+        response.assert_status_forbidden();
+        let response = request
+            .get("/api/problems")
+            .add_cookie(student_cookie.clone())
+            .await;
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["results"],
+            json!([])
+        );
+
+        let response = request.get(&url).add_cookie(teacher_cookie.clone()).await;
+        response.assert_status_ok();
+        let data = response.json::<serde_json::Value>()["data"].clone();
+        assert_eq!(data["testCaseId"], "test-case");
+        assert_eq!(data["testCase"][0]["cases"].as_array().unwrap().len(), 2);
+
+        // course teacher can also manage it
+        let response = request
+            .patch(&url)
+            .add_cookie(teacher_cookie)
+            .json(&json!({ "status": 0 }))
+            .await;
+        response.assert_status_ok();
+
+        let response = request.get(&url).add_cookie(student_cookie).await;
+        response.assert_status_ok();
+        let data = response.json::<serde_json::Value>()["data"].clone();
+        assert!(data.get("testCaseId").is_none());
+        assert!(data["testCase"][0].get("cases").is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn paginate_and_sort_problems() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+
+        let mut ids = vec![];
+        for name in ["b", "c", "a"] {
+            let mut payload = create_problem_payload();
+            payload["name"] = json!(name);
+            let response = request
+                .post("/api/problems")
+                .add_cookie(cookie.clone())
+                .json(&payload)
+                .await;
+            response.assert_status_ok();
+            ids.push(response.json::<serde_json::Value>()["id"].clone());
+        }
+
+        let response = request
+            .get("/api/problems")
+            .add_query_param("page", 2)
+            .add_query_param("page_size", 2)
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let data = response.json::<serde_json::Value>()["data"].clone();
+        assert_eq!(data["total_items"], 3);
+        assert_eq!(data["pagination"]["total_pages"], 2);
+        assert_eq!(data["results"].as_array().unwrap().len(), 1);
+        assert_eq!(data["results"][0]["id"], ids[2]);
+
+        let response = request
+            .get("/api/problems")
+            .add_query_param("page_size", 2)
+            .add_query_param("sort_by", "name")
+            .add_query_param("order", "desc")
+            .add_cookie(cookie)
+            .await;
+        response.assert_status_ok();
+        let names = response.json::<serde_json::Value>()["data"]["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![json!("c"), json!("b")]);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn problem_stats() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let response = request
+            .post("/api/problems")
+            .add_cookie(cookie.clone())
+            .json(&create_problem_payload())
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+        let problem = problems::Model::find_by_id(&ctx.db, i32::try_from(problem_id).unwrap())
+            .await
+            .unwrap();
+
+        for (username, status, duration) in [
+            ("user1", "AC", 150),
+            ("user1", "WA", 10),
+            ("teacher1", "AC", 950),
+        ] {
+            let user = users::Model::find_by_username(&ctx.db, username)
+                .await
+                .unwrap();
+            let submission = submissions::Model::add(
+                &ctx.db,
+                &submissions::AddParams {
+                    user: user.id,
+                    problem: problem.id,
+                    timestamp: chrono::Utc::now().naive_utc(),
+                    language: submissions::Language::C,
+                },
+            )
+            .await
+            .unwrap();
+            let results = (0..2)
+                .map(|case_id| submissions::JudgeResult {
+                    status: status.to_string(),
+                    duration,
+                    mem_usage: 1024,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    task_id: 0,
+                    case_id,
+                    stdout_truncated: false,
+                    stderr_truncated: false,
+                    full_output_stored: false,
+                })
+                .collect();
+            submission
+                .into_active_model()
+                .update_sandbox_result(&ctx.db, &problem, vec![results])
+                .await
+                .unwrap();
+        }
+
+        let response = request
+            .get("/api/problems")
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let item = response.json::<serde_json::Value>()["data"]["results"][0].clone();
+        assert_eq!(item["ACUser"], 2);
+        assert_eq!(item["submitter"], 2);
+        assert_eq!(item["submitCount"], 3);
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let data = response.json::<serde_json::Value>()["data"].clone();
+        assert_eq!(data["submitCount"], 3);
+        assert_eq!(data["highScore"], 100);
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/stats"))
+            .add_cookie(cookie)
+            .await;
+        response.assert_status_ok();
+        let data = response.json::<serde_json::Value>()["data"].clone();
+        let mut verdicts = data["verdicts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| (v["status"].as_i64().unwrap(), v["count"].as_i64().unwrap()))
+            .collect::<Vec<_>>();
+        verdicts.sort_unstable();
+        assert_eq!(verdicts, vec![(0, 2), (1, 1)]);
+        let histogram = data["timeHistogram"].as_array().unwrap();
+        assert_eq!(histogram.len(), 10);
+        assert_eq!(
+            histogram[1],
+            json!({ "lower": 100, "upper": 200, "count": 1 })
+        );
+        assert_eq!(histogram[9]["count"], 1);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn search_problems() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        tags::Model::create(&ctx.db, "graph").await.unwrap();
+
+        let mut ids = vec![];
+        for (name, description, tags) in [
+            (
+                "Shortest Path",
+                "find the path with dijkstra",
+                json!(["graph"]),
+            ),
+            ("Knapsack", "classic dynamic programming", json!([])),
+            ("Dijkstra", "a graph problem", json!([])),
+        ] {
+            let mut payload = create_problem_payload();
+            payload["name"] = json!(name);
+            payload["description"]["description"] = json!(description);
+            payload["tags"] = tags;
+            let response = request
+                .post("/api/problems")
+                .add_cookie(cookie.clone())
+                .json(&payload)
+                .await;
+            response.assert_status_ok();
+            ids.push(response.json::<serde_json::Value>()["id"].clone());
+        }
+
+        let list = |query: &'static str| {
+            let request = &request;
+            let cookie = cookie.clone();
+            async move {
+                let response = request
+                    .get("/api/problems")
+                    .add_raw_query_param(query)
+                    .add_cookie(cookie)
+                    .await;
+                response.assert_status_ok();
+                response.json::<serde_json::Value>()["data"]["results"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["id"].clone())
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(list("name=PATH").await, vec![ids[0].clone()]);
+        assert_eq!(list("search=knap").await, vec![ids[1].clone()]);
+        // name matches rank first
+        assert_eq!(
+            list("search=dijkstra").await,
+            vec![ids[2].clone(), ids[0].clone()]
+        );
+        assert_eq!(
+            list("search=dijkstra&tags=graph").await,
+            vec![ids[0].clone()]
+        );
+        assert_eq!(list("search=100%25").await, Vec::<serde_json::Value>::new());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn import_and_export_kattis_package() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            let opt = zip::write::SimpleFileOptions::default();
+            for (name, content) in [
+                ("problem.yaml", "name: A + B\nlimits:\n  time_limit: 2\n"),
+                (
+                    "problem_statement/problem.en.md",
+                    "Add two numbers.\n\n## Input\n\nA and B.\n\n## Output\n\nA + B.\n",
+                ),
+                ("data/sample/1.in", "1 2\n"),
+                ("data/sample/1.ans", "3\n"),
+                ("data/secret/1.in", "2 3\n"),
+                ("data/secret/1.ans", "5\n"),
+            ] {
+                zip.start_file(name, opt).unwrap();
+                zip.write_all(content.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        let package = buf.into_inner();
+        let form = || {
+            MultipartForm::new().add_part(
+                "package",
+                Part::bytes(package.clone())
+                    .file_name("a-plus-b.zip")
+                    .mime_type("application/x-zip"),
+            )
+        };
+
+        let user1 = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let response = request
+            .post("/api/problems/import")
+            .add_cookie(create_cookie(&create_token(&user1, &ctx).await))
+            .multipart(form())
+            .await;
+        response.assert_status_forbidden();
+
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        // teachers can only create problems in courses they teach
+        let response = request
+            .post("/api/problems/import")
+            .add_cookie(cookie.clone())
+            .multipart(form())
+            .await;
+        response.assert_status_forbidden();
+        let response = request
+            .post("/api/problems/import")
+            .add_query_param("courses", "course1")
+            .add_cookie(cookie.clone())
+            .multipart(form())
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+        let problem = problems::Model::find_by_id(&ctx.db, i32::try_from(problem_id).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(problem.name, "A + B");
+        assert!(problem.test_case_id.is_some());
+        let tasks = problem.tasks(&ctx.db).await.unwrap();
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|t| (t.test_case_count, t.score, t.time_limit))
+                .collect::<Vec<_>>(),
+            vec![(1, 0, 2000), (1, 100, 2000)]
+        );
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/export"))
+            .add_cookie(cookie)
+            .await;
+        response.assert_status_ok();
+        let archive =
+            zip::ZipArchive::new(std::io::Cursor::new(response.as_bytes().to_vec())).unwrap();
+        let mut names = archive.file_names().collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                "data/sample/0000.ans",
+                "data/sample/0000.in",
+                "data/secret/task01/0100.ans",
+                "data/secret/task01/0100.in",
+                "data/secret/task01/testdata.yaml",
+                "problem.yaml",
+                "problem_statement/problem.en.md",
+            ]
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn export_and_import_problem_bundle() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&admin, &ctx).await);
+        let original = create_problem(&ctx, admin.clone()).await;
+
+        let response = request
+            .get(&format!("/api/problems/{}/bundle", original.id))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let bundle = response.as_bytes().to_vec();
+        let form = MultipartForm::new().add_part(
+            "bundle",
+            Part::bytes(bundle)
+                .file_name("problem-1.bundle.zip")
+                .mime_type("application/x-zip"),
+        );
+
+        let response = request
+            .post("/api/problems/bundle")
+            .add_cookie(cookie)
+            .multipart(form)
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+        let imported = problems::Model::find_by_id(&ctx.db, i32::try_from(problem_id).unwrap())
+            .await
+            .unwrap();
+        assert_ne!(imported.id, original.id);
+        assert_eq!(imported.name, original.name);
+        assert_eq!(imported.owner_id, admin.id);
+        assert_eq!(imported.status, Visibility::Hidden as i32);
+        assert_eq!(
+            imported.tag_names(&ctx.db).await.unwrap(),
+            original.tag_names(&ctx.db).await.unwrap()
+        );
+        let task_params = |tasks: Vec<problems::tasks::Model>| {
+            tasks
+                .iter()
+                .map(|t| (t.test_case_count, t.score, t.time_limit, t.memory_limit))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            task_params(imported.tasks(&ctx.db).await.unwrap()),
+            task_params(original.tasks(&ctx.db).await.unwrap())
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn render_problem_description() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let mut payload = create_problem_payload();
+        payload["description"]["description"] = json!("Compute $A + B$.<script>alert(1)</script>");
+        let response = request
+            .post("/api/problems")
+            .add_cookie(cookie.clone())
+            .json(&payload)
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/description/html"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let data = &response.json::<serde_json::Value>()["data"];
+        assert_eq!(
+            data["description"],
+            "<p>Compute <span class=\"math math-inline\">A + B</span>.</p>\n"
+        );
+        assert_eq!(
+            data["input"],
+            "<p>two space-separated number as A &amp; B</p>\n"
+        );
+        assert_eq!(data["sampleInput"], json!(["1 2"]));
+
+        let response = request
+            .post("/api/problems/render")
+            .add_cookie(cookie)
+            .json(&json!({ "markdown": "[x](javascript:alert(1)) **y**" }))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["html"],
+            "<p><a rel=\"noopener noreferrer\">x</a> <strong>y</strong></p>\n"
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn problem_attachments() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let response = request
+            .post("/api/problems")
+            .add_cookie(cookie.clone())
+            .json(&create_problem_payload())
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+        let form = |name: &str| {
+            MultipartForm::new().add_part(
+                "file",
+                Part::bytes(b"\x89PNG fake image".to_vec()).file_name(name),
+            )
+        };
+
+        let response = request
+            .post(&format!("/api/problems/{problem_id}/attachments"))
+            .add_cookie(cookie.clone())
+            .multipart(form("figure.png"))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["url"],
+            format!("/api/problems/{problem_id}/attachments/figure.png")
+        );
+
+        let response = request
+            .post(&format!("/api/problems/{problem_id}/attachments"))
+            .add_cookie(cookie.clone())
+            .multipart(form("figure.png"))
+            .await;
+        response.assert_status(axum::http::StatusCode::CONFLICT);
+        let response = request
+            .post(&format!("/api/problems/{problem_id}/attachments"))
+            .add_cookie(cookie.clone())
+            .multipart(form("figure.svg"))
+            .await;
+        response.assert_status_bad_request();
+        let too_large = vec![0; 10 * 1024 * 1024 + 1];
+        let response = request
+            .post(&format!("/api/problems/{problem_id}/attachments"))
+            .add_cookie(cookie.clone())
+            .multipart(
+                MultipartForm::new()
+                    .add_part("file", Part::bytes(too_large).file_name("large.png")),
+            )
+            .await;
+        response.assert_status_bad_request();
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/attachments"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let data = response.json::<serde_json::Value>()["data"].clone();
+        assert_eq!(data.as_array().unwrap().len(), 1);
+        assert_eq!(data[0]["name"], "figure.png");
+        assert_eq!(data[0]["contentType"], "image/png");
+        assert_eq!(data[0]["size"], 15);
+
+        let response = request
+            .get(&format!(
+                "/api/problems/{problem_id}/attachments/figure.png"
+            ))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header("content-type"), "image/png");
+        assert_eq!(response.as_bytes().as_ref(), b"\x89PNG fake image");
+
+        // hidden problem isn't visible to students, nor its attachments
+        let user1 = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let response = request
+            .get(&format!(
+                "/api/problems/{problem_id}/attachments/figure.png"
+            ))
+            .add_cookie(create_cookie(&create_token(&user1, &ctx).await))
+            .await;
+        response.assert_status_forbidden();
+
+        let response = request
+            .delete(&format!(
+                "/api/problems/{problem_id}/attachments/figure.png"
+            ))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let response = request
+            .get(&format!(
+                "/api/problems/{problem_id}/attachments/figure.png"
+            ))
+            .add_cookie(cookie)
+            .await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn multilingual_descriptions() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let response = request
+            .post("/api/problems")
+            .add_cookie(cookie.clone())
+            .json(&create_problem_payload())
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+
+        let mut translation = create_problem_payload()["description"].clone();
+        translation["input"] = json!("兩個以空白分隔的數字 A 與 B");
+        let response = request
+            .put(&format!("/api/problems/{problem_id}/descriptions/zh_TW"))
+            .add_cookie(cookie.clone())
+            .json(&translation)
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["locales"],
+            json!(["en", "zh-tw"])
+        );
+
+        for (accept_language, query, locale) in [
+            ("zh-TW,zh;q=0.9,en;q=0.8", "", "zh-tw"),
+            ("zh", "", "zh-tw"),
+            ("ja", "", "en"),
+            ("zh-TW", "lang=en", "en"),
+        ] {
+            let response = request
+                .get(&format!("/api/problems/{problem_id}"))
+                .add_raw_query_param(query)
+                .add_header(
+                    axum::http::header::ACCEPT_LANGUAGE,
+                    axum::http::HeaderValue::from_static(accept_language),
+                )
+                .add_cookie(cookie.clone())
+                .await;
+            response.assert_status_ok();
+            let data = &response.json::<serde_json::Value>()["data"];
+            assert_eq!(data["locale"], locale, "{accept_language} {query}");
+            assert_eq!(data["locales"], json!(["en", "zh-tw"]));
+            let expected_input = if locale == "en" {
+                "two space-separated number as A & B"
+            } else {
+                "兩個以空白分隔的數字 A 與 B"
+            };
+            assert_eq!(data["description"]["input"], expected_input);
+        }
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/description/html"))
+            .add_query_param("lang", "zh-tw")
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let data = &response.json::<serde_json::Value>()["data"];
+        assert_eq!(data["locale"], "zh-tw");
+        assert_eq!(data["input"], "<p>兩個以空白分隔的數字 A 與 B</p>\n");
+
+        let response = request
+            .delete(&format!("/api/problems/{problem_id}/descriptions/en"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_bad_request();
+        let response = request
+            .delete(&format!("/api/problems/{problem_id}/descriptions/zh-tw"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let problem = problems::Model::find_by_id(&ctx.db, i32::try_from(problem_id).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(problem.locales(&ctx.db).await.unwrap(), vec!["en"]);
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn problem_revisions() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let response = request
+            .post("/api/problems")
+            .add_cookie(cookie.clone())
+            .json(&create_problem_payload())
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+
+        let mut description = create_problem_payload()["description"].clone();
+        description["input"] = json!("two space-separated integers A and B");
+        let response = request
+            .patch(&format!("/api/problems/{problem_id}"))
+            .add_cookie(cookie.clone())
+            .json(&json!({ "name": "A plus B", "description": description }))
+            .await;
+        response.assert_status_ok();
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/revisions"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let revisions = response.json::<serde_json::Value>()["data"].clone();
+        assert_eq!(revisions.as_array().unwrap().len(), 2);
+        assert_eq!(revisions[0]["number"], 2);
+        assert_eq!(revisions[0]["author"], "first_admin");
+
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/revisions/diff"))
+            .add_query_param("from", 1)
+            .add_query_param("to", 2)
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let changes = response.json::<serde_json::Value>()["data"]["changes"].clone();
+        let fields = changes
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["field"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec!["description.input", "name"]);
+        assert!(changes[0]["diff"]
+            .as_str()
+            .unwrap()
+            .contains("+two space-separated integers A and B"));
+
+        let response = request
+            .post(&format!("/api/problems/{problem_id}/revisions/1/restore"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>()["data"]["number"], 3);
+
+        let problem = problems::Model::find_by_id(&ctx.db, i32::try_from(problem_id).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(problem.name, "A + B");
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/revisions/diff"))
+            .add_query_param("from", 1)
+            .add_query_param("to", 3)
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["changes"],
+            json!([])
+        );
+
+        let user = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let response = request
+            .get(&format!("/api/problems/{problem_id}/revisions"))
+            .add_cookie(create_cookie(&create_token(&user, &ctx).await))
+            .await;
+        response.assert_status_forbidden();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn problem_collaborators() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let owner_cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let teacher_cookie = create_cookie(&create_token(&teacher, &ctx).await);
+        // hidden problem
+        let response = request
+            .post("/api/problems")
+            .add_cookie(owner_cookie.clone())
+            .json(&create_problem_payload())
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+        let url = format!("/api/problems/{problem_id}");
+        let collaborator_url = |username: &str| format!("{url}/collaborators/{username}");
+
+        let response = request.get(&url).add_cookie(teacher_cookie.clone()).await;
+        response.assert_status_forbidden();
+
+        let response = request
+            .put(&collaborator_url("teacher1"))
+            .add_cookie(owner_cookie.clone())
+            .json(&json!({ "role": "viewer" }))
+            .await;
+        response.assert_status_ok();
+        let response = request.get(&url).add_cookie(teacher_cookie.clone()).await;
+        response.assert_status_ok();
+        let response = request
+            .patch(&url)
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({ "name": "A plus B" }))
+            .await;
+        response.assert_status_forbidden();
+
+        let response = request
+            .put(&collaborator_url("teacher1"))
+            .add_cookie(owner_cookie.clone())
+            .json(&json!({ "role": "editor" }))
+            .await;
+        response.assert_status_ok();
+        let response = request
+            .patch(&url)
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({ "name": "A plus B" }))
+            .await;
+        response.assert_status_ok();
+        let response = request
+            .put(&format!("{url}/validator"))
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({
+                "language": 2,
+                "code": "import sys\nassert len(sys.stdin.read().split()) == 2\n",
+            }))
+            .await;
+        response.assert_status_ok();
+
+        // editors can't manage collaborators
+        let response = request
+            .put(&collaborator_url("user1"))
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({ "role": "editor" }))
+            .await;
+        response.assert_status_forbidden();
+        let response = request
+            .put(&collaborator_url("first_admin"))
+            .add_cookie(owner_cookie.clone())
+            .json(&json!({ "role": "editor" }))
+            .await;
+        response.assert_status_bad_request();
+
+        let response = request
+            .get(&format!("{url}/collaborators"))
+            .add_cookie(teacher_cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let data = &response.json::<serde_json::Value>()["data"];
+        assert_eq!(data[0]["username"], "teacher1");
+        assert_eq!(data[0]["role"], "editor");
+
+        let response = request
+            .delete(&collaborator_url("teacher1"))
+            .add_cookie(owner_cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let response = request.get(&url).add_cookie(teacher_cookie.clone()).await;
+        response.assert_status_forbidden();
+        let response = request
+            .delete(&collaborator_url("teacher1"))
+            .add_cookie(owner_cookie)
+            .await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn problem_templates() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let cookie = create_cookie(&create_token(&first_admin, &ctx).await);
+        let response = request
+            .post("/api/problems")
+            .add_cookie(cookie.clone())
+            .json(&create_problem_payload())
+            .await;
+        response.assert_status_ok();
+        let problem_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+        let url = format!("/api/problems/{problem_id}");
+        let template = json!({ "code": "a, b = map(int, input().split())\n" });
+
+        let user = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let response = request
+            .put(&format!("{url}/templates/2"))
+            .add_cookie(create_cookie(&create_token(&user, &ctx).await))
+            .json(&template)
+            .await;
+        response.assert_status_forbidden();
+        let response = request
+            .put(&format!("{url}/templates/9"))
+            .add_cookie(cookie.clone())
+            .json(&template)
+            .await;
+        response.assert_status_bad_request();
+
+        let response = request
+            .put(&format!("{url}/templates/2"))
+            .add_cookie(cookie.clone())
+            .json(&template)
+            .await;
+        response.assert_status_ok();
+        let response = request.get(&url).add_cookie(cookie.clone()).await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["templates"],
+            json!([{ "language": 2, "code": template["code"] }])
+        );
+
+        let response = request
+            .delete(&format!("{url}/templates/2"))
+            .add_cookie(cookie.clone())
+            .await;
+        response.assert_status_ok();
+        let response = request.get(&url).add_cookie(cookie.clone()).await;
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"]["templates"],
+            json!([])
+        );
+        let response = request
+            .delete(&format!("{url}/templates/2"))
+            .add_cookie(cookie)
+            .await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
@@ -14,8 +14,9 @@ use normal_oj::{
     app::App,
     models::{
         problems::{self, Type, Visibility},
-        users,
+        submissions, users,
     },
+    workers::submission::test_case_dir,
 };
 
 macro_rules! configure_insta {
@@ -25,6 +26,23 @@ macro_rules! configure_insta {
 }
 
 async fn create_problem(ctx: &AppContext) -> problems::Model {
+    create_problem_with_tasks(
+        ctx,
+        vec![problems::tasks::AddParams {
+            test_case_count: 2,
+            score: 100,
+            time_limit: 1000,
+            memory_limit: 65535,
+            cases: vec![],
+        }],
+    )
+    .await
+}
+
+async fn create_problem_with_tasks(
+    ctx: &AppContext,
+    tasks: Vec<problems::tasks::AddParams>,
+) -> problems::Model {
     let first_admin = users::Model::find_by_username(&ctx.db, "first_admin")
         .await
         .unwrap();
@@ -47,12 +65,8 @@ async fn create_problem(ctx: &AppContext) -> problems::Model {
             r#type: Some(Type::Normal),
             allowed_language: None,
             quota: None,
-            tasks: vec![problems::tasks::AddParams {
-                test_case_count: 2,
-                score: 100,
-                time_limit: 1000,
-                memory_limit: 65535,
-            }],
+            default_locale: None,
+            tasks,
         },
     )
     .await
@@ -107,6 +121,41 @@ async fn create_submission() {
     .await;
 }
 
+#[tokio::test]
+#[serial]
+async fn list_submissions_with_legacy_offset_and_count() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let problem = create_problem(&ctx).await;
+
+        for _ in 0..3 {
+            let response = request
+                .post("/api/submissions")
+                .add_cookie(create_cookie(&user.token))
+                .json(&create_submission_payload(problem.id))
+                .await;
+            response.assert_status_ok();
+        }
+
+        let response = request
+            .get("/api/submissions")
+            .add_cookie(create_cookie(&user.token))
+            .add_query_param("offset", 2)
+            .add_query_param("count", 2)
+            .await;
+        response.assert_status_ok();
+        let body = response.json::<serde_json::Value>();
+        assert_eq!(body["data"]["pagination"]["page"], 2);
+        assert_eq!(body["data"]["pagination"]["page_size"], 2);
+        assert_eq!(body["data"]["results"].as_array().unwrap().len(), 1);
+    })
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn upload_submission_code() {
@@ -181,3 +230,242 @@ async fn get_nonexisting_submission_returns_404() {
     })
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn only_submitter_and_problem_manager_can_get_full_output() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let problem = create_problem(&ctx).await;
+
+        let cookie = create_cookie(&user.token);
+        let response = request
+            .post("/api/submissions")
+            .add_cookie(cookie)
+            .json(&create_submission_payload(problem.id))
+            .await;
+        response.assert_status_ok();
+        let submission_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+        let submission = submissions::Model::find_by_id(&ctx.db, submission_id.try_into().unwrap())
+            .await
+            .unwrap();
+        for (stream, content) in [("stdout", "full stdout"), ("stderr", "full stderr")] {
+            ctx.storage
+                .upload(
+                    submission.output_path(0, 1, stream).as_path(),
+                    &Bytes::from(content),
+                )
+                .await
+                .unwrap();
+        }
+        let url = format!("/api/submissions/{submission_id}/output/0/1");
+
+        let response = request
+            .get(&url)
+            .add_cookie(create_cookie(&user.token))
+            .await;
+        response.assert_status_ok();
+        let output = response.json::<serde_json::Value>();
+        assert_eq!(output["data"]["stdout"], "full stdout");
+        assert_eq!(output["data"]["stderr"], "full stderr");
+
+        // problem owner can manage it
+        let owner = users::Model::find_by_username(&ctx.db, "first_admin")
+            .await
+            .unwrap();
+        let token = create_token(&owner, &ctx).await;
+        let response = request.get(&url).add_cookie(create_cookie(&token)).await;
+        response.assert_status_ok();
+
+        // being a teacher is not enough
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let token = create_token(&teacher, &ctx).await;
+        let response = request.get(&url).add_cookie(create_cookie(&token)).await;
+        response.assert_status_forbidden();
+
+        let other = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let token = create_token(&other, &ctx).await;
+        let response = request.get(&url).add_cookie(create_cookie(&token)).await;
+        response.assert_status_forbidden();
+
+        // output of this case is not stored
+        let response = request
+            .get(&format!("/api/submissions/{submission_id}/output/0/0"))
+            .add_cookie(create_cookie(&user.token))
+            .await;
+        response.assert_status_not_found();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn submission_detail_shows_io_of_public_cases_only() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let problem = create_problem_with_tasks(
+            &ctx,
+            vec![problems::tasks::AddParams {
+                test_case_count: 2,
+                score: 100,
+                time_limit: 1000,
+                memory_limit: 65535,
+                cases: vec![problems::tasks::CaseMeta {
+                    public: true,
+                    ..Default::default()
+                }],
+            }],
+        )
+        .await;
+        upload_test_case(&ctx, &problem).await;
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+
+        let submission = submissions::Model::add(
+            &ctx.db,
+            &submissions::AddParams {
+                user: user.user.id,
+                problem: problem.id,
+                timestamp: chrono::Utc::now().naive_utc(),
+                language: submissions::Language::C,
+            },
+        )
+        .await
+        .unwrap();
+        let results = (0..2)
+            .map(|case_id| submissions::JudgeResult {
+                status: "WA".to_string(),
+                duration: 10,
+                mem_usage: 1024,
+                stdout: "4\n".to_string(),
+                stderr: String::new(),
+                task_id: 0,
+                case_id,
+                stdout_truncated: false,
+                stderr_truncated: false,
+                full_output_stored: false,
+            })
+            .collect();
+        let submission = submission
+            .into_active_model()
+            .update_sandbox_result(&ctx.db, &problem, vec![results])
+            .await
+            .unwrap();
+
+        let response = request
+            .get(&format!("/api/submissions/{}", submission.id))
+            .add_cookie(create_cookie(&user.token))
+            .await;
+        response.assert_status_ok();
+        let cases = &response.json::<serde_json::Value>()["data"]["tasks"][0]["cases"];
+        assert_eq!(
+            cases[0]["io"],
+            json!({
+                "input": "1 2\n",
+                "expectedOutput": "3\n",
+                "output": "4\n",
+                "outputTruncated": false,
+                "diff": {
+                    "line": 1,
+                    "expected": "3",
+                    "actual": "4",
+                    "truncated": false,
+                },
+            })
+        );
+        assert!(cases[1].get("io").is_none());
+    })
+    .await;
+}
+
+#[tokio::test]
+#[serial]
+async fn submission_detail_reads_extracted_public_cases() {
+    configure_insta!();
+
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let user = prepare_data::init_user_login(&request, &ctx).await;
+        let public = problems::tasks::CaseMeta {
+            public: true,
+            ..Default::default()
+        };
+        let problem = create_problem_with_tasks(
+            &ctx,
+            vec![problems::tasks::AddParams {
+                test_case_count: 2,
+                score: 100,
+                time_limit: 1000,
+                memory_limit: 65535,
+                cases: vec![public.clone(), public],
+            }],
+        )
+        .await;
+        upload_test_case(&ctx, &problem).await;
+        let problem = problems::Model::find_by_id(&ctx.db, problem.id)
+            .await
+            .unwrap();
+
+        // extracted for judging, but the second case is gone
+        let dir = test_case_dir(&problem).unwrap().join("test-case/0000");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("STDIN"), "5 6\n").unwrap();
+        std::fs::write(dir.join("STDOUT"), "11\n").unwrap();
+
+        let submission = submissions::Model::add(
+            &ctx.db,
+            &submissions::AddParams {
+                user: user.user.id,
+                problem: problem.id,
+                timestamp: chrono::Utc::now().naive_utc(),
+                language: submissions::Language::C,
+            },
+        )
+        .await
+        .unwrap();
+        let results = (0..2)
+            .map(|case_id| submissions::JudgeResult {
+                status: "WA".to_string(),
+                duration: 10,
+                mem_usage: 1024,
+                stdout: "1".to_string(),
+                stderr: String::new(),
+                task_id: 0,
+                case_id,
+                stdout_truncated: true,
+                stderr_truncated: false,
+                full_output_stored: false,
+            })
+            .collect();
+        let submission = submission
+            .into_active_model()
+            .update_sandbox_result(&ctx.db, &problem, vec![results])
+            .await
+            .unwrap();
+
+        let response = request
+            .get(&format!("/api/submissions/{}", submission.id))
+            .add_cookie(create_cookie(&user.token))
+            .await;
+        response.assert_status_ok();
+        let cases = &response.json::<serde_json::Value>()["data"]["tasks"][0]["cases"];
+        assert_eq!(cases[0]["io"]["input"], "5 6\n");
+        assert_eq!(cases[0]["io"]["diff"]["truncated"], true);
+        assert!(cases[1].get("io").is_none());
+    })
+    .await;
+}
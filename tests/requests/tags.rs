@@ -0,0 +1,74 @@
+use super::create_token;
+use crate::requests::create_cookie;
+use loco_rs::testing;
+use normal_oj::{app::App, models::users};
+use serde_json::json;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn only_teacher_can_manage_tags() {
+    testing::request::<App, _, _>(|request, ctx| async move {
+        testing::seed::<App>(&ctx.db).await.unwrap();
+
+        let student = users::Model::find_by_username(&ctx.db, "user1")
+            .await
+            .unwrap();
+        let student_cookie = create_cookie(&create_token(&student, &ctx).await);
+        let teacher = users::Model::find_by_username(&ctx.db, "teacher1")
+            .await
+            .unwrap();
+        let teacher_cookie = create_cookie(&create_token(&teacher, &ctx).await);
+
+        let response = request
+            .post("/api/tags")
+            .add_cookie(student_cookie.clone())
+            .json(&json!({ "name": "dp" }))
+            .await;
+        response.assert_status_forbidden();
+
+        let response = request
+            .post("/api/tags")
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({ "name": "dp" }))
+            .await;
+        response.assert_status_ok();
+        let tag_id = response.json::<serde_json::Value>()["id"].as_i64().unwrap();
+
+        let response = request
+            .post("/api/tags")
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({ "name": "a,b" }))
+            .await;
+        response.assert_status_bad_request();
+
+        let response = request
+            .put(&format!("/api/tags/{tag_id}"))
+            .add_cookie(teacher_cookie.clone())
+            .json(&json!({ "name": "dynamic programming" }))
+            .await;
+        response.assert_status_ok();
+
+        let response = request.get("/api/tags").await;
+        response.assert_status_ok();
+        assert_eq!(
+            response.json::<serde_json::Value>()["data"],
+            json!([{ "id": tag_id, "name": "dynamic programming" }]),
+        );
+
+        let response = request
+            .delete(&format!("/api/tags/{tag_id}"))
+            .add_cookie(student_cookie)
+            .await;
+        response.assert_status_forbidden();
+        let response = request
+            .delete(&format!("/api/tags/{tag_id}"))
+            .add_cookie(teacher_cookie)
+            .await;
+        response.assert_status_ok();
+
+        let response = request.get("/api/tags").await;
+        assert_eq!(response.json::<serde_json::Value>()["data"], json!([]));
+    })
+    .await;
+}
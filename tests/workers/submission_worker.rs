@@ -42,11 +42,13 @@ async fn test_run_submission_worker_worker() {
             r#type: Some(Type::Normal),
             allowed_language: None,
             quota: None,
+            default_locale: None,
             tasks: vec![problems::tasks::AddParams {
                 test_case_count: 2,
                 score: 100,
                 time_limit: 1000,
                 memory_limit: 536_870_912,
+                cases: vec![],
             }],
         },
     )
@@ -105,6 +107,7 @@ int main()
         &boot.app_context,
         SubmissionWorkerArgs {
             submission_id: subm.id,
+            revision_id: None,
         }
     )
     .await